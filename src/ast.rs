@@ -1,6 +1,16 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fmt::Debug;
 
+use crate::error::Location;
+
+/// Custom properties set through annotations e.g. `@owner("team")`
+pub type Properties = Map<String, Value>;
+
+/// Where a field was parsed, to report the errors found while linking it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Position(pub Option<Location>);
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum HasDefault<T> {
     /// Default set to literal value (HasDefault::Default(Some(1))), default is set to null HasDefault::Default(None)
@@ -21,60 +31,109 @@ impl<T> HasDefault<T> {
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum RawField {
-    /// Name, fields, namespace, docstring
+    /// Name, fields, namespace, docstring, properties
     Protocol(
         Option<String>,
         Vec<RawField>,
         Option<String>,
         Option<String>,
+        Properties,
     ),
-    /// Name, default, docstring
-    Int(Option<String>, HasDefault<i32>, Option<String>), // TODO: Change HasDefault<i32> to Option<i32>
-    /// Name, default, docstring
-    Long(Option<String>, HasDefault<i64>, Option<String>), // TODO: Change HasDefault<..> to Option<..>
-    /// Name, default, docstring
-    Float(Option<String>, HasDefault<f32>, Option<String>), // TODO: Change HasDefault<..> to Option<..>
-    /// Name, default, docstring
-    Double(Option<String>, HasDefault<f64>, Option<String>), // TODO: Change HasDefault<..> to Option<..>
-    /// Name, default, docstring
-    Boolean(Option<String>, HasDefault<bool>, Option<String>), // TODO: Change HasDefault<..> to Option<..>
-    /// Name, default, docstring
-    String(Option<String>, HasDefault<String>, Option<String>), // TODO: Change HasDefault<..> to Option<..>
-    /// Name, values, default, namespace, docstring
+    /// Main schema, named types, namespace
+    Schema(Option<Box<RawField>>, Vec<RawField>, Option<String>),
+    /// Name, default, docstring, properties of the field, properties of the type
+    Int(
+        Option<String>,
+        HasDefault<i32>,
+        Option<String>,
+        Properties,
+        Properties,
+    ), // TODO: Change HasDefault<i32> to Option<i32>
+    /// Name, default, docstring, properties of the field, properties of the type
+    Long(
+        Option<String>,
+        HasDefault<i64>,
+        Option<String>,
+        Properties,
+        Properties,
+    ), // TODO: Change HasDefault<..> to Option<..>
+    /// Name, default, docstring, properties of the field, properties of the type
+    Float(
+        Option<String>,
+        HasDefault<f32>,
+        Option<String>,
+        Properties,
+        Properties,
+    ), // TODO: Change HasDefault<..> to Option<..>
+    /// Name, default, docstring, properties of the field, properties of the type
+    Double(
+        Option<String>,
+        HasDefault<f64>,
+        Option<String>,
+        Properties,
+        Properties,
+    ), // TODO: Change HasDefault<..> to Option<..>
+    /// Name, default, docstring, properties of the field, properties of the type
+    Boolean(
+        Option<String>,
+        HasDefault<bool>,
+        Option<String>,
+        Properties,
+        Properties,
+    ), // TODO: Change HasDefault<..> to Option<..>
+    /// Name, default, docstring, properties of the field, properties of the type
+    String(
+        Option<String>,
+        HasDefault<String>,
+        Option<String>,
+        Properties,
+        Properties,
+    ), // TODO: Change HasDefault<..> to Option<..>
+    /// Name, symbols and the comments between them, default, namespace, docstring, properties
     Enum(
         Option<String>,
-        Vec<String>,
+        Vec<RawField>,
         HasDefault<String>,
         Option<String>,
         Option<String>,
+        Properties,
     ), // TODO: Name might not need to be optional,  // TODO: Change HasDefault<..> to Option<..>
-    /// Name, subfields, namespace, docstring
+    /// Name, subfields, namespace, docstring, properties
     Record(
         Option<String>,
         Vec<RawField>,
         Option<String>,
         Option<String>,
+        Properties,
     ), // TODO: Name might not need to be optional
-    /// Name, data types in union, default value, docstring
+    /// Name, data types in union, default value, docstring, properties
     Union(
         Option<String>,
         Vec<RawField>,
         HasDefault<Literal>,
         Option<String>,
+        Properties,
     ), // TODO: Name might not need to be optional
-    /// Name, Field in Array, default value, docstring
+    /// Name, Field in Array, default value, docstring, properties of the field, properties
+    /// of the type
     Array(
         Option<String>,
         Box<RawField>,
         HasDefault<Literal>,
         Option<String>,
+        Properties,
+        Properties,
     ), // TODO: Change HasDefault<..> to Option<..>
     /// Null type needed for representing null in unions
     Null,
-    /// Name, Type, docstring
-    Unresolved(Option<String>, String, Option<String>),
+    /// Name, Type, docstring, properties, position of the type
+    Unresolved(Option<String>, String, Option<String>, Properties, Position),
     /// Path
     Import(String),
+    /// Text including the comment markers, whether it follows a declaration on the same line
+    Comment(String, bool),
+    /// Symbol of an enum and its docstring, which isn't part of the schema
+    Symbol(String, Option<String>),
 }
 
 impl RawField {
@@ -82,19 +141,35 @@ impl RawField {
         matches!(self, RawField::Unresolved(..))
     }
 
-    /// Returns a field by name and sets the default as HasDefault::None
-    pub fn find_field_by_name(&self, field_name: String) -> Option<RawField> {
+    /// Returns the named type a reference stands for and sets the default as
+    /// HasDefault::None. The name is resolved by full name relative to the namespace
+    /// enclosing the reference, see `reference_fullnames`.
+    pub fn find_field_by_name(
+        &self,
+        field_name: String,
+        namespace: Option<&str>,
+    ) -> Option<RawField> {
+        match self {
+            RawField::Protocol(_, fields, ..) | RawField::Schema(_, fields, ..) => {
+                reference_fullnames(&field_name, namespace)
+                    .iter()
+                    .find_map(|fullname| {
+                        fields
+                            .iter()
+                            .find(|field| field.fullname().as_ref() == Some(fullname))
+                    })
+                    .map(|field| field.clone().remove_default())
+            }
+            _ => None,
+        }
+    }
+
+    /// Full name of a record or enum, i.e. its name prefixed with its namespace
+    pub fn fullname(&self) -> Option<String> {
         match self {
-            RawField::Protocol(_name, fields, ..) => {
-                for field in fields.iter() {
-                    let Some(cur_name) = field.name() else {
-                        continue;
-                    };
-                    if cur_name == field_name {
-                        return Some(field.clone().remove_default());
-                    }
-                }
-                None
+            RawField::Record(Some(name), _, namespace, ..)
+            | RawField::Enum(Some(name), _, _, namespace, ..) => {
+                Some(qualify(name, namespace.as_deref()))
             }
             _ => None,
         }
@@ -103,6 +178,7 @@ impl RawField {
     pub fn name(&self) -> Option<String> {
         match self {
             RawField::Protocol(name, ..) => name.clone(),
+            RawField::Schema(..) => None,
             RawField::Int(name, ..) => name.clone(),
             RawField::Long(name, ..) => name.clone(),
             RawField::Float(name, ..) => name.clone(),
@@ -116,27 +192,124 @@ impl RawField {
             RawField::Array(name, ..) => name.clone(),
             RawField::Null => None,
             RawField::Import(_) => None,
+            RawField::Comment(..) => None,
+            RawField::Symbol(..) => None,
+        }
+    }
+
+    /// Symbols of an enum without their docstrings and the comments between them
+    pub fn symbols(&self) -> Vec<String> {
+        match self {
+            RawField::Enum(_, body, ..) => body
+                .iter()
+                .filter_map(|item| match item {
+                    RawField::Symbol(symbol, _) => Some(symbol.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Properties of the type, i.e. the annotations before it, which only primitives and
+    /// arrays can have
+    pub fn type_properties(&self) -> Option<&Properties> {
+        match self {
+            RawField::Int(.., type_props)
+            | RawField::Long(.., type_props)
+            | RawField::Float(.., type_props)
+            | RawField::Double(.., type_props)
+            | RawField::Boolean(.., type_props)
+            | RawField::String(.., type_props)
+            | RawField::Array(.., type_props) => Some(type_props),
+            _ => None,
         }
     }
 
     fn remove_default(self) -> RawField {
         match self {
-            RawField::Int(name, _default, ..) => RawField::Int(name, HasDefault::None, None),
-            RawField::Long(name, _default, ..) => RawField::Long(name, HasDefault::None, None),
-            RawField::Float(name, _default, ..) => RawField::Float(name, HasDefault::None, None),
-            RawField::Double(name, _default, ..) => RawField::Double(name, HasDefault::None, None),
-            RawField::Boolean(name, _default, ..) => {
-                RawField::Boolean(name, HasDefault::None, None)
-            }
-            RawField::String(name, _default, ..) => RawField::String(name, HasDefault::None, None),
+            RawField::Int(name, _default, ..) => RawField::Int(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            ),
+            RawField::Long(name, _default, ..) => RawField::Long(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            ),
+            RawField::Float(name, _default, ..) => RawField::Float(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            ),
+            RawField::Double(name, _default, ..) => RawField::Double(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            ),
+            RawField::Boolean(name, _default, ..) => RawField::Boolean(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            ),
+            RawField::String(name, _default, ..) => RawField::String(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            ),
             RawField::Enum(name, values, _, ns, ..) => {
-                RawField::Enum(name, values, HasDefault::None, ns, None)
+                RawField::Enum(name, values, HasDefault::None, ns, None, Properties::new())
             }
             _ => self,
         }
     }
 }
 
+/// Full names a reference to a named type can stand for, in the order they are tried. Like
+/// the Avro specification says, a name containing a dot is a full name and any other name
+/// is in the namespace enclosing the reference. Names in no namespace are tried last, since
+/// they can't be written as a full name.
+pub fn reference_fullnames(name: &str, namespace: Option<&str>) -> Vec<String> {
+    match namespace {
+        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+            vec![format!("{}.{}", namespace, name), name.to_string()]
+        }
+        _ => vec![name.to_string()],
+    }
+}
+
+/// Whether a name of a type, field or symbol matches `[A-Za-z_][A-Za-z0-9_]*`
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Prefix a name with the namespace unless it already is a full name
+pub fn qualify(name: &str, namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+            format!("{}.{}", namespace, name)
+        }
+        _ => name.to_string(),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Literal {
@@ -151,56 +324,164 @@ pub enum Literal {
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Field {
-    /// Name, fields, namespace, docstring
-    Protocol(Option<String>, Vec<Field>, Option<String>, Option<String>),
-    /// Name, default, docstring
-    Int(Option<String>, HasDefault<i32>, Option<String>),
-    /// Name, default, docstring
-    Long(Option<String>, HasDefault<i64>, Option<String>),
-    /// Name, default, docstring
-    Float(Option<String>, HasDefault<f32>, Option<String>),
-    /// Name, default, docstring
-    Double(Option<String>, HasDefault<f64>, Option<String>),
-    /// Name, default, docstring
-    Boolean(Option<String>, HasDefault<bool>, Option<String>),
-    /// Name, default, docstring
-    String(Option<String>, HasDefault<String>, Option<String>),
-    /// Name, values, default, namespace, docstring
+    /// Name, fields, namespace, docstring, properties
+    Protocol(
+        Option<String>,
+        Vec<Field>,
+        Option<String>,
+        Option<String>,
+        Properties,
+    ),
+    /// Main schema, named types, namespace
+    Schema(Option<Box<Field>>, Vec<Field>, Option<String>),
+    /// Name, default, docstring, properties of the field, properties of the type
+    Int(
+        Option<String>,
+        HasDefault<i32>,
+        Option<String>,
+        Properties,
+        Properties,
+    ),
+    /// Name, default, docstring, properties of the field, properties of the type
+    Long(
+        Option<String>,
+        HasDefault<i64>,
+        Option<String>,
+        Properties,
+        Properties,
+    ),
+    /// Name, default, docstring, properties of the field, properties of the type
+    Float(
+        Option<String>,
+        HasDefault<f32>,
+        Option<String>,
+        Properties,
+        Properties,
+    ),
+    /// Name, default, docstring, properties of the field, properties of the type
+    Double(
+        Option<String>,
+        HasDefault<f64>,
+        Option<String>,
+        Properties,
+        Properties,
+    ),
+    /// Name, default, docstring, properties of the field, properties of the type
+    Boolean(
+        Option<String>,
+        HasDefault<bool>,
+        Option<String>,
+        Properties,
+        Properties,
+    ),
+    /// Name, default, docstring, properties of the field, properties of the type
+    String(
+        Option<String>,
+        HasDefault<String>,
+        Option<String>,
+        Properties,
+        Properties,
+    ),
+    /// Name, values, default, namespace, docstring, properties
     Enum(
         Option<String>,
         Vec<String>,
         HasDefault<String>,
         Option<String>,
         Option<String>,
+        Properties,
+    ),
+    /// Name, subfields, namespace, docstring, properties
+    Record(
+        Option<String>,
+        Vec<Field>,
+        Option<String>,
+        Option<String>,
+        Properties,
     ),
-    /// Name, subfields, namespace, docstring
-    Record(Option<String>, Vec<Field>, Option<String>, Option<String>),
-    /// Name, data types in union, default value, docstring
+    /// Name, data types in union, default value, docstring, properties
     Union(
         Option<String>,
         Vec<Field>,
         HasDefault<Literal>,
         Option<String>,
+        Properties,
     ),
-    /// Name, Field in Array, default value, docstring
+    /// Name, Field in Array, default value, docstring, properties of the field, properties
+    /// of the type
     Array(
         Option<String>,
         Box<Field>,
         HasDefault<Literal>,
         Option<String>,
+        Properties,
+        Properties,
+    ),
+    /// Name, type, docstring, properties
+    RecordReference(Option<String>, String, Option<String>, Properties),
+    /// Name, type, default, docstring, properties
+    EnumReference(
+        Option<String>,
+        String,
+        HasDefault<String>,
+        Option<String>,
+        Properties,
     ),
-    /// Name, type, docstring
-    RecordReference(Option<String>, String, Option<String>),
-    /// Name, type, default, docstring
-    EnumReference(Option<String>, String, HasDefault<String>, Option<String>),
     /// Null type needed for representing null in unions
     Null,
 }
 
 impl Field {
+    pub fn name(&self) -> Option<String> {
+        match self {
+            Field::Protocol(name, ..) => name.clone(),
+            Field::Schema(..) => None,
+            Field::Int(name, ..) => name.clone(),
+            Field::Long(name, ..) => name.clone(),
+            Field::Float(name, ..) => name.clone(),
+            Field::Double(name, ..) => name.clone(),
+            Field::Boolean(name, ..) => name.clone(),
+            Field::String(name, ..) => name.clone(),
+            Field::Record(name, ..) => name.clone(),
+            Field::Enum(name, ..) => name.clone(),
+            Field::Union(name, ..) => name.clone(),
+            Field::Array(name, ..) => name.clone(),
+            Field::RecordReference(name, ..) => name.clone(),
+            Field::EnumReference(name, ..) => name.clone(),
+            Field::Null => None,
+        }
+    }
+
+    /// Full name of a record or enum, i.e. its name prefixed with its namespace
+    pub fn fullname(&self) -> Option<String> {
+        match self {
+            Field::Record(Some(name), _, namespace, ..)
+            | Field::Enum(Some(name), _, _, namespace, ..) => {
+                Some(qualify(name, namespace.as_deref()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Properties of the type, i.e. the annotations before it, which only primitives and
+    /// arrays can have
+    pub fn type_properties(&self) -> Option<&Properties> {
+        match self {
+            Field::Int(.., type_props)
+            | Field::Long(.., type_props)
+            | Field::Float(.., type_props)
+            | Field::Double(.., type_props)
+            | Field::Boolean(.., type_props)
+            | Field::String(.., type_props)
+            | Field::Array(.., type_props) => Some(type_props),
+            _ => None,
+        }
+    }
+
     pub fn get_avro_type_name(&self) -> Option<String> {
         match self {
             Field::Protocol(..) => Some("protocol".to_string()),
+            Field::Schema(..) => None,
             Field::Int(..) => Some("int".to_string()),
             Field::Long(..) => Some("long".to_string()),
             Field::Float(..) => Some("float".to_string()),
@@ -217,3 +498,69 @@ impl Field {
         }
     }
 }
+
+/// Convert a linked field back into a parsed field, e.g. for printing it as IDL. References
+/// become unresolved again.
+impl From<Field> for RawField {
+    fn from(field: Field) -> Self {
+        let convert_all = |fields: Vec<Field>| fields.into_iter().map(RawField::from).collect();
+        match field {
+            Field::Protocol(name, fields, namespace, docstring, props) => {
+                RawField::Protocol(name, convert_all(fields), namespace, docstring, props)
+            }
+            Field::Schema(main, fields, namespace) => RawField::Schema(
+                main.map(|main| Box::new(RawField::from(*main))),
+                convert_all(fields),
+                namespace,
+            ),
+            Field::Int(name, default, docstring, props, type_props) => {
+                RawField::Int(name, default, docstring, props, type_props)
+            }
+            Field::Long(name, default, docstring, props, type_props) => {
+                RawField::Long(name, default, docstring, props, type_props)
+            }
+            Field::Float(name, default, docstring, props, type_props) => {
+                RawField::Float(name, default, docstring, props, type_props)
+            }
+            Field::Double(name, default, docstring, props, type_props) => {
+                RawField::Double(name, default, docstring, props, type_props)
+            }
+            Field::Boolean(name, default, docstring, props, type_props) => {
+                RawField::Boolean(name, default, docstring, props, type_props)
+            }
+            Field::String(name, default, docstring, props, type_props) => {
+                RawField::String(name, default, docstring, props, type_props)
+            }
+            Field::Enum(name, symbols, default, namespace, docstring, props) => RawField::Enum(
+                name,
+                symbols
+                    .into_iter()
+                    .map(|symbol| RawField::Symbol(symbol, None))
+                    .collect(),
+                default,
+                namespace,
+                docstring,
+                props,
+            ),
+            Field::Record(name, fields, namespace, docstring, props) => {
+                RawField::Record(name, convert_all(fields), namespace, docstring, props)
+            }
+            Field::Union(name, fields, default, docstring, props) => {
+                RawField::Union(name, convert_all(fields), default, docstring, props)
+            }
+            Field::Array(name, inner, default, docstring, props, type_props) => RawField::Array(
+                name,
+                Box::new(RawField::from(*inner)),
+                default,
+                docstring,
+                props,
+                type_props,
+            ),
+            Field::RecordReference(name, type_, docstring, props)
+            | Field::EnumReference(name, type_, _, docstring, props) => {
+                RawField::Unresolved(name, type_, docstring, props, Position::default())
+            }
+            Field::Null => RawField::Null,
+        }
+    }
+}
@@ -0,0 +1,838 @@
+//! Fluent builder for protocols, e.g. to generate schemas from Rust metadata. The builder
+//! produces the same linked AST as `LinkParser::parse` and validates names, duplicates
+//! and defaults as types are added, and references once all types are declared. The first
+//! problem is returned by `build`.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::ast::{is_valid_name, Field, HasDefault, Properties};
+use crate::error::AvroError;
+use crate::visitor::{walk_field_mut, VisitorMut};
+
+pub struct ProtocolBuilder {
+    name: String,
+    namespace: Option<String>,
+    doc: Option<String>,
+    properties: Properties,
+    types: Vec<Field>,
+    declared: HashMap<String, Declared>,
+    error: Option<AvroError>,
+}
+
+/// A declared record or enum
+#[derive(Clone)]
+struct Declared {
+    is_enum: bool,
+    /// Name references are written with, which is the full name of types with their own
+    /// namespace so they resolve from any record
+    reference: String,
+}
+
+impl ProtocolBuilder {
+    pub fn new(name: &str) -> Self {
+        let mut builder = Self {
+            name: name.to_string(),
+            namespace: None,
+            doc: None,
+            properties: Properties::new(),
+            types: vec![],
+            declared: HashMap::new(),
+            error: None,
+        };
+        builder.check(validate_name(name, "protocol"));
+        builder
+    }
+
+    /// Namespace of the protocol, which is also used by types without their own namespace
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.check(validate_namespace(namespace));
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_string());
+        self
+    }
+
+    /// Custom property, like an annotation e.g. `@owner("team")`
+    pub fn property(mut self, key: &str, value: Value) -> Self {
+        self.properties.insert(key.to_string(), value);
+        self
+    }
+
+    /// Declare an enum
+    pub fn enumeration(self, name: &str, symbols: &[&str]) -> Self {
+        self.enumeration_with(name, symbols, |e| e)
+    }
+
+    /// Declare an enum with the symbol used when reading an unknown symbol
+    pub fn enumeration_default(self, name: &str, symbols: &[&str], default: &str) -> Self {
+        self.enumeration_with(name, symbols, |e| e.default(default))
+    }
+
+    /// Declare an enum, whose default, namespace, docstring and properties are set in
+    /// `build`
+    pub fn enumeration_with(
+        mut self,
+        name: &str,
+        symbols: &[&str],
+        build: impl FnOnce(EnumBuilder) -> EnumBuilder,
+    ) -> Self {
+        if self.declare(name, true) {
+            let builder = build(EnumBuilder::new(name, symbols));
+            match builder.build() {
+                Ok(enum_) => self.add_type(enum_),
+                Err(err) => self.check(Err(err)),
+            }
+        }
+        self
+    }
+
+    /// Declare a record, whose fields are added in `build`. Fields can reference any type
+    /// of the protocol, including ones declared later.
+    pub fn record(
+        mut self,
+        name: &str,
+        build: impl FnOnce(RecordBuilder) -> RecordBuilder,
+    ) -> Self {
+        if self.declare(name, false) {
+            let builder = build(RecordBuilder::new(name));
+            match builder.build() {
+                Ok(record) => self.add_type(record),
+                Err(err) => self.check(Err(err)),
+            }
+        }
+        self
+    }
+
+    /// Validate the protocol and return it as a linked `Field::Protocol`
+    pub fn build(self) -> Result<Field, AvroError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        // Attach the protocol namespace unless the type sets its own, like the lexer does
+        let namespace = self.namespace;
+        let mut types: Vec<Field> = self
+            .types
+            .into_iter()
+            .map(|field| match field {
+                Field::Record(name, fields, ns, doc, props) => {
+                    Field::Record(name, fields, ns.or_else(|| namespace.clone()), doc, props)
+                }
+                Field::Enum(name, symbols, default, ns, doc, props) => Field::Enum(
+                    name,
+                    symbols,
+                    default,
+                    ns.or_else(|| namespace.clone()),
+                    doc,
+                    props,
+                ),
+                field => field,
+            })
+            .collect();
+
+        let mut linker = ReferenceLinker {
+            declared: &self.declared,
+            error: None,
+        };
+        for field in types.iter_mut() {
+            linker.visit_field_mut(field);
+        }
+        if let Some(err) = linker.error {
+            return Err(err);
+        }
+        Ok(Field::Protocol(
+            Some(self.name),
+            types,
+            namespace,
+            self.doc,
+            self.properties,
+        ))
+    }
+
+    /// Add a declared type, which is referenced by its full name if it has a namespace
+    fn add_type(&mut self, field: Field) {
+        if let Field::Record(Some(name), _, Some(namespace), ..)
+        | Field::Enum(Some(name), _, _, Some(namespace), ..) = &field
+        {
+            if let Some(declared) = self.declared.get_mut(name) {
+                declared.reference = format!("{}.{}", namespace, name);
+            }
+        }
+        self.types.push(field);
+    }
+
+    /// Register a type name, returning whether it is valid and not declared yet
+    fn declare(&mut self, name: &str, is_enum: bool) -> bool {
+        let valid = validate_name(name, "type");
+        if valid.is_err() {
+            self.check(valid);
+            return false;
+        }
+        let declared = Declared {
+            is_enum,
+            reference: name.to_string(),
+        };
+        if self.declared.insert(name.to_string(), declared).is_some() {
+            self.check(Err(AvroError::InvalidASTDataType(format!(
+                "Type '{}' is declared more than once",
+                name
+            ))));
+            return false;
+        }
+        true
+    }
+
+    /// Keep the first error
+    fn check(&mut self, result: Result<(), AvroError>) {
+        if let (None, Err(err)) = (&self.error, result) {
+            self.error = Some(err);
+        }
+    }
+}
+
+/// Builds an enum from its symbols
+pub struct EnumBuilder {
+    name: String,
+    symbols: Vec<String>,
+    default: Option<String>,
+    namespace: Option<String>,
+    doc: Option<String>,
+    properties: Properties,
+    error: Option<AvroError>,
+}
+
+impl EnumBuilder {
+    fn new(name: &str, symbols: &[&str]) -> Self {
+        let mut builder = Self {
+            name: name.to_string(),
+            symbols: symbols.iter().map(|symbol| symbol.to_string()).collect(),
+            default: None,
+            namespace: None,
+            doc: None,
+            properties: Properties::new(),
+            error: None,
+        };
+        let mut seen = HashSet::new();
+        for symbol in symbols.iter() {
+            builder.check(validate_name(symbol, "symbol"));
+            if !seen.insert(*symbol) {
+                builder.check(Err(AvroError::InvalidASTDataType(format!(
+                    "Duplicate symbol '{}' in enum '{}'",
+                    symbol, name
+                ))));
+            }
+        }
+        builder
+    }
+
+    /// Symbol used when reading an unknown symbol
+    pub fn default(mut self, default: &str) -> Self {
+        if !self.symbols.iter().any(|symbol| symbol == default) {
+            self.check(Err(AvroError::InvalidASTDataType(format!(
+                "Default '{}' of enum '{}' is not one of its symbols",
+                default, self.name
+            ))));
+        }
+        self.default = Some(default.to_string());
+        self
+    }
+
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.check(validate_namespace(namespace));
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_string());
+        self
+    }
+
+    /// Custom property of the enum, like an annotation e.g. `@owner("team")`
+    pub fn property(mut self, key: &str, value: Value) -> Self {
+        self.properties.insert(key.to_string(), value);
+        self
+    }
+
+    fn build(self) -> Result<Field, AvroError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        Ok(Field::Enum(
+            Some(self.name),
+            self.symbols,
+            match self.default {
+                Some(default) => HasDefault::Default(Some(default)),
+                None => HasDefault::None,
+            },
+            self.namespace,
+            self.doc,
+            self.properties,
+        ))
+    }
+
+    /// Keep the first error
+    fn check(&mut self, result: Result<(), AvroError>) {
+        if let (None, Err(err)) = (&self.error, result) {
+            self.error = Some(err);
+        }
+    }
+}
+
+/// Builds the fields of a record. Types of arrays and unions are given by name, either a
+/// primitive such as `"int"` or a declared record or enum.
+pub struct RecordBuilder {
+    name: String,
+    namespace: Option<String>,
+    doc: Option<String>,
+    properties: Properties,
+    fields: Vec<Field>,
+    error: Option<AvroError>,
+}
+
+impl RecordBuilder {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            namespace: None,
+            doc: None,
+            properties: Properties::new(),
+            fields: vec![],
+            error: None,
+        }
+    }
+
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.check(validate_namespace(namespace));
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_string());
+        self
+    }
+
+    /// Custom property of the record, like an annotation e.g. `@owner("team")`
+    pub fn property(mut self, key: &str, value: Value) -> Self {
+        self.properties.insert(key.to_string(), value);
+        self
+    }
+
+    pub fn field_int(self, name: &str) -> Self {
+        self.add(name, |name| {
+            Field::Int(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_int_default(self, name: &str, default: i32) -> Self {
+        self.add(name, |name| {
+            Field::Int(
+                name,
+                HasDefault::Default(Some(default)),
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_long(self, name: &str) -> Self {
+        self.add(name, |name| {
+            Field::Long(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_long_default(self, name: &str, default: i64) -> Self {
+        self.add(name, |name| {
+            Field::Long(
+                name,
+                HasDefault::Default(Some(default)),
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_float(self, name: &str) -> Self {
+        self.add(name, |name| {
+            Field::Float(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_float_default(self, name: &str, default: f32) -> Self {
+        self.add(name, |name| {
+            Field::Float(
+                name,
+                HasDefault::Default(Some(default)),
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_double(self, name: &str) -> Self {
+        self.add(name, |name| {
+            Field::Double(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_double_default(self, name: &str, default: f64) -> Self {
+        self.add(name, |name| {
+            Field::Double(
+                name,
+                HasDefault::Default(Some(default)),
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_boolean(self, name: &str) -> Self {
+        self.add(name, |name| {
+            Field::Boolean(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_boolean_default(self, name: &str, default: bool) -> Self {
+        self.add(name, |name| {
+            Field::Boolean(
+                name,
+                HasDefault::Default(Some(default)),
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_string(self, name: &str) -> Self {
+        self.add(name, |name| {
+            Field::String(
+                name,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    pub fn field_string_default(self, name: &str, default: &str) -> Self {
+        let default = default.to_string();
+        self.add(name, |name| {
+            Field::String(
+                name,
+                HasDefault::Default(Some(default)),
+                None,
+                Properties::new(),
+                Properties::new(),
+            )
+        })
+    }
+
+    /// Field referencing a record or enum of the protocol
+    pub fn field_reference(self, name: &str, type_name: &str) -> Self {
+        let field = reference(type_name);
+        self.add(name, |name| set_name(field, name))
+    }
+
+    pub fn field_array(mut self, name: &str, item_type: &str) -> Self {
+        match self.resolve(item_type) {
+            Ok(item) => self.add(name, |name| {
+                Field::Array(
+                    name,
+                    Box::new(item),
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )
+            }),
+            Err(err) => {
+                self.check(Err(err));
+                self
+            }
+        }
+    }
+
+    /// Union of the given types, e.g. `&["null", "string", "Person"]`
+    pub fn field_union(mut self, name: &str, types: &[&str]) -> Self {
+        match types.iter().map(|type_| self.resolve(type_)).collect() {
+            Ok(types) => self.add(name, |name| {
+                Field::Union(name, types, HasDefault::None, None, Properties::new())
+            }),
+            Err(err) => {
+                self.check(Err(err));
+                self
+            }
+        }
+    }
+
+    /// Union of the given type and null, like `T? name;`
+    pub fn field_optional(mut self, name: &str, type_name: &str) -> Self {
+        match self.resolve(type_name) {
+            Ok(Field::Null) => {
+                self.check(Err(AvroError::InvalidASTDataType(format!(
+                    "Optional field '{}' can't be of type null",
+                    name
+                ))));
+                self
+            }
+            Ok(field) => self.add(name, |name| {
+                Field::Union(
+                    name,
+                    vec![field, Field::Null],
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                )
+            }),
+            Err(err) => {
+                self.check(Err(err));
+                self
+            }
+        }
+    }
+
+    /// Set the docstring of the last added field
+    pub fn field_doc(mut self, doc: &str) -> Self {
+        match self.fields.last_mut().and_then(field_parts) {
+            Some((docstring, _)) => *docstring = Some(doc.to_string()),
+            None => self.check(Err(AvroError::InvalidASTDataType(format!(
+                "Record '{}' has no field to document",
+                self.name
+            )))),
+        }
+        self
+    }
+
+    /// Set a custom property of the last added field
+    pub fn field_property(mut self, key: &str, value: Value) -> Self {
+        match self.fields.last_mut().and_then(field_parts) {
+            Some((_, properties)) => {
+                properties.insert(key.to_string(), value);
+            }
+            None => self.check(Err(AvroError::InvalidASTDataType(format!(
+                "Record '{}' has no field to annotate",
+                self.name
+            )))),
+        }
+        self
+    }
+
+    fn build(self) -> Result<Field, AvroError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        Ok(Field::Record(
+            Some(self.name),
+            self.fields,
+            self.namespace,
+            self.doc,
+            self.properties,
+        ))
+    }
+
+    fn add(mut self, name: &str, field: impl FnOnce(Option<String>) -> Field) -> Self {
+        if let Err(err) = validate_name(name, "field") {
+            self.check(Err(err));
+        } else if self
+            .fields
+            .iter()
+            .any(|f| f.name().as_deref() == Some(name))
+        {
+            self.check(Err(AvroError::InvalidASTDataType(format!(
+                "Field '{}' is declared more than once in record '{}'",
+                name, self.name
+            ))));
+        } else {
+            self.fields.push(field(Some(name.to_string())));
+        }
+        self
+    }
+
+    /// Unnamed field of a primitive or declared type, as used in arrays and unions
+    fn resolve(&self, type_name: &str) -> Result<Field, AvroError> {
+        let props = Properties::new;
+        Ok(match type_name {
+            "null" => Field::Null,
+            "int" => Field::Int(None, HasDefault::None, None, props(), props()),
+            "long" => Field::Long(None, HasDefault::None, None, props(), props()),
+            "float" => Field::Float(None, HasDefault::None, None, props(), props()),
+            "double" => Field::Double(None, HasDefault::None, None, props(), props()),
+            "boolean" => Field::Boolean(None, HasDefault::None, None, props(), props()),
+            "string" => Field::String(None, HasDefault::None, None, props(), props()),
+            _ => reference(type_name),
+        })
+    }
+
+    /// Keep the first error
+    fn check(&mut self, result: Result<(), AvroError>) {
+        if let (None, Err(err)) = (&self.error, result) {
+            self.error = Some(err);
+        }
+    }
+}
+
+/// Reference to a type as given to the builder, which `ReferenceLinker` resolves to a
+/// record or enum
+fn reference(type_name: &str) -> Field {
+    Field::RecordReference(None, type_name.to_string(), None, Properties::new())
+}
+
+/// Resolves the references of the declared types once all of them are known
+struct ReferenceLinker<'a> {
+    declared: &'a HashMap<String, Declared>,
+    error: Option<AvroError>,
+}
+
+impl VisitorMut for ReferenceLinker<'_> {
+    fn visit_field_mut(&mut self, field: &mut Field) {
+        if self.error.is_none() {
+            walk_field_mut(self, field)
+        }
+    }
+
+    fn visit_reference_mut(&mut self, reference: &mut Field) {
+        let Field::RecordReference(name, type_name, doc, props) = reference else {
+            return;
+        };
+        match self.declared.get(type_name.as_str()) {
+            Some(Declared {
+                is_enum: true,
+                reference: fullname,
+            }) => {
+                *reference = Field::EnumReference(
+                    name.take(),
+                    fullname.clone(),
+                    HasDefault::None,
+                    doc.take(),
+                    std::mem::take(props),
+                )
+            }
+            Some(Declared {
+                is_enum: false,
+                reference: fullname,
+            }) => *type_name = fullname.clone(),
+            None => {
+                self.error = Some(AvroError::UndefinedReference(
+                    format!("Field of type '{}' cannot be found!", type_name),
+                    None,
+                ))
+            }
+        }
+    }
+}
+
+fn set_name(field: Field, name: Option<String>) -> Field {
+    match field {
+        Field::RecordReference(_, type_, doc, props) => {
+            Field::RecordReference(name, type_, doc, props)
+        }
+        Field::EnumReference(_, type_, default, doc, props) => {
+            Field::EnumReference(name, type_, default, doc, props)
+        }
+        field => field,
+    }
+}
+
+/// Docstring and properties of a record field
+fn field_parts(field: &mut Field) -> Option<(&mut Option<String>, &mut Properties)> {
+    match field {
+        Field::Int(_, _, doc, props, _)
+        | Field::Long(_, _, doc, props, _)
+        | Field::Float(_, _, doc, props, _)
+        | Field::Double(_, _, doc, props, _)
+        | Field::Boolean(_, _, doc, props, _)
+        | Field::String(_, _, doc, props, _)
+        | Field::Union(_, _, _, doc, props)
+        | Field::Array(_, _, _, doc, props, _)
+        | Field::RecordReference(_, _, doc, props)
+        | Field::EnumReference(_, _, _, doc, props) => Some((doc, props)),
+        _ => None,
+    }
+}
+
+/// Names must match `[A-Za-z_][A-Za-z0-9_]*`
+fn validate_name(name: &str, kind: &str) -> Result<(), AvroError> {
+    if is_valid_name(name) {
+        Ok(())
+    } else if name.is_empty() {
+        Err(AvroError::MissingName(format!(
+            "The {} is missing a name!",
+            kind
+        )))
+    } else {
+        Err(AvroError::InvalidASTDataType(format!(
+            "Invalid {} name '{}', names must match [A-Za-z_][A-Za-z0-9_]*",
+            kind, name
+        )))
+    }
+}
+
+fn validate_namespace(namespace: &str) -> Result<(), AvroError> {
+    namespace
+        .split('.')
+        .try_for_each(|part| validate_name(part, "namespace"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::ProtocolBuilder;
+    use crate::compiler::IdlCompiler;
+    use crate::error::AvroError;
+
+    #[test]
+    fn test_builder() {
+        let protocol = ProtocolBuilder::new("Event")
+            .namespace("org.example")
+            .enumeration_default("Meal", &["Dinner", "Lunch"], "Dinner")
+            .record("Person", |r| {
+                r.doc("A person")
+                    .field_string("name")
+                    .field_property("pii", json!(true))
+                    .field_int_default("age", 0)
+                    .field_optional("meal", "Meal")
+                    .field_array("friends", "Person")
+                    .field_union("id", &["long", "string"])
+            })
+            .build()
+            .unwrap();
+
+        let src = "@namespace(\"org.example\")
+protocol Event {
+  enum Meal { Dinner, Lunch } = Dinner;
+  /** A person */
+  record Person {
+    string @pii(true) name;
+    int age = 0;
+    Meal? meal;
+    array<Person> friends;
+    union { long, string } id;
+  }
+}";
+        assert_eq!(protocol, IdlCompiler::new().compile(src).unwrap());
+    }
+
+    #[test]
+    fn test_builder_optional() {
+        let protocol = ProtocolBuilder::new("Event")
+            .enumeration_with("Meal", &["Dinner", "Lunch"], |e| {
+                e.namespace("org.meals")
+                    .doc("A meal")
+                    .property("owner", json!("kitchen"))
+            })
+            .record("Person", |r| {
+                r.field_optional("age", "int")
+                    .field_optional("meal", "Meal")
+            })
+            .build()
+            .unwrap();
+
+        let src = "protocol Event {
+  /** A meal */
+  @namespace(\"org.meals\") @owner(\"kitchen\")
+  enum Meal { Dinner, Lunch }
+  record Person {
+    int? age;
+    org.meals.Meal? meal;
+  }
+}";
+        assert_eq!(protocol, IdlCompiler::new().compile(src).unwrap());
+    }
+
+    #[test]
+    fn test_builder_forward_references() {
+        let protocol = ProtocolBuilder::new("Event")
+            .record("Person", |r| {
+                r.field_optional("team", "Team")
+                    .field_reference("meal", "Meal")
+            })
+            .record("Team", |r| r.field_array("members", "Person"))
+            .enumeration_with("Meal", &["Dinner", "Lunch"], |e| e.namespace("org.meals"))
+            .build()
+            .unwrap();
+
+        let src = "protocol Event {
+  record Person {
+    Team? team;
+    org.meals.Meal meal;
+  }
+  record Team {
+    array<Person> members;
+  }
+  @namespace(\"org.meals\")
+  enum Meal { Dinner, Lunch }
+}";
+        assert_eq!(protocol, IdlCompiler::new().compile(src).unwrap());
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let undefined = ProtocolBuilder::new("Event")
+            .record("Person", |r| r.field_reference("meal", "Meal"))
+            .build();
+        assert!(matches!(undefined, Err(AvroError::UndefinedReference(..))));
+
+        let duplicate = ProtocolBuilder::new("Event")
+            .record("Person", |r| r.field_string("name").field_int("name"))
+            .build();
+        assert_eq!(
+            duplicate.unwrap_err().to_string(),
+            "Field 'name' is declared more than once in record 'Person'"
+        );
+
+        let default = ProtocolBuilder::new("Event")
+            .enumeration_default("Meal", &["Dinner"], "Lunch")
+            .build();
+        assert_eq!(
+            default.unwrap_err().to_string(),
+            "Default 'Lunch' of enum 'Meal' is not one of its symbols"
+        );
+
+        let name = ProtocolBuilder::new("Event")
+            .record("1Person", |r| r)
+            .build();
+        assert!(matches!(name, Err(AvroError::InvalidASTDataType(_))));
+    }
+}
@@ -0,0 +1,145 @@
+//! Generators emitting source code of other languages from the typed schema model
+
+mod protobuf;
+mod python;
+mod rust;
+mod sql;
+mod typescript;
+
+pub use protobuf::ProtobufGenerator;
+pub use python::PythonGenerator;
+pub use rust::RustGenerator;
+pub use sql::{SqlDialect, SqlGenerator};
+pub use typescript::TypeScriptGenerator;
+
+use crate::schema::{Document, NamedSchema, Schema};
+
+/// Convert a name like `firstName` or `FIRST_NAME` into `first_name`
+pub(crate) fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_uppercase()
+            && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+        {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+        previous = Some(c);
+    }
+    snake
+}
+
+/// Convert a name like `first_name`, `FIRST_NAME` or `firstName` into `FirstName`
+pub(crate) fn pascal_case(name: &str) -> String {
+    let shouting = !name.chars().any(|c| c.is_ascii_lowercase());
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().map(|c| c.to_ascii_uppercase());
+            let rest: String = if shouting {
+                chars.as_str().to_ascii_lowercase()
+            } else {
+                chars.as_str().to_string()
+            };
+            first.into_iter().collect::<String>() + &rest
+        })
+        .collect()
+}
+
+/// Name of a referenced type without its namespace, for languages where every generated
+/// type is in the same scope
+pub(crate) fn short_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// Lines of a docstring without surrounding whitespace
+pub(crate) fn doc_lines(doc: &str) -> Vec<&str> {
+    doc.lines().map(|line| line.trim()).collect()
+}
+
+/// Order named types so that types come after the ones they reference, keeping the
+/// declaration order otherwise. Also returns the names of records which still reference
+/// themselves or a type after them, because they are part of a cycle.
+pub(crate) fn dependency_order(document: &Document) -> (Vec<&NamedSchema>, Vec<String>) {
+    fn references<'a>(schema: &'a Schema, names: &mut Vec<&'a str>) {
+        match schema {
+            Schema::Record(name) | Schema::Enum(name) => names.push(name),
+            Schema::Array(inner) => references(inner, names),
+            Schema::Union(types) => types.iter().for_each(|t| references(t, names)),
+            _ => {}
+        }
+    }
+
+    fn visit<'a>(
+        document: &'a Document,
+        named: &'a NamedSchema,
+        visiting: &mut Vec<String>,
+        ordered: &mut Vec<&'a NamedSchema>,
+    ) {
+        let fullname = named.fullname();
+        if visiting.contains(&fullname) || ordered.iter().any(|n| n.fullname() == fullname) {
+            return;
+        }
+        visiting.push(fullname);
+        if let NamedSchema::Record(record) = named {
+            let mut names = vec![];
+            record
+                .fields()
+                .iter()
+                .for_each(|field| references(field.schema(), &mut names));
+            for name in names {
+                if let Some(referenced) = document.named_type(name) {
+                    visit(document, referenced, visiting, ordered);
+                }
+            }
+        }
+        visiting.pop();
+        ordered.push(named);
+    }
+
+    let mut ordered = vec![];
+    for named in document.types() {
+        visit(document, named, &mut vec![], &mut ordered);
+    }
+
+    let position = |name: &str| {
+        ordered
+            .iter()
+            .position(|named| named.name() == name || named.fullname() == name)
+    };
+    let mut cyclic = vec![];
+    for (index, named) in ordered.iter().enumerate() {
+        let NamedSchema::Record(record) = named else {
+            continue;
+        };
+        let mut names = vec![];
+        record
+            .fields()
+            .iter()
+            .for_each(|field| references(field.schema(), &mut names));
+        if names
+            .iter()
+            .any(|name| position(name).is_some_and(|position| position >= index))
+        {
+            cyclic.push(record.name().to_string());
+        }
+    }
+    (ordered, cyclic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pascal_case, snake_case};
+
+    #[test]
+    fn test_case_conversion() {
+        assert_eq!(snake_case("firstName"), "first_name");
+        assert_eq!(snake_case("FIRST_NAME"), "first_name");
+        assert_eq!(snake_case("address2Line"), "address2_line");
+        assert_eq!(pascal_case("first_name"), "FirstName");
+        assert_eq!(pascal_case("FIRST_NAME"), "FirstName");
+        assert_eq!(pascal_case("firstName"), "FirstName");
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use super::{doc_lines, short_name, snake_case};
+use crate::ast::Field;
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, FieldDef, NamedSchema, RecordSchema, Schema};
+
+/// Annotation setting the number of a field, e.g. `string @proto_field(3) name;`
+const FIELD_ANNOTATION: &str = "proto_field";
+/// Field numbers reserved by the protobuf implementation
+const RESERVED_NUMBERS: std::ops::RangeInclusive<u64> = 19000..=19999;
+const MAX_NUMBER: u64 = (1 << 29) - 1;
+
+/// Generates proto3 messages and enums. The package is the namespace of the protocol or
+/// schema file, so types of other namespaces can't share a name with another type. Field numbers are taken from `@proto_field(n)` or else assigned in
+/// declaration order, skipping numbers which are taken. Nullable fields become `optional`,
+/// other unions a `oneof` with a field per type, and arrays `repeated`. Enum values are
+/// prefixed with the enum name, since they share a scope in protobuf.
+pub struct ProtobufGenerator {
+    field: Field,
+}
+
+impl ProtobufGenerator {
+    pub fn new(field: Field) -> Self {
+        Self { field }
+    }
+
+    pub fn generate(self) -> Result<String, AvroError> {
+        let document = Document::try_from(self.field)?;
+        let package = match &document {
+            Document::Protocol(protocol) => protocol.namespace(),
+            Document::Schema(schema) => schema.namespace(),
+        };
+
+        // Every type is in the same package, so types may not share a name across namespaces
+        for (index, named) in document.types().iter().enumerate() {
+            if let Some(other) = document.types()[..index]
+                .iter()
+                .find(|other| other.name() == named.name())
+            {
+                return Err(AvroError::InvalidASTDataType(format!(
+                    "Types '{}' and '{}' have the same name, which protobuf can't tell apart in a single package",
+                    other.fullname(),
+                    named.fullname()
+                )));
+            }
+        }
+
+        let mut out = "// Generated by avro-idl, do not edit\nsyntax = \"proto3\";\n".to_string();
+        if let Some(package) = package {
+            let _ = writeln!(out, "\npackage {};", package);
+        }
+        for named in document.types() {
+            out.push('\n');
+            match named {
+                NamedSchema::Record(record) => out.push_str(&message(record)?),
+                NamedSchema::Enum(enum_) => out.push_str(&enumeration(enum_)),
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn message(record: &RecordSchema) -> Result<String, AvroError> {
+    let mut numbers = FieldNumbers::new(record)?;
+    let mut out = comment(record.doc(), "");
+    let _ = writeln!(out, "message {} {{", record.name());
+    for field in record.fields() {
+        out.push_str(&comment(field.doc(), "  "));
+        let name = snake_case(field.name());
+        match field.schema() {
+            Schema::Array(inner) => {
+                let type_ = scalar_type(record, field, inner)?;
+                let number = numbers.next(field)?;
+                let _ = writeln!(out, "  repeated {} {} = {};", type_, name, number);
+            }
+            Schema::Union(types) => {
+                let non_null: Vec<&Schema> = types.iter().filter(|t| **t != Schema::Null).collect();
+                let nullable = non_null.len() < types.len();
+                match non_null.as_slice() {
+                    [Schema::Array(inner)] => {
+                        // Repeated fields can't be optional, an empty list stands in for null
+                        let type_ = scalar_type(record, field, inner)?;
+                        let number = numbers.next(field)?;
+                        let _ = writeln!(out, "  repeated {} {} = {};", type_, name, number);
+                    }
+                    [single] => {
+                        let type_ = scalar_type(record, field, single)?;
+                        let label = if nullable { "optional " } else { "" };
+                        let number = numbers.next(field)?;
+                        let _ = writeln!(out, "  {}{} {} = {};", label, type_, name, number);
+                    }
+                    branches => {
+                        let _ = writeln!(out, "  oneof {} {{", name);
+                        for branch in branches.iter() {
+                            let type_ = scalar_type(record, field, branch)?;
+                            let branch_name =
+                                format!("{}_{}", name, snake_case(short_name(branch.type_name())));
+                            let number = numbers.next(field)?;
+                            let _ = writeln!(out, "    {} {} = {};", type_, branch_name, number);
+                        }
+                        out.push_str("  }\n");
+                    }
+                }
+            }
+            schema => {
+                let type_ = scalar_type(record, field, schema)?;
+                let number = numbers.next(field)?;
+                let _ = writeln!(out, "  {} {} = {};", type_, name, number);
+            }
+        }
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+fn enumeration(enum_: &EnumSchema) -> String {
+    let prefix = snake_case(enum_.name()).to_ascii_uppercase();
+    let mut out = comment(enum_.doc(), "");
+    let _ = writeln!(out, "enum {} {{", enum_.name());
+    for (number, symbol) in enum_.symbols().iter().enumerate() {
+        let value = snake_case(symbol).to_ascii_uppercase();
+        let _ = writeln!(out, "  {}_{} = {};", prefix, value, number);
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Protobuf type of a schema which isn't a union or an array
+fn scalar_type(
+    record: &RecordSchema,
+    field: &FieldDef,
+    schema: &Schema,
+) -> Result<String, AvroError> {
+    Ok(match schema {
+        Schema::Boolean => "bool".to_string(),
+        Schema::Int => "int32".to_string(),
+        Schema::Long => "int64".to_string(),
+        Schema::Float => "float".to_string(),
+        Schema::Double => "double".to_string(),
+        Schema::String => "string".to_string(),
+        // Every type is in the same package, so references use the name without namespace
+        Schema::Record(name) | Schema::Enum(name) => short_name(name).to_string(),
+        _ => {
+            return Err(AvroError::InvalidASTDataType(format!(
+                "Field '{}' of record '{}' has a type which can't be expressed in protobuf",
+                field.name(),
+                record.name()
+            )))
+        }
+    })
+}
+
+/// Assigns field numbers of a message, taking the annotated numbers first
+struct FieldNumbers<'a> {
+    record: &'a RecordSchema,
+    /// Numbers set through annotations, which automatic numbers skip
+    annotated: HashSet<u64>,
+    /// Fields whose annotated number was handed out already
+    used_annotations: HashSet<&'a str>,
+    next: u64,
+}
+
+impl<'a> FieldNumbers<'a> {
+    fn new(record: &'a RecordSchema) -> Result<Self, AvroError> {
+        let mut annotated = HashSet::new();
+        for field in record.fields() {
+            let Some(value) = field.properties().get(FIELD_ANNOTATION) else {
+                continue;
+            };
+            let number = value
+                .as_u64()
+                .filter(|n| (1..=MAX_NUMBER).contains(n) && !RESERVED_NUMBERS.contains(n))
+                .ok_or_else(|| {
+                    AvroError::InvalidASTDataType(format!(
+                        "Invalid field number {} of field '{}' in record '{}'",
+                        value,
+                        field.name(),
+                        record.name()
+                    ))
+                })?;
+            if !annotated.insert(number) {
+                return Err(AvroError::InvalidASTDataType(format!(
+                    "Field number {} is used more than once in record '{}'",
+                    number,
+                    record.name()
+                )));
+            }
+        }
+        Ok(Self {
+            record,
+            annotated,
+            used_annotations: HashSet::new(),
+            next: 1,
+        })
+    }
+
+    /// Number of the field, or of the next branch of its oneof
+    fn next(&mut self, field: &'a FieldDef) -> Result<u64, AvroError> {
+        if let Some(number) = field
+            .properties()
+            .get(FIELD_ANNOTATION)
+            .and_then(|n| n.as_u64())
+        {
+            if self.used_annotations.insert(field.name()) {
+                return Ok(number);
+            }
+        }
+        while self.annotated.contains(&self.next) || RESERVED_NUMBERS.contains(&self.next) {
+            self.next += 1;
+        }
+        if self.next > MAX_NUMBER {
+            return Err(AvroError::InvalidASTDataType(format!(
+                "Record '{}' has too many fields for protobuf",
+                self.record.name()
+            )));
+        }
+        self.next += 1;
+        Ok(self.next - 1)
+    }
+}
+
+fn comment(doc: Option<&str>, indent: &str) -> String {
+    let mut out = String::new();
+    if let Some(doc) = doc {
+        for line in doc_lines(doc) {
+            if line.is_empty() {
+                let _ = writeln!(out, "{}//", indent);
+            } else {
+                let _ = writeln!(out, "{}// {}", indent, line);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProtobufGenerator;
+    use crate::compiler::IdlCompiler;
+
+    #[test]
+    fn test_generate_protobuf() {
+        let src = "@namespace(\"org.example\")
+protocol Event {
+  enum Meal { DINNER, LUNCH }
+  /** A person */
+  record Person {
+    string firstName;
+    int? @proto_field(2) age;
+    array<Meal> meals;
+    union { long, string, Person } id;
+    boolean @proto_field(1) active;
+  }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        let expected = "// Generated by avro-idl, do not edit
+syntax = \"proto3\";
+
+package org.example;
+
+enum Meal {
+  MEAL_DINNER = 0;
+  MEAL_LUNCH = 1;
+}
+
+// A person
+message Person {
+  string first_name = 3;
+  optional int32 age = 2;
+  repeated Meal meals = 4;
+  oneof id {
+    int64 id_long = 5;
+    string id_string = 6;
+    Person id_person = 7;
+  }
+  bool active = 1;
+}
+";
+        assert_eq!(
+            ProtobufGenerator::new(protocol).generate().unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_duplicate_field_number() {
+        let src = "protocol Event {
+  record Person {
+    string @proto_field(1) name;
+    int @proto_field(1) age;
+  }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        assert_eq!(
+            ProtobufGenerator::new(protocol)
+                .generate()
+                .unwrap_err()
+                .to_string(),
+            "Field number 1 is used more than once in record 'Person'"
+        );
+    }
+
+    #[test]
+    fn test_same_name_in_other_namespace() {
+        let src = "@namespace(\"org.example\")
+protocol Event {
+  record Person { string name; }
+  @namespace(\"org.other\") record Person { int id; }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        assert_eq!(
+            ProtobufGenerator::new(protocol)
+                .generate()
+                .unwrap_err()
+                .to_string(),
+            "Types 'org.example.Person' and 'org.other.Person' have the same name, which protobuf can't tell apart in a single package"
+        );
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use super::{dependency_order, doc_lines, short_name};
+use crate::ast::Field;
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, FieldDef, NamedSchema, RecordSchema, Schema};
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Generates a Python module with a dataclass, or a pydantic model, per record and an
+/// `enum.Enum` per enum. Types are ordered so that referenced types come first, and
+/// annotations are postponed so that cycles resolve as well. Fields keep their Avro names,
+/// where Python keywords get a trailing underscore.
+pub struct PythonGenerator {
+    field: Field,
+    pydantic: bool,
+}
+
+impl PythonGenerator {
+    pub fn new(field: Field) -> Self {
+        Self {
+            field,
+            pydantic: false,
+        }
+    }
+
+    /// Generate pydantic models instead of dataclasses
+    pub fn with_pydantic(mut self, pydantic: bool) -> Self {
+        self.pydantic = pydantic;
+        self
+    }
+
+    pub fn generate(self) -> Result<String, AvroError> {
+        let document = Document::try_from(self.field)?;
+        let mut writer = PythonWriter {
+            pydantic: self.pydantic,
+            out: String::new(),
+            typing: BTreeSet::new(),
+            uses_enum: false,
+            uses_field: false,
+        };
+        let (ordered, rebuilt) = dependency_order(&document);
+        for named in ordered.iter() {
+            writer.out.push_str("\n\n");
+            match named {
+                NamedSchema::Record(record) => writer.record(record)?,
+                NamedSchema::Enum(enum_) => writer.enumeration(enum_),
+            }
+        }
+        // Models referencing types defined after them are completed once all are defined
+        if self.pydantic && !rebuilt.is_empty() {
+            writer.out.push_str("\n\n");
+            for name in rebuilt.iter() {
+                let _ = writeln!(writer.out, "{}.model_rebuild()", name);
+            }
+        }
+        Ok(writer.header() + &writer.out)
+    }
+}
+
+struct PythonWriter {
+    pydantic: bool,
+    out: String,
+    /// Names imported from `typing`
+    typing: BTreeSet<&'static str>,
+    uses_enum: bool,
+    uses_field: bool,
+}
+
+impl PythonWriter {
+    fn header(&self) -> String {
+        let mut header =
+            "# Generated by avro-idl, do not edit\nfrom __future__ import annotations\n\n"
+                .to_string();
+        if self.uses_enum {
+            header.push_str("import enum\n");
+        }
+        if !self.pydantic {
+            let imports = if self.uses_field {
+                "dataclass, field"
+            } else {
+                "dataclass"
+            };
+            let _ = writeln!(header, "from dataclasses import {}", imports);
+        }
+        if !self.typing.is_empty() {
+            let typing: Vec<&str> = self.typing.iter().copied().collect();
+            let _ = writeln!(header, "from typing import {}", typing.join(", "));
+        }
+        if self.pydantic {
+            let imports = if self.uses_field {
+                "BaseModel, Field"
+            } else {
+                "BaseModel"
+            };
+            let _ = writeln!(header, "\nfrom pydantic import {}", imports);
+        }
+        header
+    }
+
+    fn record(&mut self, record: &RecordSchema) -> Result<(), AvroError> {
+        if self.pydantic {
+            let _ = writeln!(self.out, "class {}(BaseModel):", record.name());
+        } else {
+            // Keyword only, so fields without defaults can follow fields with defaults
+            let _ = writeln!(
+                self.out,
+                "@dataclass(kw_only=True)\nclass {}:",
+                record.name()
+            );
+        }
+        if let Some(doc) = record.doc() {
+            self.docstring(doc);
+            if !record.fields().is_empty() {
+                self.out.push('\n');
+            }
+        } else if record.fields().is_empty() {
+            self.out.push_str("    pass\n");
+        }
+        for field in record.fields() {
+            self.field(field)?;
+        }
+        Ok(())
+    }
+
+    fn field(&mut self, field: &FieldDef) -> Result<(), AvroError> {
+        let name = identifier(field.name());
+        let type_ = self.python_type(field.schema());
+        let default = field
+            .default()
+            .map(|default| self.default(field, field.schema(), default))
+            .transpose()?;
+        let mutable = matches!(field.default(), Some(Value::Array(_)));
+
+        let value = match (default, self.pydantic) {
+            (Some(default), _) if mutable => {
+                self.uses_field = true;
+                let factory = if default == "[]" {
+                    "list".to_string()
+                } else {
+                    format!("lambda: {}", default)
+                };
+                let function = if self.pydantic { "Field" } else { "field" };
+                Some(format!(
+                    "{}(default_factory={}{})",
+                    function,
+                    factory,
+                    self.alias(field)
+                ))
+            }
+            (Some(default), true) if name != field.name() => {
+                self.uses_field = true;
+                Some(format!("Field({}{})", default, self.alias(field)))
+            }
+            (None, true) if name != field.name() => {
+                self.uses_field = true;
+                Some(format!("Field(alias=\"{}\")", field.name()))
+            }
+            (default, _) => default,
+        };
+        match value {
+            Some(value) => {
+                let _ = writeln!(self.out, "    {}: {} = {}", name, type_, value);
+            }
+            None => {
+                let _ = writeln!(self.out, "    {}: {}", name, type_);
+            }
+        }
+        if let Some(doc) = field.doc() {
+            self.docstring(doc);
+        }
+        Ok(())
+    }
+
+    /// Alias of a pydantic field whose name had to be escaped
+    fn alias(&self, field: &FieldDef) -> String {
+        if self.pydantic && identifier(field.name()) != field.name() {
+            format!(", alias=\"{}\"", field.name())
+        } else {
+            String::new()
+        }
+    }
+
+    fn enumeration(&mut self, enum_: &EnumSchema) {
+        self.uses_enum = true;
+        let _ = writeln!(self.out, "class {}(enum.Enum):", enum_.name());
+        if let Some(doc) = enum_.doc() {
+            self.docstring(doc);
+            self.out.push('\n');
+        }
+        for symbol in enum_.symbols() {
+            let _ = writeln!(self.out, "    {} = \"{}\"", identifier(symbol), symbol);
+        }
+        // Unknown symbols are read as the default, like Avro does
+        if let Some(default) = enum_.default() {
+            let _ = write!(
+                self.out,
+                "\n    @classmethod\n    def _missing_(cls, value):\n        return cls.{}\n",
+                identifier(default)
+            );
+        }
+        if enum_.symbols().is_empty() && enum_.doc().is_none() {
+            self.out.push_str("    pass\n");
+        }
+    }
+
+    fn python_type(&mut self, schema: &Schema) -> String {
+        match schema {
+            Schema::Null => "None".to_string(),
+            Schema::Boolean => "bool".to_string(),
+            Schema::Int | Schema::Long => "int".to_string(),
+            Schema::Float | Schema::Double => "float".to_string(),
+            Schema::String => "str".to_string(),
+            Schema::Array(inner) => {
+                self.typing.insert("List");
+                format!("List[{}]", self.python_type(inner))
+            }
+            Schema::Record(name) | Schema::Enum(name) => short_name(name).to_string(),
+            Schema::Union(types) => {
+                let non_null: Vec<String> = types
+                    .iter()
+                    .filter(|t| **t != Schema::Null)
+                    .map(|t| self.python_type(t))
+                    .collect();
+                let nullable = non_null.len() < types.len();
+                let inner = match non_null.as_slice() {
+                    [] => return "None".to_string(),
+                    [single] => single.clone(),
+                    _ => {
+                        self.typing.insert("Union");
+                        format!("Union[{}]", non_null.join(", "))
+                    }
+                };
+                if nullable {
+                    self.typing.insert("Optional");
+                    format!("Optional[{}]", inner)
+                } else {
+                    inner
+                }
+            }
+        }
+    }
+
+    /// Python expression of a default
+    fn default(
+        &self,
+        field: &FieldDef,
+        schema: &Schema,
+        value: &Value,
+    ) -> Result<String, AvroError> {
+        Ok(match (schema, value) {
+            (_, Value::Null) => "None".to_string(),
+            (Schema::Boolean, Value::Bool(true)) => "True".to_string(),
+            (Schema::Boolean, Value::Bool(false)) => "False".to_string(),
+            (Schema::Int | Schema::Long, Value::Number(value)) => value.to_string(),
+            (Schema::Float, Value::Number(value)) => {
+                format!("{:?}", value.as_f64().unwrap_or_default() as f32)
+            }
+            (Schema::Double, Value::Number(value)) => {
+                format!("{:?}", value.as_f64().unwrap_or_default())
+            }
+            // JSON string literals are valid Python string literals
+            (Schema::String, Value::String(_)) => value.to_string(),
+            (Schema::Enum(name), Value::String(symbol)) => {
+                format!("{}.{}", short_name(name), identifier(symbol))
+            }
+            (Schema::Array(inner), Value::Array(items)) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.default(field, inner, item))
+                    .collect::<Result<Vec<String>, AvroError>>()?;
+                format!("[{}]", items.join(", "))
+            }
+            // Defaults of unions are of the first type
+            (Schema::Union(types), value) if !types.is_empty() => {
+                self.default(field, &types[0], value)?
+            }
+            _ => {
+                return Err(AvroError::InvalidASTDataType(format!(
+                    "Default of field '{}' can't be expressed in Python",
+                    field.name()
+                )))
+            }
+        })
+    }
+
+    fn docstring(&mut self, doc: &str) {
+        let doc = doc.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"");
+        let lines = doc_lines(&doc);
+        if lines.len() == 1 {
+            let _ = writeln!(self.out, "    \"\"\"{}\"\"\"", lines[0]);
+        } else {
+            self.out.push_str("    \"\"\"\n");
+            for line in lines {
+                if line.is_empty() {
+                    self.out.push('\n');
+                } else {
+                    let _ = writeln!(self.out, "    {}", line);
+                }
+            }
+            self.out.push_str("    \"\"\"\n");
+        }
+    }
+}
+
+/// Escape Python keywords with a trailing underscore
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PythonGenerator;
+    use crate::compiler::IdlCompiler;
+
+    const SRC: &str = "protocol Event {
+  /** A person */
+  record Person {
+    string name = \"Bob\";
+    union { null, Address } address = null;
+    array<Meal> meals;
+    /** Friends of the person */
+    array<Person> friends;
+    union { long, string } id;
+    boolean from;
+  }
+  enum Meal { DINNER, LUNCH } = LUNCH;
+  record Address {
+    string city;
+  }
+}";
+
+    #[test]
+    fn test_generate_dataclasses() {
+        let protocol = IdlCompiler::new().compile(SRC).unwrap();
+        let expected = r#"# Generated by avro-idl, do not edit
+from __future__ import annotations
+
+import enum
+from dataclasses import dataclass
+from typing import List, Optional, Union
+
+
+@dataclass(kw_only=True)
+class Address:
+    city: str
+
+
+class Meal(enum.Enum):
+    DINNER = "DINNER"
+    LUNCH = "LUNCH"
+
+    @classmethod
+    def _missing_(cls, value):
+        return cls.LUNCH
+
+
+@dataclass(kw_only=True)
+class Person:
+    """A person"""
+
+    name: str = "Bob"
+    address: Optional[Address] = None
+    meals: List[Meal]
+    friends: List[Person]
+    """Friends of the person"""
+    id: Union[int, str]
+    from_: bool
+"#;
+        assert_eq!(PythonGenerator::new(protocol).generate().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_generate_pydantic() {
+        let protocol = IdlCompiler::new().compile(SRC).unwrap();
+        let generated = PythonGenerator::new(protocol)
+            .with_pydantic(true)
+            .generate()
+            .unwrap();
+        assert!(generated.contains("from pydantic import BaseModel, Field\n"));
+        assert!(generated.contains("class Person(BaseModel):\n"));
+        assert!(generated.contains("    from_: bool = Field(alias=\"from\")\n"));
+        assert!(generated.ends_with("\n\nPerson.model_rebuild()\n"));
+    }
+
+    #[test]
+    fn test_namespaced_enum_default() {
+        let src = "@namespace(\"org.example\")
+protocol Event {
+  @namespace(\"org.meals\")
+  enum Meal { DINNER, LUNCH }
+  record Person {
+    union { org.meals.Meal, null } meal = \"LUNCH\";
+  }
+}";
+        for pydantic in [false, true] {
+            let protocol = IdlCompiler::new().compile(src).unwrap();
+            let generated = PythonGenerator::new(protocol)
+                .with_pydantic(pydantic)
+                .generate()
+                .unwrap();
+            assert!(generated.contains("    meal: Optional[Meal] = Meal.LUNCH\n"));
+        }
+    }
+}
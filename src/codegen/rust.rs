@@ -0,0 +1,410 @@
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use super::{doc_lines, pascal_case, short_name, snake_case};
+use crate::ast::Field;
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, FieldDef, NamedSchema, RecordSchema, Schema};
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// Generates Rust structs and enums which (de)serialize with serde. Records become structs,
+/// enums become enums and unions become enums with one variant per type, tagged with the
+/// Avro type name, where a union with null becomes an `Option`. Records referencing
+/// themselves or types declared after them are boxed, which breaks every cycle.
+pub struct RustGenerator {
+    field: Field,
+}
+
+impl RustGenerator {
+    pub fn new(field: Field) -> Self {
+        Self { field }
+    }
+
+    pub fn generate(self) -> Result<String, AvroError> {
+        let document = Document::try_from(self.field)?;
+        let mut writer = RustWriter {
+            document: &document,
+            out: String::new(),
+            unions: vec![],
+        };
+        writer.out.push_str(
+            "// Generated by avro-idl, do not edit\n\nuse serde::{Deserialize, Serialize};\n",
+        );
+        for (index, named) in document.types().iter().enumerate() {
+            writer.out.push('\n');
+            match named {
+                NamedSchema::Record(record) => writer.record(record, index)?,
+                NamedSchema::Enum(enum_) => writer.enumeration(enum_),
+            }
+        }
+        Ok(writer.out)
+    }
+}
+
+struct RustWriter<'a> {
+    document: &'a Document,
+    out: String,
+    /// Enums generated for unions of the current record, written after it
+    unions: Vec<String>,
+}
+
+impl RustWriter<'_> {
+    fn record(&mut self, record: &RecordSchema, index: usize) -> Result<(), AvroError> {
+        self.doc(record.doc(), "");
+        self.out
+            .push_str("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+        let _ = writeln!(self.out, "pub struct {} {{", record.name());
+
+        let mut defaults = vec![];
+        for field in record.fields() {
+            let union_name = format!("{}{}", record.name(), pascal_case(field.name()));
+            let type_ = self.rust_type(field.schema(), &union_name, index, false);
+            let ident = identifier(&snake_case(field.name()));
+
+            self.doc(field.doc(), "    ");
+            if ident.trim_start_matches("r#") != field.name() {
+                let _ = writeln!(self.out, "    #[serde(rename = \"{}\")]", field.name());
+            }
+            match field.default() {
+                Some(Value::Null) if matches!(field.schema(), Schema::Union(_)) => {
+                    self.out.push_str("    #[serde(default)]\n");
+                }
+                Some(default) => {
+                    let function = format!("default_{}", ident.trim_start_matches("r#"));
+                    let value = self.default(field, field.schema(), default, &union_name)?;
+                    let _ = writeln!(
+                        self.out,
+                        "    #[serde(default = \"{}::{}\")]",
+                        record.name(),
+                        function
+                    );
+                    defaults.push((function, type_.clone(), value));
+                }
+                None => {}
+            }
+            let _ = writeln!(self.out, "    pub {}: {},", ident, type_);
+        }
+        self.out.push_str("}\n");
+
+        if !defaults.is_empty() {
+            let _ = writeln!(self.out, "\nimpl {} {{", record.name());
+            for (i, (function, type_, value)) in defaults.iter().enumerate() {
+                if i > 0 {
+                    self.out.push('\n');
+                }
+                let _ = writeln!(
+                    self.out,
+                    "    fn {}() -> {} {{\n        {}\n    }}",
+                    function, type_, value
+                );
+            }
+            self.out.push_str("}\n");
+        }
+
+        for union in std::mem::take(&mut self.unions) {
+            self.out.push('\n');
+            self.out.push_str(&union);
+        }
+        Ok(())
+    }
+
+    fn enumeration(&mut self, enum_: &EnumSchema) {
+        self.doc(enum_.doc(), "");
+        let derive_default = if enum_.default().is_some() {
+            ", Default"
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            self.out,
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash{}, Serialize, Deserialize)]",
+            derive_default
+        );
+        let _ = writeln!(self.out, "pub enum {} {{", enum_.name());
+        for symbol in enum_.symbols() {
+            let variant = pascal_case(symbol);
+            if variant != *symbol {
+                let _ = writeln!(self.out, "    #[serde(rename = \"{}\")]", symbol);
+            }
+            // Unknown symbols are read as the default, like Avro does
+            if enum_.default() == Some(symbol.as_str()) {
+                self.out.push_str("    #[default]\n    #[serde(other)]\n");
+            }
+            let _ = writeln!(self.out, "    {},", variant);
+        }
+        self.out.push_str("}\n");
+    }
+
+    /// Rust type of a schema. Unions which aren't just nullable become enums named after
+    /// `union_name`, records declared at or after `index` are boxed unless in a `Vec`.
+    fn rust_type(
+        &mut self,
+        schema: &Schema,
+        union_name: &str,
+        index: usize,
+        in_vec: bool,
+    ) -> String {
+        match schema {
+            Schema::Null => "()".to_string(),
+            Schema::Boolean => "bool".to_string(),
+            Schema::Int => "i32".to_string(),
+            Schema::Long => "i64".to_string(),
+            Schema::Float => "f32".to_string(),
+            Schema::Double => "f64".to_string(),
+            Schema::String => "String".to_string(),
+            Schema::Array(inner) => format!(
+                "Vec<{}>",
+                self.rust_type(inner, &format!("{}Item", union_name), index, true)
+            ),
+            Schema::Enum(name) => short_name(name).to_string(),
+            Schema::Record(fullname) => {
+                let name = short_name(fullname);
+                let position = self
+                    .document
+                    .types()
+                    .iter()
+                    .position(|named| named.fullname() == *fullname);
+                if !in_vec && position.is_some_and(|position| position >= index) {
+                    format!("Box<{}>", name)
+                } else {
+                    name.to_string()
+                }
+            }
+            Schema::Union(types) => {
+                let non_null: Vec<&Schema> = types.iter().filter(|t| **t != Schema::Null).collect();
+                let nullable = non_null.len() < types.len();
+                let inner = match non_null.as_slice() {
+                    [] => "()".to_string(),
+                    [single] => self.rust_type(single, union_name, index, false),
+                    _ => {
+                        self.union_enum(&non_null, union_name, index);
+                        union_name.to_string()
+                    }
+                };
+                if nullable {
+                    format!("Option<{}>", inner)
+                } else {
+                    inner
+                }
+            }
+        }
+    }
+
+    fn union_enum(&mut self, types: &[&Schema], name: &str, index: usize) {
+        let mut variants = String::new();
+        for schema in types.iter() {
+            let type_ = self.rust_type(schema, &format!("{}Item", name), index, false);
+            let (variant, tag) = self.variant(schema);
+            let _ = writeln!(variants, "    #[serde(rename = \"{}\")]", tag);
+            let _ = writeln!(variants, "    {}({}),", variant, type_);
+        }
+        self.unions.push(format!(
+            "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub enum {} {{\n{}}}\n",
+            name, variants
+        ));
+    }
+
+    /// Variant name of a union branch and the type name tagging it in the Avro JSON encoding
+    fn variant(&self, schema: &Schema) -> (String, String) {
+        match schema {
+            Schema::Record(name) | Schema::Enum(name) => {
+                (short_name(name).to_string(), name.clone())
+            }
+            _ => (
+                pascal_case(schema.type_name()),
+                schema.type_name().to_string(),
+            ),
+        }
+    }
+
+    /// Rust expression of a default
+    fn default(
+        &self,
+        field: &FieldDef,
+        schema: &Schema,
+        value: &Value,
+        union_name: &str,
+    ) -> Result<String, AvroError> {
+        let unsupported = || {
+            AvroError::InvalidASTDataType(format!(
+                "Default of field '{}' can't be expressed in Rust",
+                field.name()
+            ))
+        };
+        Ok(match (schema, value) {
+            (Schema::Null, Value::Null) => "()".to_string(),
+            (Schema::Boolean, Value::Bool(value)) => value.to_string(),
+            (Schema::Int | Schema::Long, Value::Number(value)) => value.to_string(),
+            (Schema::Float, Value::Number(value)) => {
+                format!("{:?}", value.as_f64().ok_or_else(unsupported)? as f32)
+            }
+            (Schema::Double, Value::Number(value)) => {
+                format!("{:?}", value.as_f64().ok_or_else(unsupported)?)
+            }
+            (Schema::String, Value::String(value)) => format!("{:?}.to_string()", value),
+            (Schema::Enum(name), Value::String(symbol)) => {
+                format!("{}::{}", short_name(name), pascal_case(symbol))
+            }
+            (Schema::Array(inner), Value::Array(items)) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.default(field, inner, item, &format!("{}Item", union_name)))
+                    .collect::<Result<Vec<String>, AvroError>>()?;
+                format!("vec![{}]", items.join(", "))
+            }
+            (Schema::Union(types), value) => {
+                let non_null: Vec<&Schema> = types.iter().filter(|t| **t != Schema::Null).collect();
+                let nullable = non_null.len() < types.len();
+                // Defaults of unions are of the first type
+                let first = types.first().ok_or_else(unsupported)?;
+                if *first == Schema::Null {
+                    "None".to_string()
+                } else {
+                    let inner = self.default(field, first, value, union_name)?;
+                    let inner = if non_null.len() > 1 {
+                        format!("{}::{}({})", union_name, self.variant(first).0, inner)
+                    } else {
+                        inner
+                    };
+                    if nullable {
+                        format!("Some({})", inner)
+                    } else {
+                        inner
+                    }
+                }
+            }
+            _ => return Err(unsupported()),
+        })
+    }
+
+    fn doc(&mut self, doc: Option<&str>, indent: &str) {
+        if let Some(doc) = doc {
+            for line in doc_lines(doc) {
+                let _ = writeln!(
+                    self.out,
+                    "{}///{}{}",
+                    indent,
+                    if line.is_empty() { "" } else { " " },
+                    line
+                );
+            }
+        }
+    }
+}
+
+/// Escape Rust keywords as raw identifiers
+fn identifier(name: &str) -> String {
+    match name {
+        "self" | "super" | "crate" => format!("{}_", name),
+        name if KEYWORDS.contains(&name) => format!("r#{}", name),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RustGenerator;
+    use crate::compiler::IdlCompiler;
+
+    #[test]
+    fn test_generate_rust() {
+        let src = "protocol Event {
+  enum Meal { DINNER, LUNCH } = LUNCH;
+  /** A person */
+  record Person {
+    string firstName = \"Bob\";
+    int? age = null;
+    float height = 1.8;
+    union { null, Meal } meal = null;
+    array<Person> friends;
+    union { null, Person } partner;
+    union { long, string } id = 1;
+    string type;
+  }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        let expected = r#"// Generated by avro-idl, do not edit
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Meal {
+    #[serde(rename = "DINNER")]
+    Dinner,
+    #[serde(rename = "LUNCH")]
+    #[default]
+    #[serde(other)]
+    Lunch,
+}
+
+/// A person
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Person {
+    #[serde(rename = "firstName")]
+    #[serde(default = "Person::default_first_name")]
+    pub first_name: String,
+    #[serde(default)]
+    pub age: Option<i32>,
+    #[serde(default = "Person::default_height")]
+    pub height: f32,
+    #[serde(default)]
+    pub meal: Option<Meal>,
+    pub friends: Vec<Person>,
+    pub partner: Option<Box<Person>>,
+    #[serde(default = "Person::default_id")]
+    pub id: PersonId,
+    pub r#type: String,
+}
+
+impl Person {
+    fn default_first_name() -> String {
+        "Bob".to_string()
+    }
+
+    fn default_height() -> f32 {
+        1.8
+    }
+
+    fn default_id() -> PersonId {
+        PersonId::Long(1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PersonId {
+    #[serde(rename = "long")]
+    Long(i64),
+    #[serde(rename = "string")]
+    String(String),
+}
+"#;
+        assert_eq!(RustGenerator::new(protocol).generate().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_namespaced_enum_default() {
+        let src = "@namespace(\"org.example\")
+protocol Event {
+  @namespace(\"org.meals\")
+  enum Meal { DINNER, LUNCH }
+  record Person {
+    union { org.meals.Meal, null } meal = \"LUNCH\";
+  }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        let generated = RustGenerator::new(protocol).generate().unwrap();
+        assert!(generated.contains(
+            "    fn default_meal() -> Option<Meal> {
+        Some(Meal::Lunch)
+    }"
+        ));
+    }
+}
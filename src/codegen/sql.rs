@@ -0,0 +1,628 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use serde_json::Value;
+
+use super::snake_case;
+use crate::ast::{Field, Properties};
+use crate::error::AvroError;
+use crate::schema::{Document, NamedSchema, RecordSchema, Schema};
+
+/// Words which are quoted when used as table or column names
+const RESERVED: &[&str] = &[
+    "all",
+    "and",
+    "as",
+    "asc",
+    "between",
+    "by",
+    "case",
+    "check",
+    "column",
+    "constraint",
+    "create",
+    "default",
+    "desc",
+    "distinct",
+    "else",
+    "end",
+    "false",
+    "from",
+    "group",
+    "having",
+    "in",
+    "index",
+    "is",
+    "join",
+    "key",
+    "like",
+    "limit",
+    "not",
+    "null",
+    "offset",
+    "on",
+    "or",
+    "order",
+    "primary",
+    "references",
+    "select",
+    "table",
+    "then",
+    "to",
+    "true",
+    "union",
+    "unique",
+    "user",
+    "when",
+    "where",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum SqlDialect {
+    Postgres,
+    #[value(name = "bigquery")]
+    BigQuery,
+    Sqlite,
+}
+
+/// Generates a `CREATE TABLE` statement per top-level record, i.e. per record which isn't
+/// used by another record, or only by records it uses in turn. Nested records become `STRUCT`s in BigQuery and are flattened
+/// into columns prefixed with the field name otherwise. Arrays become arrays where the
+/// dialect has them and JSON otherwise, as do unions of several types. Logical types set
+/// through `@logicalType` map to the native date, time, timestamp, decimal and uuid types.
+pub struct SqlGenerator {
+    field: Field,
+    dialect: SqlDialect,
+}
+
+impl SqlGenerator {
+    pub fn new(field: Field) -> Self {
+        Self {
+            field,
+            dialect: SqlDialect::Postgres,
+        }
+    }
+
+    pub fn with_dialect(mut self, dialect: SqlDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn generate(self) -> Result<String, AvroError> {
+        let document = Document::try_from(self.field)?;
+        let writer = SqlWriter {
+            document: &document,
+            dialect: self.dialect,
+        };
+
+        // Records in a cycle reference each other, so none of them is nested in the others
+        let mut nested = HashSet::new();
+        for named in document.types() {
+            if let NamedSchema::Record(record) = named {
+                for name in referenced_names(record) {
+                    if let Some(NamedSchema::Record(referenced)) = document.named_type(&name) {
+                        if !reaches(
+                            &document,
+                            referenced,
+                            &record.fullname(),
+                            &mut HashSet::new(),
+                        ) {
+                            nested.insert(referenced.fullname());
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut out = "-- Generated by avro-idl, do not edit\n".to_string();
+        for named in document.types() {
+            if let NamedSchema::Record(record) = named {
+                if !nested.contains(&record.fullname()) {
+                    out.push('\n');
+                    out.push_str(&writer.table(record)?);
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+struct Column {
+    name: String,
+    type_: String,
+    nullable: bool,
+    doc: Option<String>,
+}
+
+struct SqlWriter<'a> {
+    document: &'a Document,
+    dialect: SqlDialect,
+}
+
+impl SqlWriter<'_> {
+    /// Constraint of a column which isn't nullable. BigQuery arrays are never null and
+    /// reject the constraint.
+    fn not_null(&self, type_: &str, nullable: bool) -> &'static str {
+        match self.dialect {
+            _ if nullable => "",
+            SqlDialect::BigQuery if type_.starts_with("ARRAY<") => "",
+            _ => " NOT NULL",
+        }
+    }
+
+    fn table(&self, record: &RecordSchema) -> Result<String, AvroError> {
+        let table = self.identifier(&snake_case(record.name()));
+        let columns = self.columns(record, "", false, &mut vec![record.fullname()])?;
+
+        let mut out = String::new();
+        if let (SqlDialect::Sqlite, Some(doc)) = (self.dialect, record.doc()) {
+            out.push_str(&line_comment(doc, ""));
+        }
+        let _ = writeln!(out, "CREATE TABLE {} (", table);
+        let definitions: Vec<String> = columns
+            .iter()
+            .map(|column| {
+                let mut definition = String::new();
+                if let (SqlDialect::Sqlite, Some(doc)) = (self.dialect, &column.doc) {
+                    definition.push_str(&line_comment(doc, "  "));
+                }
+                let _ = write!(definition, "  {} {}", column.name, column.type_);
+                definition.push_str(self.not_null(&column.type_, column.nullable));
+                if let (SqlDialect::BigQuery, Some(doc)) = (self.dialect, &column.doc) {
+                    let _ = write!(
+                        definition,
+                        " OPTIONS(description={})",
+                        Value::from(doc.as_str())
+                    );
+                }
+                definition
+            })
+            .collect();
+        let _ = writeln!(out, "{}", definitions.join(",\n"));
+        out.push(')');
+
+        match self.dialect {
+            SqlDialect::Postgres => {
+                out.push_str(";\n");
+                if let Some(doc) = record.doc() {
+                    let _ = writeln!(
+                        out,
+                        "COMMENT ON TABLE {} IS {};",
+                        table,
+                        string_literal(doc)
+                    );
+                }
+                for column in columns.iter() {
+                    if let Some(doc) = &column.doc {
+                        let _ = writeln!(
+                            out,
+                            "COMMENT ON COLUMN {}.{} IS {};",
+                            table,
+                            column.name,
+                            string_literal(doc)
+                        );
+                    }
+                }
+            }
+            SqlDialect::BigQuery => {
+                if let Some(doc) = record.doc() {
+                    let _ = write!(out, "\nOPTIONS(description={})", Value::from(doc));
+                }
+                out.push_str(";\n");
+            }
+            SqlDialect::Sqlite => out.push_str(";\n"),
+        }
+        Ok(out)
+    }
+
+    /// Columns of a record, where nested records are flattened unless the dialect has
+    /// structs. `stack` holds the records being flattened to detect recursion.
+    fn columns(
+        &self,
+        record: &RecordSchema,
+        prefix: &str,
+        nullable: bool,
+        stack: &mut Vec<String>,
+    ) -> Result<Vec<Column>, AvroError> {
+        let mut columns = vec![];
+        for field in record.fields() {
+            let name = format!("{}{}", prefix, snake_case(field.name()));
+            let (schema, field_nullable) = match field.schema() {
+                Schema::Union(types) => match types
+                    .iter()
+                    .filter(|t| **t != Schema::Null)
+                    .collect::<Vec<_>>()
+                    .as_slice()
+                {
+                    [single] => (*single, types.len() > 1),
+                    _ => (field.schema(), types.contains(&Schema::Null)),
+                },
+                schema => (schema, false),
+            };
+            let nullable = nullable || field_nullable;
+
+            let nested = match (schema, self.dialect) {
+                (Schema::Record(name), SqlDialect::Postgres | SqlDialect::Sqlite) => {
+                    self.record(name)
+                }
+                _ => None,
+            };
+            match nested {
+                Some(nested) => {
+                    self.enter(nested, stack)?;
+                    columns.extend(self.columns(nested, &format!("{}_", name), nullable, stack)?);
+                    stack.pop();
+                }
+                None => columns.push(Column {
+                    name: self.identifier(&name),
+                    type_: self.sql_type(schema, field.type_properties(), stack)?,
+                    nullable,
+                    doc: field.doc().map(String::from),
+                }),
+            }
+        }
+        Ok(columns)
+    }
+
+    fn sql_type(
+        &self,
+        schema: &Schema,
+        properties: &Properties,
+        stack: &mut Vec<String>,
+    ) -> Result<String, AvroError> {
+        if let Some(type_) = self.logical_type(properties) {
+            return Ok(type_);
+        }
+        let dialect = self.dialect;
+        Ok(match schema {
+            Schema::Boolean => match dialect {
+                SqlDialect::Postgres => "BOOLEAN",
+                SqlDialect::BigQuery => "BOOL",
+                SqlDialect::Sqlite => "INTEGER",
+            }
+            .to_string(),
+            Schema::Int => match dialect {
+                SqlDialect::Postgres | SqlDialect::Sqlite => "INTEGER",
+                SqlDialect::BigQuery => "INT64",
+            }
+            .to_string(),
+            Schema::Long => match dialect {
+                SqlDialect::Postgres => "BIGINT",
+                SqlDialect::BigQuery => "INT64",
+                SqlDialect::Sqlite => "INTEGER",
+            }
+            .to_string(),
+            Schema::Float => match dialect {
+                SqlDialect::Postgres | SqlDialect::Sqlite => "REAL",
+                SqlDialect::BigQuery => "FLOAT64",
+            }
+            .to_string(),
+            Schema::Double => match dialect {
+                SqlDialect::Postgres => "DOUBLE PRECISION",
+                SqlDialect::BigQuery => "FLOAT64",
+                SqlDialect::Sqlite => "REAL",
+            }
+            .to_string(),
+            Schema::String | Schema::Enum(_) => match dialect {
+                SqlDialect::Postgres | SqlDialect::Sqlite => "TEXT",
+                SqlDialect::BigQuery => "STRING",
+            }
+            .to_string(),
+            Schema::Array(inner) => match (dialect, &**inner) {
+                (SqlDialect::Postgres, inner)
+                    if inner.is_primitive() || matches!(inner, Schema::Enum(_)) =>
+                {
+                    format!("{}[]", self.sql_type(inner, &Properties::new(), stack)?)
+                }
+                (SqlDialect::BigQuery, inner)
+                    if !matches!(inner, Schema::Union(_) | Schema::Array(_)) =>
+                {
+                    format!(
+                        "ARRAY<{}>",
+                        self.sql_type(inner, &Properties::new(), stack)?
+                    )
+                }
+                _ => self.json_type(),
+            },
+            Schema::Record(name) if dialect == SqlDialect::BigQuery => {
+                let record = self.record(name).ok_or_else(|| {
+                    AvroError::UndefinedReference(
+                        format!("Field of type '{}' cannot be found!", name),
+                        None,
+                    )
+                })?;
+                self.enter(record, stack)?;
+                let fields = record
+                    .fields()
+                    .iter()
+                    .map(|field| {
+                        let (schema, nullable) = match field.schema() {
+                            Schema::Union(types)
+                                if types.len() == 2 && types.contains(&Schema::Null) =>
+                            {
+                                (
+                                    field.schema().nullable_inner().unwrap_or(field.schema()),
+                                    true,
+                                )
+                            }
+                            schema => (schema, false),
+                        };
+                        let type_ = self.sql_type(schema, field.type_properties(), stack)?;
+                        Ok(format!(
+                            "{} {}{}",
+                            self.identifier(&snake_case(field.name())),
+                            type_,
+                            self.not_null(&type_, nullable)
+                        ))
+                    })
+                    .collect::<Result<Vec<String>, AvroError>>()?;
+                stack.pop();
+                format!("STRUCT<{}>", fields.join(", "))
+            }
+            Schema::Null | Schema::Record(_) | Schema::Union(_) => self.json_type(),
+        })
+    }
+
+    /// Native type of a logical type e.g. `@logicalType("timestamp-millis")`
+    fn logical_type(&self, properties: &Properties) -> Option<String> {
+        let dialect = self.dialect;
+        let logical_type = properties.get("logicalType")?.as_str()?;
+        Some(
+            match (logical_type, dialect) {
+                ("date", _) => "DATE",
+                ("time-millis" | "time-micros", _) => "TIME",
+                ("timestamp-millis" | "timestamp-micros", SqlDialect::Postgres) => "TIMESTAMPTZ",
+                ("timestamp-millis" | "timestamp-micros", _) => "TIMESTAMP",
+                ("local-timestamp-millis" | "local-timestamp-micros", SqlDialect::BigQuery) => {
+                    "DATETIME"
+                }
+                ("local-timestamp-millis" | "local-timestamp-micros", _) => "TIMESTAMP",
+                ("uuid", SqlDialect::Postgres) => "UUID",
+                ("uuid", SqlDialect::BigQuery) => "STRING",
+                ("uuid", SqlDialect::Sqlite) => "TEXT",
+                ("decimal", _) => {
+                    let precision = properties.get("precision").and_then(|p| p.as_u64());
+                    let scale = properties
+                        .get("scale")
+                        .and_then(|s| s.as_u64())
+                        .unwrap_or(0);
+                    let name = match dialect {
+                        SqlDialect::Postgres | SqlDialect::BigQuery => "NUMERIC",
+                        SqlDialect::Sqlite => "DECIMAL",
+                    };
+                    return Some(match precision {
+                        Some(precision) => format!("{}({}, {})", name, precision, scale),
+                        None => name.to_string(),
+                    });
+                }
+                _ => return None,
+            }
+            .to_string(),
+        )
+    }
+
+    fn json_type(&self) -> String {
+        match self.dialect {
+            SqlDialect::Postgres => "JSONB",
+            SqlDialect::BigQuery => "JSON",
+            SqlDialect::Sqlite => "TEXT",
+        }
+        .to_string()
+    }
+
+    fn record(&self, name: &str) -> Option<&RecordSchema> {
+        match self.document.named_type(name) {
+            Some(NamedSchema::Record(record)) => Some(record),
+            _ => None,
+        }
+    }
+
+    /// Track a nested record, failing if it contains itself
+    fn enter(&self, record: &RecordSchema, stack: &mut Vec<String>) -> Result<(), AvroError> {
+        if stack.contains(&record.fullname()) {
+            return Err(AvroError::InvalidASTDataType(format!(
+                "Record '{}' contains itself and can't be nested in a table",
+                record.name()
+            )));
+        }
+        stack.push(record.fullname());
+        Ok(())
+    }
+
+    fn identifier(&self, name: &str) -> String {
+        if RESERVED.contains(&name) {
+            match self.dialect {
+                SqlDialect::BigQuery => format!("`{}`", name),
+                SqlDialect::Postgres | SqlDialect::Sqlite => format!("\"{}\"", name),
+            }
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+/// Call `found` with the name of every record or enum a schema references
+fn references(schema: &Schema, found: &mut impl FnMut(&str)) {
+    match schema {
+        Schema::Record(name) | Schema::Enum(name) => found(name),
+        Schema::Array(inner) => references(inner, found),
+        Schema::Union(types) => types.iter().for_each(|t| references(t, found)),
+        _ => {}
+    }
+}
+
+fn referenced_names(record: &RecordSchema) -> Vec<String> {
+    let mut names = vec![];
+    for field in record.fields() {
+        references(field.schema(), &mut |name| names.push(name.to_string()));
+    }
+    names
+}
+
+/// Whether a record is or references the record with the full name, directly or through
+/// other records
+fn reaches(
+    document: &Document,
+    record: &RecordSchema,
+    fullname: &str,
+    visited: &mut HashSet<String>,
+) -> bool {
+    if record.fullname() == fullname {
+        return true;
+    }
+    if !visited.insert(record.fullname()) {
+        return false;
+    }
+    referenced_names(record)
+        .iter()
+        .any(|name| match document.named_type(name) {
+            Some(NamedSchema::Record(referenced)) => {
+                reaches(document, referenced, fullname, visited)
+            }
+            _ => false,
+        })
+}
+
+fn string_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn line_comment(doc: &str, indent: &str) -> String {
+    doc.lines()
+        .map(|line| format!("{}-- {}\n", indent, line.trim()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SqlDialect, SqlGenerator};
+    use crate::compiler::IdlCompiler;
+
+    const SRC: &str = "protocol Event {
+  record Address {
+    string city;
+    string? zip;
+  }
+  /** A person's details */
+  record Person {
+    /** Full name */
+    string name;
+    int? age;
+    union { null, Address } address;
+    array<string> tags;
+    @logicalType(\"timestamp-millis\") long created;
+    @logicalType(\"decimal\") @precision(10) @scale(2) long balance;
+    string order;
+  }
+}";
+
+    #[test]
+    fn test_generate_postgres() {
+        let protocol = IdlCompiler::new().compile(SRC).unwrap();
+        let expected = "-- Generated by avro-idl, do not edit
+
+CREATE TABLE person (
+  name TEXT NOT NULL,
+  age INTEGER,
+  address_city TEXT,
+  address_zip TEXT,
+  tags TEXT[] NOT NULL,
+  created TIMESTAMPTZ NOT NULL,
+  balance NUMERIC(10, 2) NOT NULL,
+  \"order\" TEXT NOT NULL
+);
+COMMENT ON TABLE person IS 'A person''s details';
+COMMENT ON COLUMN person.name IS 'Full name';
+";
+        assert_eq!(SqlGenerator::new(protocol).generate().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_generate_bigquery() {
+        let protocol = IdlCompiler::new().compile(SRC).unwrap();
+        let expected = "-- Generated by avro-idl, do not edit
+
+CREATE TABLE person (
+  name STRING NOT NULL OPTIONS(description=\"Full name\"),
+  age INT64,
+  address STRUCT<city STRING NOT NULL, zip STRING>,
+  tags ARRAY<STRING>,
+  created TIMESTAMP NOT NULL,
+  balance NUMERIC(10, 2) NOT NULL,
+  `order` STRING NOT NULL
+)
+OPTIONS(description=\"A person's details\");
+";
+        let generated = SqlGenerator::new(protocol)
+            .with_dialect(SqlDialect::BigQuery)
+            .generate()
+            .unwrap();
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_recursive_record() {
+        let src = "protocol Event {
+  record Person {
+    union { null, Person } partner;
+  }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        let err = SqlGenerator::new(protocol)
+            .with_dialect(SqlDialect::Sqlite)
+            .generate()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Record 'Person' contains itself and can't be nested in a table"
+        );
+    }
+
+    #[test]
+    fn test_mutually_referencing_records() {
+        let src = "protocol Event {
+  record Person {
+    string name;
+    Address? home;
+  }
+  record Address {
+    string city;
+    Person? owner;
+  }
+}";
+        for dialect in [
+            SqlDialect::Postgres,
+            SqlDialect::BigQuery,
+            SqlDialect::Sqlite,
+        ] {
+            let protocol = IdlCompiler::new().compile(src).unwrap();
+            let err = SqlGenerator::new(protocol)
+                .with_dialect(dialect)
+                .generate()
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Record 'Person' contains itself and can't be nested in a table"
+            );
+        }
+
+        // Through an array the records aren't nested, so each gets a table
+        let src = src.replace("Address? home", "array<Address> homes");
+        let protocol = IdlCompiler::new().compile(&src).unwrap();
+        let expected = "-- Generated by avro-idl, do not edit
+
+CREATE TABLE person (
+  name TEXT NOT NULL,
+  homes JSONB NOT NULL
+);
+
+CREATE TABLE address (
+  city TEXT NOT NULL,
+  owner_name TEXT,
+  owner_homes JSONB
+);
+";
+        assert_eq!(SqlGenerator::new(protocol).generate().unwrap(), expected);
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use super::doc_lines;
+use crate::ast::Field;
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, NamedSchema, RecordSchema, Schema};
+
+/// Generates TypeScript declarations. Records become interfaces, enums become unions of
+/// string literals and unions become union types. Types are grouped into a `namespace`
+/// block per Avro namespace, and references to types of other namespaces are qualified.
+/// Longs become `number` like the other numeric types.
+pub struct TypeScriptGenerator {
+    field: Field,
+}
+
+impl TypeScriptGenerator {
+    pub fn new(field: Field) -> Self {
+        Self { field }
+    }
+
+    pub fn generate(self) -> Result<String, AvroError> {
+        let document = Document::try_from(self.field)?;
+
+        // Namespaces in order of their first type, types without a namespace first
+        let mut namespaces: Vec<Option<&str>> = vec![];
+        for named in document.types() {
+            if !namespaces.contains(&named.namespace()) {
+                namespaces.push(named.namespace());
+            }
+        }
+        namespaces.sort_by_key(|namespace| namespace.is_some());
+
+        let mut out = "// Generated by avro-idl, do not edit\n".to_string();
+        for namespace in namespaces {
+            let writer = TypeScriptWriter {
+                document: &document,
+                namespace,
+                indent: if namespace.is_some() { "  " } else { "" },
+            };
+            let declarations: Vec<String> = document
+                .types()
+                .iter()
+                .filter(|named| named.namespace() == namespace)
+                .map(|named| match named {
+                    NamedSchema::Record(record) => writer.record(record),
+                    NamedSchema::Enum(enum_) => writer.enumeration(enum_),
+                })
+                .collect();
+            out.push('\n');
+            match namespace {
+                Some(namespace) => {
+                    let _ = writeln!(out, "export namespace {} {{", namespace);
+                    out.push_str(&declarations.join("\n"));
+                    out.push_str("}\n");
+                }
+                None => out.push_str(&declarations.join("\n")),
+            }
+        }
+        Ok(out)
+    }
+}
+
+struct TypeScriptWriter<'a> {
+    document: &'a Document,
+    /// Namespace of the block being written
+    namespace: Option<&'a str>,
+    indent: &'static str,
+}
+
+impl TypeScriptWriter<'_> {
+    fn record(&self, record: &RecordSchema) -> String {
+        let mut out = self.jsdoc(record.doc(), None, self.indent);
+        let _ = writeln!(out, "{}export interface {} {{", self.indent, record.name());
+        let indent = format!("{}  ", self.indent);
+        for field in record.fields() {
+            out.push_str(&self.jsdoc(field.doc(), field.default(), &indent));
+            let _ = writeln!(
+                out,
+                "{}{}: {};",
+                indent,
+                field.name(),
+                self.typescript_type(field.schema())
+            );
+        }
+        let _ = writeln!(out, "{}}}", self.indent);
+        out
+    }
+
+    fn enumeration(&self, enum_: &EnumSchema) -> String {
+        let mut out = self.jsdoc(enum_.doc(), None, self.indent);
+        let symbols: Vec<String> = enum_
+            .symbols()
+            .iter()
+            .map(|symbol| format!("\"{}\"", symbol))
+            .collect();
+        let symbols = if symbols.is_empty() {
+            "never".to_string()
+        } else {
+            symbols.join(" | ")
+        };
+        let _ = writeln!(
+            out,
+            "{}export type {} = {};",
+            self.indent,
+            enum_.name(),
+            symbols
+        );
+        out
+    }
+
+    fn typescript_type(&self, schema: &Schema) -> String {
+        match schema {
+            Schema::Null => "null".to_string(),
+            Schema::Boolean => "boolean".to_string(),
+            Schema::Int | Schema::Long | Schema::Float | Schema::Double => "number".to_string(),
+            Schema::String => "string".to_string(),
+            Schema::Array(inner) => match **inner {
+                Schema::Union(_) => format!("({})[]", self.typescript_type(inner)),
+                _ => format!("{}[]", self.typescript_type(inner)),
+            },
+            Schema::Record(name) | Schema::Enum(name) => match self.document.named_type(name) {
+                Some(named) if named.namespace() != self.namespace => named.fullname(),
+                Some(named) => named.name().to_string(),
+                None => name.clone(),
+            },
+            Schema::Union(types) => {
+                let types: Vec<String> = types.iter().map(|t| self.typescript_type(t)).collect();
+                types.join(" | ")
+            }
+        }
+    }
+
+    /// JSDoc comment with the docstring and default
+    fn jsdoc(&self, doc: Option<&str>, default: Option<&Value>, indent: &str) -> String {
+        let mut lines: Vec<String> = doc
+            .map(|doc| {
+                doc_lines(&doc.replace("*/", "*\\/"))
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(default) = default {
+            lines.push(format!("@default {}", default));
+        }
+        match lines.as_slice() {
+            [] => String::new(),
+            [line] => format!("{}/** {} */\n", indent, line),
+            lines => {
+                let mut out = format!("{}/**\n", indent);
+                for line in lines {
+                    if line.is_empty() {
+                        let _ = writeln!(out, "{} *", indent);
+                    } else {
+                        let _ = writeln!(out, "{} * {}", indent, line);
+                    }
+                }
+                let _ = writeln!(out, "{} */", indent);
+                out
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypeScriptGenerator;
+    use crate::compiler::IdlCompiler;
+
+    #[test]
+    fn test_generate_typescript() {
+        let src = "@namespace(\"org.example\")
+protocol Event {
+  @namespace(\"org.meals\")
+  enum Meal { DINNER, LUNCH }
+  /** A person */
+  record Person {
+    /** Full name */
+    string name = \"Bob\";
+    int? age = null;
+    array<org.meals.Meal> meals;
+    union { org.meals.Meal, string } choice;
+    union { null, Person } partner;
+  }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        let expected = r#"// Generated by avro-idl, do not edit
+
+export namespace org.meals {
+  export type Meal = "DINNER" | "LUNCH";
+}
+
+export namespace org.example {
+  /** A person */
+  export interface Person {
+    /**
+     * Full name
+     * @default "Bob"
+     */
+    name: string;
+    /** @default null */
+    age: number | null;
+    meals: org.meals.Meal[];
+    choice: org.meals.Meal | string;
+    partner: null | Person;
+  }
+}
+"#;
+        assert_eq!(
+            TypeScriptGenerator::new(protocol).generate().unwrap(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! Compatibility checks between two versions of a protocol or schema file, following the
+//! schema resolution rules of the Avro specification
+
+use std::fmt;
+
+use clap::ValueEnum;
+
+use crate::ast::{Field, Properties};
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, NamedSchema, RecordSchema, Schema};
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum CompatMode {
+    Backward,
+    Forward,
+    Full,
+}
+
+/// A change which breaks compatibility, at a path like `Person.age`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incompatibility {
+    path: String,
+    message: String,
+}
+
+impl Incompatibility {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Checks whether data written with one version of a file can be read with the other.
+/// Named types are paired by full name or by an alias of the reading type, so renames
+/// are backward compatible when the new type lists the old name in `@aliases`, and the
+/// same goes for fields. Types which only exist in one of the versions aren't checked,
+/// unless a field of a checked record references them.
+pub struct CompatChecker {
+    old: Field,
+    new: Field,
+    mode: CompatMode,
+}
+
+impl CompatChecker {
+    pub fn new(old: Field, new: Field) -> Self {
+        Self {
+            old,
+            new,
+            mode: CompatMode::Backward,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: CompatMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Find the incompatibilities between the versions, which is empty if they are
+    /// compatible
+    pub fn check(self) -> Result<Vec<Incompatibility>, AvroError> {
+        let old = Document::try_from(self.old)?;
+        let new = Document::try_from(self.new)?;
+
+        let mut incompatibilities = vec![];
+        if matches!(self.mode, CompatMode::Backward | CompatMode::Full) {
+            incompatibilities.extend(Resolution::new(&new, &old, true).check());
+        }
+        if matches!(self.mode, CompatMode::Forward | CompatMode::Full) {
+            for incompatibility in Resolution::new(&old, &new, false).check() {
+                if !incompatibilities.contains(&incompatibility) {
+                    incompatibilities.push(incompatibility);
+                }
+            }
+        }
+        Ok(incompatibilities)
+    }
+}
+
+/// Resolution of the schemas of a writer with the schemas of a reader
+struct Resolution<'a> {
+    reader: &'a Document,
+    writer: &'a Document,
+    /// Whether the reader is the new version
+    backward: bool,
+    incompatibilities: Vec<Incompatibility>,
+}
+
+impl<'a> Resolution<'a> {
+    fn new(reader: &'a Document, writer: &'a Document, backward: bool) -> Self {
+        Self {
+            reader,
+            writer,
+            backward,
+            incompatibilities: vec![],
+        }
+    }
+
+    fn check(mut self) -> Vec<Incompatibility> {
+        if let (Document::Schema(reader), Document::Schema(writer)) = (self.reader, self.writer) {
+            if let (Some(reader), Some(writer)) = (reader.main(), writer.main()) {
+                self.check_schema(reader, writer, "schema");
+            }
+        }
+
+        for reader in self.reader.types() {
+            let writer = self
+                .writer
+                .types()
+                .iter()
+                .find(|writer| same_type(reader, writer));
+            // A type renamed in the new version, which the old version can't resolve
+            let writer = writer.or_else(|| {
+                let renamed = self
+                    .writer
+                    .types()
+                    .iter()
+                    .find(|writer| same_type(writer, reader))?;
+                let message = format!(
+                    "renamed to `{}` without an alias in the old version",
+                    renamed.name()
+                );
+                self.report(reader.name(), message);
+                Some(renamed)
+            });
+            match (reader, writer) {
+                (NamedSchema::Record(reader), Some(NamedSchema::Record(writer))) => {
+                    self.check_record(reader, writer)
+                }
+                (NamedSchema::Enum(reader), Some(NamedSchema::Enum(writer))) => {
+                    self.check_enum(reader, writer)
+                }
+                (_, Some(writer)) => {
+                    let (old, new) = self.old_new(writer, reader);
+                    self.report(
+                        reader.name(),
+                        format!("changed from {} to {}", kind_name(old), kind_name(new)),
+                    );
+                }
+                (_, None) => {}
+            }
+        }
+        self.incompatibilities
+    }
+
+    fn check_record(&mut self, reader: &RecordSchema, writer: &RecordSchema) {
+        for field in reader.fields() {
+            let path = format!("{}.{}", reader.name(), field.name());
+            let writer_field = writer.field(field.name()).or_else(|| {
+                aliases(field.properties(), None)
+                    .iter()
+                    .find_map(|alias| writer.field(alias))
+            });
+            match writer_field {
+                Some(writer_field) => {
+                    self.check_schema(field.schema(), writer_field.schema(), &path)
+                }
+                None if field.default().is_none() => {
+                    let message = if self.backward {
+                        "field was added without a default"
+                    } else {
+                        "field was removed, but has no default in the old version"
+                    };
+                    self.report(&path, message.to_string());
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn check_enum(&mut self, reader: &EnumSchema, writer: &EnumSchema) {
+        // Unknown symbols are read as the default of the reader
+        if reader.default().is_some() {
+            return;
+        }
+        for symbol in writer.symbols() {
+            if !reader.symbols().contains(symbol) {
+                let change = if self.backward { "removed" } else { "added" };
+                let message = format!("symbol '{}' was {} and there is no default", symbol, change);
+                self.report(reader.name(), message);
+            }
+        }
+    }
+
+    /// Check a schema of the reader against one of the writer
+    fn check_schema(&mut self, reader: &Schema, writer: &Schema, path: &str) {
+        match (reader, writer) {
+            (Schema::Array(reader), Schema::Array(writer)) => {
+                self.check_schema(reader, writer, path)
+            }
+            (Schema::Union(readers), Schema::Union(writers)) => {
+                for writer in writers.iter() {
+                    if !readers.iter().any(|reader| self.matches(reader, writer)) {
+                        let change = if self.backward { "removed" } else { "added" };
+                        let message = format!("union branch `{}` was {}", writer, change);
+                        self.report(path, message);
+                    }
+                }
+            }
+            (reader, writer) => {
+                if !self.matches(reader, writer) {
+                    let (old, new) = self.old_new(writer, reader);
+                    let message = format!("type changed from `{}` to `{}`", old, new);
+                    self.report(path, message);
+                }
+            }
+        }
+    }
+
+    /// Whether data of the writer's schema can be read with the reader's schema. Named
+    /// types only need to match by name, since their content is checked on its own.
+    fn matches(&self, reader: &Schema, writer: &Schema) -> bool {
+        match (reader, writer) {
+            (_, Schema::Union(writers)) => writers.iter().all(|w| self.matches(reader, w)),
+            (Schema::Union(readers), _) => readers.iter().any(|r| self.matches(r, writer)),
+            (Schema::Array(reader), Schema::Array(writer)) => self.matches(reader, writer),
+            (Schema::Record(reader), Schema::Record(writer))
+            | (Schema::Enum(reader), Schema::Enum(writer)) => {
+                match (
+                    self.reader.named_type(reader),
+                    self.writer.named_type(writer),
+                ) {
+                    (Some(reader), Some(writer)) => same_type(reader, writer),
+                    _ => reader == writer,
+                }
+            }
+            (reader, writer) => reader == writer || promotes(writer, reader),
+        }
+    }
+
+    fn old_new<'b, T: ?Sized>(&self, writer: &'b T, reader: &'b T) -> (&'b T, &'b T) {
+        if self.backward {
+            (writer, reader)
+        } else {
+            (reader, writer)
+        }
+    }
+
+    fn report(&mut self, path: &str, message: String) {
+        self.incompatibilities.push(Incompatibility {
+            path: path.to_string(),
+            message,
+        });
+    }
+}
+
+/// Whether a type of the writer is promoted to a type of the reader when reading
+fn promotes(writer: &Schema, reader: &Schema) -> bool {
+    matches!(
+        (writer, reader),
+        (Schema::Int, Schema::Long | Schema::Float | Schema::Double)
+            | (Schema::Long, Schema::Float | Schema::Double)
+            | (Schema::Float, Schema::Double)
+    )
+}
+
+/// Whether a named type of the reader resolves a named type of the writer, by having
+/// the same full name or the writer's full name as an alias
+fn same_type(reader: &NamedSchema, writer: &NamedSchema) -> bool {
+    let same_kind = matches!(
+        (reader, writer),
+        (NamedSchema::Record(_), NamedSchema::Record(_))
+            | (NamedSchema::Enum(_), NamedSchema::Enum(_))
+    );
+    same_kind
+        && (reader.fullname() == writer.fullname()
+            || aliases(reader.properties(), reader.namespace()).contains(&writer.fullname()))
+}
+
+/// Aliases set with `@aliases`, qualified with the namespace if given
+fn aliases(properties: &Properties, namespace: Option<&str>) -> Vec<String> {
+    let Some(aliases) = properties.get("aliases").and_then(|a| a.as_array()) else {
+        return vec![];
+    };
+    aliases
+        .iter()
+        .filter_map(|alias| alias.as_str())
+        .map(|alias| match namespace {
+            Some(namespace) if !alias.contains('.') => format!("{}.{}", namespace, alias),
+            _ => alias.to_string(),
+        })
+        .collect()
+}
+
+fn kind_name(named: &NamedSchema) -> &'static str {
+    match named {
+        NamedSchema::Record(_) => "a record",
+        NamedSchema::Enum(_) => "an enum",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompatChecker, CompatMode};
+    use crate::compiler::IdlCompiler;
+
+    const OLD: &str = "protocol Event {
+  enum Meal { DINNER, LUNCH, BREAKFAST }
+  record Person {
+    string name;
+    int age;
+    union { null, string, int } id;
+    Meal meal;
+    int height;
+  }
+}";
+
+    const NEW: &str = "protocol Event {
+  enum Meal { DINNER, LUNCH }
+  @aliases([\"Person\"])
+  record Human {
+    string @aliases([\"name\"]) fullName;
+    long age;
+    union { null, string } id;
+    Meal meal;
+    string height;
+    int weight;
+    boolean active = true;
+  }
+}";
+
+    fn check(old: &str, new: &str, mode: CompatMode) -> Vec<String> {
+        let old = IdlCompiler::new().compile(old).unwrap();
+        let new = IdlCompiler::new().compile(new).unwrap();
+        CompatChecker::new(old, new)
+            .with_mode(mode)
+            .check()
+            .unwrap()
+            .iter()
+            .map(|incompatibility| incompatibility.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_backward() {
+        assert_eq!(
+            check(OLD, NEW, CompatMode::Backward),
+            vec![
+                "Meal: symbol 'BREAKFAST' was removed and there is no default",
+                "Human.id: union branch `int` was removed",
+                "Human.height: type changed from `int` to `string`",
+                "Human.weight: field was added without a default",
+            ]
+        );
+        assert!(check(OLD, OLD, CompatMode::Full).is_empty());
+    }
+
+    #[test]
+    fn test_renamed_reference() {
+        // `Person` in the old version is the one of the enclosing namespace, not org.other
+        let old = "@namespace(\"org.example\")
+protocol Event {
+  @namespace(\"org.other\") record Person { int id; }
+  record Person { string name; }
+  record Team { Person lead; }
+}";
+        let new = "@namespace(\"org.example\")
+protocol Event {
+  @namespace(\"org.staff\") @aliases([\"org.example.Person\"])
+  record Member { string name; }
+  record Team { org.staff.Member lead; }
+}";
+        assert!(check(old, new, CompatMode::Backward).is_empty());
+    }
+
+    #[test]
+    fn test_forward() {
+        assert_eq!(
+            check(OLD, NEW, CompatMode::Forward),
+            vec![
+                "Person: renamed to `Human` without an alias in the old version",
+                "Person.name: field was removed, but has no default in the old version",
+                "Person.age: type changed from `int` to `long`",
+                "Person.height: type changed from `int` to `string`",
+            ]
+        );
+    }
+}
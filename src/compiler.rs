@@ -0,0 +1,150 @@
+use std::rc::Rc;
+
+use crate::ast::Field;
+use crate::error::AvroError;
+use crate::lexer::AvroIdlLexer;
+use crate::linker::LinkParser;
+use crate::resolver::{FileSystemResolver, ImportResolver};
+use crate::schema::Document;
+use crate::serializer::{AvprSerializer, AvscSerializer};
+use serde_json::Value;
+
+/// Compiles Avro IDL held in memory, where imports are resolved through an
+/// `ImportResolver` e.g. to read them from a database or from test fixtures
+pub struct IdlCompiler {
+    path: String,
+    resolver: Rc<dyn ImportResolver>,
+}
+
+impl Default for IdlCompiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdlCompiler {
+    /// Create a new compiler which resolves imports on the file system
+    pub fn new() -> Self {
+        Self {
+            path: "<memory>".to_string(),
+            resolver: Rc::new(FileSystemResolver::default()),
+        }
+    }
+
+    /// Resolve imports through the given resolver
+    pub fn with_resolver(mut self, resolver: impl ImportResolver + 'static) -> Self {
+        self.resolver = Rc::new(resolver);
+        self
+    }
+
+    /// Set the path of the source, which is used in error messages and to resolve imports
+    /// relative to it
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Parse and link IDL
+    pub fn compile(&self, src: &str) -> Result<Field, AvroError> {
+        let parsed_ast = AvroIdlLexer::new(self.path.clone())
+            .with_resolver(self.resolver.clone())
+            .parse_source(src.to_string())?;
+        LinkParser::new().parse(parsed_ast)
+    }
+
+    /// Parse and link IDL into the typed schema model
+    pub fn compile_document(&self, src: &str) -> Result<Document, AvroError> {
+        Document::try_from(self.compile(src)?)
+    }
+
+    /// Compile IDL to JSON, where protocols become avpr and schema files avsc
+    pub fn compile_to_json(&self, src: &str) -> Result<Value, AvroError> {
+        match self.compile(src)? {
+            schema @ Field::Schema(..) => AvscSerializer::new(schema).serialize(),
+            protocol => AvprSerializer::new(protocol).serialize(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::IdlCompiler;
+    use crate::resolver::InMemoryResolver;
+
+    #[test]
+    fn test_compile_with_in_memory_imports() {
+        let resolver = InMemoryResolver::new()
+            .with_file(
+                "common/date.avdl",
+                "protocol Common { import idl \"time.avdl\"; }",
+            )
+            .with_file(
+                "common/time.avdl",
+                "protocol Time { record Time { long ms; } }",
+            );
+        let compiler = IdlCompiler::new()
+            .with_resolver(resolver)
+            .with_path("events/event.avdl");
+
+        let src = "protocol Event {
+            import idl \"../common/date.avdl\";
+            record Event { Time at; }
+        }";
+        let res = compiler.compile_to_json(src).unwrap();
+        let expected = json!({
+            "protocol": "Event",
+            "types": [
+                {"type": "record", "name": "Time", "fields": [{"name": "ms", "type": "long"}]},
+                {"type": "record", "name": "Event", "fields": [{"name": "at", "type": "Time"}]}
+            ]
+        });
+        assert_eq!(res, expected);
+
+        let err = compiler
+            .compile("protocol Event { import idl \"missing.avdl\"; }")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "events/event.avdl: Failed to find import 'missing.avdl'"
+        );
+    }
+
+    #[test]
+    fn test_compile_references_across_namespaces() {
+        let src = "@namespace(\"org.example\")
+protocol Event {
+  @namespace(\"org.meals\")
+  enum Meal { DINNER, LUNCH }
+  @namespace(\"org.meals\")
+  record Person { Meal favourite; }
+  record Person {
+    org.meals.Meal meal;
+    array<org.meals.Meal> meals;
+    union { null, org.meals.Person } chef;
+    union { null, Person } partner;
+  }
+}";
+        let res = IdlCompiler::new().compile_to_json(src).unwrap();
+        let fields = json!([
+            {"name": "meal", "type": "org.meals.Meal"},
+            {"name": "meals", "type": {"type": "array", "items": "org.meals.Meal"}},
+            {"name": "chef", "type": ["null", "org.meals.Person"]},
+            {"name": "partner", "type": ["null", "Person"]}
+        ]);
+        assert_eq!(
+            res["types"][1]["fields"],
+            json!([{"name": "favourite", "type": "Meal"}])
+        );
+        assert_eq!(res["types"][2]["fields"], fields);
+
+        let err = IdlCompiler::new()
+            .compile(&src.replace("org.meals.Meal meal", "Meal meal"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "<memory>:8:5: Field of type 'Meal' cannot be found!"
+        );
+    }
+}
@@ -0,0 +1,774 @@
+use std::collections::HashMap;
+
+use crate::ast::{qualify, reference_fullnames, Field, HasDefault, Literal, Properties};
+use crate::error::AvroError;
+use serde_json::{Map, Value};
+
+/// Deserialize an avpr protocol or an avsc schema into a Field, which can be printed as
+/// IDL. Named types defined inline are moved to the types of the protocol or schema file
+/// and replaced by references, and namespaces equal to the namespace of the protocol or
+/// schema file are left out.
+pub struct JsonDeserializer {
+    json: Value,
+}
+
+/// Kind of a named type
+#[derive(Clone, Copy, PartialEq)]
+enum NamedKind {
+    Record,
+    Enum,
+}
+
+impl JsonDeserializer {
+    pub fn new(json: Value) -> Self {
+        Self { json }
+    }
+
+    pub fn deserialize(&self) -> Result<Field, AvroError> {
+        let mut named_types = NamedTypes::default();
+        match &self.json {
+            Value::Object(object) if object.contains_key("protocol") => {
+                let name = required_string(object, "protocol")?;
+                let namespace = optional_string(object, "namespace")?;
+                let docstring = optional_string(object, "doc")?;
+                match object.get("messages") {
+                    None | Some(Value::Null) => {}
+                    Some(Value::Object(messages)) if messages.is_empty() => {}
+                    Some(_) => {
+                        return Err(AvroError::InvalidJson(format!(
+                            "Protocol '{}' has messages, which are not supported!",
+                            name
+                        )))
+                    }
+                }
+                let types = match object.get("types") {
+                    Some(Value::Array(types)) => types.clone(),
+                    None => vec![],
+                    Some(_) => {
+                        return Err(AvroError::InvalidJson(
+                            "The types of a protocol must be an array!".to_string(),
+                        ))
+                    }
+                };
+                for type_ in types.iter() {
+                    named_types.define(type_, &namespace)?;
+                }
+                let props = properties(
+                    object,
+                    &["protocol", "namespace", "doc", "types", "messages"],
+                );
+                Ok(Field::Protocol(
+                    Some(name),
+                    named_types.relative_to(&namespace),
+                    namespace,
+                    docstring,
+                    props,
+                ))
+            }
+            json => {
+                let main = named_types.unnamed_type(json, &None, true)?;
+                // The file namespace is the one of the main schema, or of its first named type
+                let namespace = match &main {
+                    Field::RecordReference(_, name, ..) | Field::EnumReference(_, name, ..) => {
+                        namespace_of(name)
+                    }
+                    _ => named_types.types.first().and_then(namespace_of_type),
+                };
+                let main = named_types.qualify(main, &namespace);
+                Ok(Field::Schema(
+                    Some(Box::new(main)),
+                    named_types.relative_to(&namespace),
+                    namespace,
+                ))
+            }
+        }
+    }
+}
+
+/// Named types in the order they are defined, where nested types come before the types
+/// using them
+#[derive(Default)]
+struct NamedTypes {
+    types: Vec<Field>,
+    /// Kinds of the named types defined so far by their full name
+    kinds: HashMap<String, NamedKind>,
+}
+
+impl NamedTypes {
+    /// Define a record or enum and return a reference to it
+    fn define(&mut self, json: &Value, namespace: &Option<String>) -> Result<Field, AvroError> {
+        let Value::Object(object) = json else {
+            return Err(AvroError::InvalidJson(format!(
+                "Expected a named type definition, found '{}'!",
+                json
+            )));
+        };
+        let kind = match object.get("type").and_then(Value::as_str) {
+            Some("record") => NamedKind::Record,
+            Some("enum") => NamedKind::Enum,
+            Some(type_) => {
+                return Err(AvroError::InvalidJson(format!(
+                    "Type '{}' is not supported!",
+                    type_
+                )))
+            }
+            None => {
+                return Err(AvroError::InvalidJson(format!(
+                    "Expected a named type definition, found '{}'!",
+                    json
+                )))
+            }
+        };
+
+        let fullname = required_string(object, "name")?;
+        let (name, namespace) = match fullname.rsplit_once('.') {
+            Some((namespace, name)) => (name.to_string(), Some(namespace.to_string())),
+            None => (
+                fullname.clone(),
+                optional_string(object, "namespace")?.or_else(|| namespace.clone()),
+            ),
+        };
+        let fullname = qualify(&name, namespace.as_deref());
+        if self.kinds.contains_key(&fullname) {
+            return Err(AvroError::InvalidJson(format!(
+                "Type '{}' is defined more than once, which is only supported in different files!",
+                fullname
+            )));
+        }
+        self.kinds.insert(fullname.clone(), kind);
+        let docstring = optional_string(object, "doc")?;
+
+        match kind {
+            NamedKind::Record => {
+                let fields = match object.get("fields") {
+                    Some(Value::Array(fields)) => fields
+                        .iter()
+                        .map(|field| self.field(field, &namespace))
+                        .collect::<Result<Vec<Field>, AvroError>>()?,
+                    _ => {
+                        return Err(AvroError::InvalidJson(format!(
+                            "Record '{}' must have an array of fields!",
+                            name
+                        )))
+                    }
+                };
+                let props = properties(object, &["type", "name", "namespace", "doc", "fields"]);
+                self.types.push(Field::Record(
+                    Some(name.clone()),
+                    fields,
+                    namespace,
+                    docstring,
+                    props,
+                ));
+            }
+            NamedKind::Enum => {
+                let symbols = match object.get("symbols") {
+                    Some(Value::Array(symbols)) => symbols
+                        .iter()
+                        .map(|symbol| {
+                            symbol.as_str().map(|s| s.to_string()).ok_or_else(|| {
+                                AvroError::InvalidJson(format!(
+                                    "Symbols of enum '{}' must be strings!",
+                                    name
+                                ))
+                            })
+                        })
+                        .collect::<Result<Vec<String>, AvroError>>()?,
+                    _ => {
+                        return Err(AvroError::InvalidJson(format!(
+                            "Enum '{}' must have an array of symbols!",
+                            name
+                        )))
+                    }
+                };
+                let default = match optional_string(object, "default")? {
+                    Some(default) => HasDefault::Default(Some(default)),
+                    None => HasDefault::None,
+                };
+                let props = properties(
+                    object,
+                    &["type", "name", "namespace", "doc", "symbols", "default"],
+                );
+                self.types.push(Field::Enum(
+                    Some(name.clone()),
+                    symbols,
+                    default,
+                    namespace,
+                    docstring,
+                    props,
+                ));
+            }
+        }
+        Ok(self.reference(None, &fullname, None, Properties::new()))
+    }
+
+    /// Deserialize a field of a record
+    fn field(&mut self, json: &Value, namespace: &Option<String>) -> Result<Field, AvroError> {
+        let Value::Object(object) = json else {
+            return Err(AvroError::InvalidJson(format!(
+                "Expected a field, found '{}'!",
+                json
+            )));
+        };
+        let name = required_string(object, "name")?;
+        let docstring = optional_string(object, "doc")?;
+        let props = properties(object, &["name", "type", "default", "doc"]);
+        let default = object.get("default");
+        let Some(type_) = object.get("type") else {
+            return Err(AvroError::InvalidJson(format!(
+                "Field '{}' doesn't have a type!",
+                name
+            )));
+        };
+
+        let unsupported_default = |kind: &str| {
+            AvroError::InvalidJson(format!(
+                "Field '{}' has a default, which is not supported for {} in IDL!",
+                name, kind
+            ))
+        };
+        let field = match self.unnamed_type(type_, namespace, true)? {
+            Field::Int(.., type_props) => Field::Int(
+                Some(name.clone()),
+                primitive_default(&name, default, |v| {
+                    v.as_i64().and_then(|v| i32::try_from(v).ok())
+                })?,
+                docstring,
+                props,
+                type_props,
+            ),
+            Field::Long(.., type_props) => Field::Long(
+                Some(name.clone()),
+                primitive_default(&name, default, Value::as_i64)?,
+                docstring,
+                props,
+                type_props,
+            ),
+            Field::Float(.., type_props) => Field::Float(
+                Some(name.clone()),
+                primitive_default(&name, default, |v| v.as_f64().map(|v| v as f32))?,
+                docstring,
+                props,
+                type_props,
+            ),
+            Field::Double(.., type_props) => Field::Double(
+                Some(name.clone()),
+                primitive_default(&name, default, Value::as_f64)?,
+                docstring,
+                props,
+                type_props,
+            ),
+            Field::Boolean(.., type_props) => Field::Boolean(
+                Some(name.clone()),
+                primitive_default(&name, default, Value::as_bool)?,
+                docstring,
+                props,
+                type_props,
+            ),
+            Field::String(.., type_props) => Field::String(
+                Some(name.clone()),
+                primitive_default(&name, default, |v| {
+                    v.as_str()
+                        .filter(|v| !v.contains('"'))
+                        .map(|v| v.to_string())
+                })?,
+                docstring,
+                props,
+                type_props,
+            ),
+            Field::Union(_, types, ..) => {
+                let default = match default {
+                    None => HasDefault::None,
+                    Some(Value::Null) => HasDefault::Default(None),
+                    Some(value) => {
+                        let literal = types
+                            .first()
+                            .and_then(|first| literal(first, value))
+                            .ok_or_else(|| unsupported_default("this union"))?;
+                        HasDefault::Default(Some(literal))
+                    }
+                };
+                Field::Union(Some(name.clone()), types, default, docstring, props)
+            }
+            Field::Array(_, inner, .., type_props) => {
+                if default.is_some() {
+                    return Err(unsupported_default("arrays"));
+                }
+                Field::Array(
+                    Some(name.clone()),
+                    inner,
+                    HasDefault::None,
+                    docstring,
+                    props,
+                    type_props,
+                )
+            }
+            Field::RecordReference(_, type_, ..) | Field::EnumReference(_, type_, ..) => {
+                if default.is_some() {
+                    return Err(unsupported_default("records and enums"));
+                }
+                self.reference(Some(name.clone()), &type_, docstring, props)
+            }
+            _ => {
+                return Err(AvroError::InvalidJson(format!(
+                    "Field '{}' has a type which is not supported!",
+                    name
+                )))
+            }
+        };
+        Ok(field)
+    }
+
+    /// Deserialize a type without a name as used in fields, arrays and unions. Arrays
+    /// and unions may only be nested at the top, since IDL can't express nested ones.
+    fn unnamed_type(
+        &mut self,
+        json: &Value,
+        namespace: &Option<String>,
+        top: bool,
+    ) -> Result<Field, AvroError> {
+        match json {
+            Value::String(type_) => match type_.as_str() {
+                "int" => Ok(Field::Int(
+                    None,
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )),
+                "long" => Ok(Field::Long(
+                    None,
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )),
+                "float" => Ok(Field::Float(
+                    None,
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )),
+                "double" => Ok(Field::Double(
+                    None,
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )),
+                "boolean" => Ok(Field::Boolean(
+                    None,
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )),
+                "string" => Ok(Field::String(
+                    None,
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )),
+                "null" => Ok(Field::Null),
+                "bytes" => Err(AvroError::InvalidJson(
+                    "Type 'bytes' is not supported!".to_string(),
+                )),
+                reference => {
+                    let Some(fullname) = reference_fullnames(reference, namespace.as_deref())
+                        .into_iter()
+                        .find(|fullname| self.kinds.contains_key(fullname))
+                    else {
+                        return Err(AvroError::InvalidJson(format!(
+                            "Type '{}' is used before it is defined!",
+                            reference
+                        )));
+                    };
+                    Ok(self.reference(None, &fullname, None, Properties::new()))
+                }
+            },
+            Value::Array(types) if top => {
+                let types = types
+                    .iter()
+                    .map(|type_| self.unnamed_type(type_, namespace, false))
+                    .collect::<Result<Vec<Field>, AvroError>>()?;
+                Ok(Field::Union(
+                    None,
+                    types,
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                ))
+            }
+            Value::Object(object) => match object.get("type") {
+                Some(Value::String(type_)) if type_ == "array" && top => {
+                    let Some(items) = object.get("items") else {
+                        return Err(AvroError::InvalidJson(
+                            "Array doesn't have items!".to_string(),
+                        ));
+                    };
+                    let items = self.unnamed_type(items, namespace, false)?;
+                    Ok(Field::Array(
+                        None,
+                        Box::new(items),
+                        HasDefault::None,
+                        None,
+                        Properties::new(),
+                        properties(object, &["type", "items"]),
+                    ))
+                }
+                // Attributes of a primitive type, e.g. a logicalType, are kept on the type
+                Some(type_) if is_primitive(Some(type_)) => {
+                    let primitive = self.unnamed_type(type_, namespace, top)?;
+                    Ok(with_type_props(primitive, properties(object, &["type"])))
+                }
+                Some(Value::String(type_)) if matches!(type_.as_str(), "record" | "enum") => {
+                    self.define(json, namespace)
+                }
+                Some(Value::String(type_)) if matches!(type_.as_str(), "array") => {
+                    Err(AvroError::InvalidJson(
+                        "Arrays nested in arrays or unions are not supported!".to_string(),
+                    ))
+                }
+                Some(Value::String(type_)) => Err(AvroError::InvalidJson(format!(
+                    "Type '{}' is not supported!",
+                    type_
+                ))),
+                Some(type_) => Err(AvroError::InvalidJson(format!("Invalid type '{}'!", type_))),
+                None => Err(AvroError::InvalidJson(format!(
+                    "Type '{}' doesn't have a type attribute!",
+                    json
+                ))),
+            },
+            Value::Array(_) => Err(AvroError::InvalidJson(
+                "Unions nested in arrays or unions are not supported!".to_string(),
+            )),
+            _ => Err(AvroError::InvalidJson(format!("Invalid type '{}'!", json))),
+        }
+    }
+
+    fn reference(
+        &self,
+        name: Option<String>,
+        type_: &str,
+        docstring: Option<String>,
+        props: Properties,
+    ) -> Field {
+        match self.kinds.get(type_) {
+            Some(NamedKind::Enum) => {
+                Field::EnumReference(name, type_.to_string(), HasDefault::None, docstring, props)
+            }
+            _ => Field::RecordReference(name, type_.to_string(), docstring, props),
+        }
+    }
+
+    /// Write a reference, which holds the full name of the type, as the name alone if the
+    /// type is in the enclosing namespace
+    fn qualify(&self, field: Field, enclosing: &Option<String>) -> Field {
+        let qualified = |type_: String| match type_.rsplit_once('.') {
+            Some((namespace, name)) if Some(namespace) == enclosing.as_deref() => name.to_string(),
+            _ => type_,
+        };
+        match field {
+            Field::RecordReference(name, type_, docstring, props) => {
+                Field::RecordReference(name, qualified(type_), docstring, props)
+            }
+            Field::EnumReference(name, type_, default, docstring, props) => {
+                Field::EnumReference(name, qualified(type_), default, docstring, props)
+            }
+            Field::Array(name, inner, default, docstring, props, type_props) => Field::Array(
+                name,
+                Box::new(self.qualify(*inner, enclosing)),
+                default,
+                docstring,
+                props,
+                type_props,
+            ),
+            Field::Union(name, types, default, docstring, props) => Field::Union(
+                name,
+                types
+                    .into_iter()
+                    .map(|type_| self.qualify(type_, enclosing))
+                    .collect(),
+                default,
+                docstring,
+                props,
+            ),
+            field => field,
+        }
+    }
+
+    /// The named types, without namespaces equal to the given namespace
+    fn relative_to(&self, namespace: &Option<String>) -> Vec<Field> {
+        let relative = |ns: Option<String>| ns.filter(|ns| Some(ns) != namespace.as_ref());
+        self.types
+            .iter()
+            .cloned()
+            .map(|type_| match type_ {
+                Field::Record(name, fields, ns, docstring, props) => {
+                    let fields = fields
+                        .into_iter()
+                        .map(|field| self.qualify(field, &ns))
+                        .collect();
+                    Field::Record(name, fields, relative(ns), docstring, props)
+                }
+                Field::Enum(name, symbols, default, ns, docstring, props) => {
+                    Field::Enum(name, symbols, default, relative(ns), docstring, props)
+                }
+                _ => type_,
+            })
+            .collect()
+    }
+}
+
+/// Namespace of a full name
+fn namespace_of(fullname: &str) -> Option<String> {
+    fullname
+        .rsplit_once('.')
+        .map(|(namespace, _)| namespace.to_string())
+}
+
+fn namespace_of_type(field: &Field) -> Option<String> {
+    match field {
+        Field::Record(_, _, namespace, ..) | Field::Enum(_, _, _, namespace, ..) => {
+            namespace.clone()
+        }
+        _ => None,
+    }
+}
+
+/// Set the properties of a primitive type
+fn with_type_props(field: Field, type_props: Properties) -> Field {
+    match field {
+        Field::Int(name, default, docstring, props, _) => {
+            Field::Int(name, default, docstring, props, type_props)
+        }
+        Field::Long(name, default, docstring, props, _) => {
+            Field::Long(name, default, docstring, props, type_props)
+        }
+        Field::Float(name, default, docstring, props, _) => {
+            Field::Float(name, default, docstring, props, type_props)
+        }
+        Field::Double(name, default, docstring, props, _) => {
+            Field::Double(name, default, docstring, props, type_props)
+        }
+        Field::Boolean(name, default, docstring, props, _) => {
+            Field::Boolean(name, default, docstring, props, type_props)
+        }
+        Field::String(name, default, docstring, props, _) => {
+            Field::String(name, default, docstring, props, type_props)
+        }
+        field => field,
+    }
+}
+
+fn is_primitive(type_: Option<&Value>) -> bool {
+    matches!(
+        type_.and_then(Value::as_str),
+        Some("int" | "long" | "float" | "double" | "boolean" | "string" | "null")
+    )
+}
+
+/// Convert the default of a primitive field, where null becomes `HasDefault::Default(None)`
+fn primitive_default<T>(
+    name: &str,
+    default: Option<&Value>,
+    convert: impl Fn(&Value) -> Option<T>,
+) -> Result<HasDefault<T>, AvroError> {
+    match default {
+        None => Ok(HasDefault::None),
+        Some(Value::Null) => Ok(HasDefault::Default(None)),
+        Some(value) => convert(value)
+            .map(|value| HasDefault::Default(Some(value)))
+            .ok_or_else(|| {
+                AvroError::InvalidJson(format!(
+                    "Field '{}' has a default '{}', which can't be expressed in IDL!",
+                    name, value
+                ))
+            }),
+    }
+}
+
+/// Convert the default of a union according to its first type
+fn literal(first: &Field, value: &Value) -> Option<Literal> {
+    match first {
+        Field::Int(..) => value
+            .as_i64()
+            .and_then(|v| i32::try_from(v).ok())
+            .map(Literal::Int),
+        Field::Long(..) => value.as_i64().map(Literal::Long),
+        Field::Float(..) => value.as_f64().map(|v| Literal::Float(v as f32)),
+        Field::Double(..) => value.as_f64().map(Literal::Double),
+        Field::Boolean(..) => value.as_bool().map(Literal::Boolean),
+        Field::String(..) => value
+            .as_str()
+            .filter(|v| !v.contains('"'))
+            .map(|v| Literal::String(v.to_string())),
+        _ => None,
+    }
+}
+
+fn required_string(object: &Map<String, Value>, key: &str) -> Result<String, AvroError> {
+    optional_string(object, key)?
+        .ok_or_else(|| AvroError::InvalidJson(format!("Attribute '{}' is missing!", key)))
+}
+
+fn optional_string(object: &Map<String, Value>, key: &str) -> Result<Option<String>, AvroError> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(value) => Err(AvroError::InvalidJson(format!(
+            "Attribute '{}' must be a string, found '{}'!",
+            key, value
+        ))),
+    }
+}
+
+/// Attributes which aren't part of the schema itself are kept as custom properties
+fn properties(object: &Map<String, Value>, known: &[&str]) -> Properties {
+    object
+        .iter()
+        .filter(|(key, _)| !known.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::JsonDeserializer;
+    use crate::ast::RawField;
+    use crate::lexer::AvroIdlLexer;
+    use crate::linker::LinkParser;
+    use crate::printer::IdlPrinter;
+    use crate::serializer::{AvprSerializer, AvscSerializer};
+
+    fn compile(idl: String) -> crate::ast::Field {
+        let raw = AvroIdlLexer::new("none".to_string())
+            .parse_source(idl)
+            .unwrap();
+        LinkParser::new().parse(raw).unwrap()
+    }
+
+    fn to_idl(json: serde_json::Value) -> String {
+        let field = JsonDeserializer::new(json).deserialize().unwrap();
+        IdlPrinter::new(vec![RawField::from(field)])
+            .print()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_protocol_round_trip() {
+        let src = "/** Events */
+@namespace(\"org.example\")
+@version(\"1\")
+protocol Event {
+  /** A meal */
+  @namespace(\"org.food\")
+  enum Meal {
+    Dinner,
+    Lunch
+  } = Dinner;
+
+  record Person {
+    /** The name */
+    string name      = \"Bob\";
+    int? age;
+    @pii(true) long income = 10;
+    float ratio      = 0.5;
+    double? rate     = 1.5;
+    boolean active   = true;
+    array<org.food.Meal> meals;
+    union { null, org.food.Meal } favourite = null;
+  }
+}
+";
+        let avpr = AvprSerializer::new(compile(src.to_string()))
+            .serialize()
+            .unwrap();
+        let idl = to_idl(avpr.clone());
+        let round_trip = AvprSerializer::new(compile(idl)).serialize().unwrap();
+        assert_eq!(round_trip, avpr);
+    }
+
+    #[test]
+    fn test_nested_named_types() {
+        let avsc = json!({
+            "type": "record",
+            "name": "org.example.Person",
+            "doc": "A person, not a */ comment",
+            "fields": [
+                {"name": "address", "type": {
+                    "type": "record",
+                    "name": "Address",
+                    "fields": [{"name": "street", "type": "string"}]
+                }},
+                {"name": "home", "type": ["null", "Address"], "default": null},
+                {"name": "created", "type": {"type": "long", "logicalType": "timestamp-millis"}}
+            ]
+        });
+        let idl = to_idl(avsc.clone());
+        let expected = "namespace org.example;
+schema Person;
+
+record Address {
+  string street;
+}
+
+/** A person, not a *\\/ comment */
+record Person {
+  Address address;
+  union { null, Address } home = null;
+  @logicalType(\"timestamp-millis\") long created;
+}
+";
+        assert_eq!(idl, expected);
+
+        let round_trip = AvscSerializer::new(compile(idl)).serialize().unwrap();
+        assert_eq!(round_trip["fields"][0]["type"]["name"], json!("Address"));
+        assert_eq!(round_trip["fields"][1]["type"], json!(["null", "Address"]));
+        assert_eq!(round_trip["fields"][2]["type"], avsc["fields"][2]["type"]);
+        assert_eq!(round_trip["doc"], avsc["doc"]);
+    }
+
+    #[test]
+    fn test_same_name_in_different_namespaces() {
+        let avpr = json!({"protocol": "P", "namespace": "a", "types": [
+            {"type": "record", "name": "Person", "fields": []},
+            {"type": "record", "name": "b.Person", "fields": [
+                {"name": "other", "type": "a.Person"},
+                {"name": "self", "type": ["null", "Person"]}
+            ]}
+        ]});
+        let expected = "@namespace(\"a\")
+protocol P {
+  record Person {}
+
+  @namespace(\"b\")
+  record Person {
+    a.Person other;
+    union { null, Person } self;
+  }
+}
+";
+        let idl = to_idl(avpr.clone());
+        assert_eq!(idl, expected);
+        let round_trip = AvprSerializer::new(compile(idl)).serialize().unwrap();
+        assert_eq!(round_trip["types"][1]["fields"], avpr["types"][1]["fields"]);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let messages =
+            json!({"protocol": "P", "messages": {"ping": {"request": [], "response": "null"}}});
+        assert!(JsonDeserializer::new(messages).deserialize().is_err());
+
+        let map = json!({"type": "record", "name": "R", "fields": [
+            {"name": "m", "type": {"type": "map", "values": "int"}}
+        ]});
+        let err = JsonDeserializer::new(map).deserialize().unwrap_err();
+        assert_eq!(err.to_string(), "Type 'map' is not supported!");
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+
+use crate::error::{AvroError, Location};
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Clone, ValueEnum)]
+pub enum DiagnosticFormat {
+    Text,
+    Json,
+}
+
+/// A problem found in a file. Parse errors and undefined references carry their location,
+/// other errors found while linking or serializing only the file they were found in.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    pub kind: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Create the diagnostics of an error which happened while processing the given path.
+    /// Parse errors hold one located error each, which become one diagnostic each.
+    pub fn from_error(path: &str, error: &AvroError) -> Vec<Diagnostic> {
+        match error {
+            AvroError::FailedParsing(errors) => errors
+                .iter()
+                .map(|err| Self::located(error.kind(), &err.location, &err.message))
+                .collect(),
+            AvroError::UndefinedReference(message, Some(location)) => {
+                vec![Self::located(error.kind(), location, message)]
+            }
+            _ => {
+                let message = error.to_string();
+                let message = message
+                    .strip_prefix(path)
+                    .and_then(|m| m.strip_prefix(": "))
+                    .unwrap_or(&message);
+                vec![Diagnostic {
+                    path: path.to_string(),
+                    line: None,
+                    column: None,
+                    start: None,
+                    end: None,
+                    kind: error.kind().to_string(),
+                    message: message.to_string(),
+                }]
+            }
+        }
+    }
+
+    fn located(kind: &str, location: &Location, message: &str) -> Diagnostic {
+        Diagnostic {
+            path: location.path.clone(),
+            line: Some(location.line),
+            column: Some(location.column),
+            start: Some(location.span.start),
+            end: Some(location.span.end),
+            kind: kind.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "{}:{}:{}: {}", self.path, line, column, self.message)
+            }
+            _ => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::IdlCompiler;
+    use crate::error::ParseError;
+
+    #[test]
+    fn test_diagnostics_from_errors() {
+        let error = AvroError::FailedParsing(vec![ParseError {
+            location: Location {
+                path: "schemas/a.avdl".to_string(),
+                line: 3,
+                column: 5,
+                span: 20..21,
+            },
+            message: "found 'x' but expected '}'".to_string(),
+        }]);
+        let diagnostics = Diagnostic::from_error("schemas/a.avdl", &error);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                path: "schemas/a.avdl".to_string(),
+                line: Some(3),
+                column: Some(5),
+                start: Some(20),
+                end: Some(21),
+                kind: "FailedParsing".to_string(),
+                message: "found 'x' but expected '}'".to_string(),
+            }]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "schemas/a.avdl:3:5: found 'x' but expected '}'"
+        );
+
+        let error = AvroError::UndefinedReference(
+            "Field of type 'Meal' cannot be found!".to_string(),
+            None,
+        );
+        let diagnostics = Diagnostic::from_error("schemas/a.avdl", &error);
+        assert_eq!(diagnostics[0].line, None);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "schemas/a.avdl: Field of type 'Meal' cannot be found!"
+        );
+
+        let error = IdlCompiler::new()
+            .with_path("schemas/b.avdl")
+            .compile("protocol Event {\n  record Person { Meal meal; }\n}")
+            .unwrap_err();
+        let diagnostics = Diagnostic::from_error("schemas/b.avdl", &error);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                path: "schemas/b.avdl".to_string(),
+                line: Some(2),
+                column: Some(19),
+                start: Some(35),
+                end: Some(39),
+                kind: "UndefinedReference".to_string(),
+                message: "Field of type 'Meal' cannot be found!".to_string(),
+            }]
+        );
+    }
+}
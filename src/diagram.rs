@@ -0,0 +1,398 @@
+//! Class diagrams of the types of a protocol or schema file, as Graphviz DOT or Mermaid
+
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::ast::Field;
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, NamedSchema, RecordSchema, Schema};
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum DiagramFormat {
+    Dot,
+    Mermaid,
+}
+
+/// Generates a class diagram where records and enums are nodes listing their fields or
+/// symbols, and fields referencing other types are edges labelled with the field name.
+/// References through arrays are marked with `*` and nullable ones with `0..1`. Types
+/// brought in by an import can be grouped into a cluster per import.
+pub struct DiagramGenerator {
+    field: Field,
+    format: DiagramFormat,
+    clusters: Vec<(String, Vec<String>)>,
+}
+
+struct Edge<'a> {
+    from: &'a NamedSchema,
+    to: &'a NamedSchema,
+    label: &'a str,
+    multiplicity: Option<&'static str>,
+}
+
+impl DiagramGenerator {
+    pub fn new(field: Field) -> Self {
+        Self {
+            field,
+            format: DiagramFormat::Dot,
+            clusters: vec![],
+        }
+    }
+
+    pub fn with_format(mut self, format: DiagramFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Group types into clusters, given as a label with the full names of its types, e.g.
+    /// the imports of a file from `AvroIdlLexer::imported_types`
+    pub fn with_clusters(mut self, clusters: Vec<(String, Vec<String>)>) -> Self {
+        self.clusters = clusters;
+        self
+    }
+
+    pub fn generate(self) -> Result<String, AvroError> {
+        let document = Document::try_from(self.field)?;
+        let name = match &document {
+            Document::Protocol(protocol) => protocol.name(),
+            Document::Schema(_) => "Schema",
+        };
+
+        // Each type is drawn in the first cluster listing it, or else outside of clusters
+        let cluster_of = |named: &NamedSchema| {
+            self.clusters
+                .iter()
+                .position(|(_, types)| types.contains(&named.fullname()))
+        };
+        let mut groups: Vec<(Option<&str>, Vec<&NamedSchema>)> = vec![(
+            None,
+            document
+                .types()
+                .iter()
+                .filter(|named| cluster_of(named).is_none())
+                .collect(),
+        )];
+        for (index, (label, _)) in self.clusters.iter().enumerate() {
+            let types: Vec<&NamedSchema> = document
+                .types()
+                .iter()
+                .filter(|named| cluster_of(named) == Some(index))
+                .collect();
+            if !types.is_empty() {
+                groups.push((Some(label), types));
+            }
+        }
+
+        let edges = edges(&document);
+        Ok(match self.format {
+            DiagramFormat::Dot => dot(name, &groups, &edges),
+            DiagramFormat::Mermaid => mermaid(&groups, &edges),
+        })
+    }
+}
+
+/// Edges of the fields of every record referencing a record or enum
+fn edges(document: &Document) -> Vec<Edge<'_>> {
+    fn targets<'a>(
+        schema: &'a Schema,
+        multiplicity: Option<&'static str>,
+        found: &mut Vec<(&'a str, Option<&'static str>)>,
+    ) {
+        match schema {
+            Schema::Record(name) | Schema::Enum(name) => found.push((name, multiplicity)),
+            Schema::Array(inner) => targets(inner, Some("*"), found),
+            Schema::Union(types) => {
+                let multiplicity = if types.contains(&Schema::Null) {
+                    multiplicity.or(Some("0..1"))
+                } else {
+                    multiplicity
+                };
+                for type_ in types.iter() {
+                    targets(type_, multiplicity, found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut edges = vec![];
+    for named in document.types() {
+        let NamedSchema::Record(record) = named else {
+            continue;
+        };
+        for field in record.fields() {
+            let mut found = vec![];
+            targets(field.schema(), None, &mut found);
+            for (name, multiplicity) in found {
+                if let Some(to) = document.named_type(name) {
+                    edges.push(Edge {
+                        from: named,
+                        to,
+                        label: field.name(),
+                        multiplicity,
+                    });
+                }
+            }
+        }
+    }
+    edges
+}
+
+/// Type of a field as shown in a node, with references by their short name
+fn type_label(schema: &Schema) -> String {
+    if let Some(inner) = schema.nullable_inner() {
+        return format!("{}?", type_label(inner));
+    }
+    match schema {
+        Schema::Array(inner) => format!("array<{}>", type_label(inner)),
+        Schema::Union(types) => {
+            let types: Vec<String> = types.iter().map(type_label).collect();
+            format!("union<{}>", types.join(", "))
+        }
+        Schema::Record(name) | Schema::Enum(name) => {
+            name.rsplit('.').next().unwrap_or(name).to_string()
+        }
+        primitive => primitive.type_name().to_string(),
+    }
+}
+
+fn dot(name: &str, groups: &[(Option<&str>, Vec<&NamedSchema>)], edges: &[Edge]) -> String {
+    let mut out = format!(
+        "// Generated by avro-idl, do not edit\ndigraph {} {{\n  node [shape=record];\n",
+        dot_string(name)
+    );
+    for (index, (label, types)) in groups.iter().enumerate() {
+        let indent = match label {
+            Some(label) => {
+                let _ = writeln!(out, "\n  subgraph cluster_{} {{", index);
+                let _ = writeln!(out, "    label={};", dot_string(label));
+                "    "
+            }
+            None => {
+                if !types.is_empty() {
+                    out.push('\n');
+                }
+                "  "
+            }
+        };
+        for named in types.iter() {
+            let label = match named {
+                NamedSchema::Record(record) => dot_record(record),
+                NamedSchema::Enum(enum_) => dot_enum(enum_),
+            };
+            let _ = writeln!(
+                out,
+                "{}{} [label={}];",
+                indent,
+                dot_string(&named.fullname()),
+                dot_string(&label)
+            );
+        }
+        if label.is_some() {
+            out.push_str("  }\n");
+        }
+    }
+    if !edges.is_empty() {
+        out.push('\n');
+    }
+    for edge in edges {
+        let mut attributes = format!("label={}", dot_string(edge.label));
+        if let Some(multiplicity) = edge.multiplicity {
+            let _ = write!(attributes, ", headlabel={}", dot_string(multiplicity));
+        }
+        let _ = writeln!(
+            out,
+            "  {} -> {} [{}];",
+            dot_string(&edge.from.fullname()),
+            dot_string(&edge.to.fullname()),
+            attributes
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Label of a record node with a row per field
+fn dot_record(record: &RecordSchema) -> String {
+    let fields: String = record
+        .fields()
+        .iter()
+        .map(|field| {
+            format!(
+                "{}: {}\\l",
+                escape_record(field.name()),
+                escape_record(&type_label(field.schema()))
+            )
+        })
+        .collect();
+    format!("{{{}|{}}}", escape_record(record.name()), fields)
+}
+
+fn dot_enum(enum_: &EnumSchema) -> String {
+    let symbols: String = enum_
+        .symbols()
+        .iter()
+        .map(|symbol| format!("{}\\l", escape_record(symbol)))
+        .collect();
+    format!(
+        "{{\\<\\<enum\\>\\> {}|{}}}",
+        escape_record(enum_.name()),
+        symbols
+    )
+}
+
+/// Escape the characters which structure the label of a record node
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A quoted DOT string, where backslashes are kept for the escapes of labels
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\\\""))
+}
+
+fn mermaid(groups: &[(Option<&str>, Vec<&NamedSchema>)], edges: &[Edge]) -> String {
+    let mut out = "%% Generated by avro-idl, do not edit\nclassDiagram\n".to_string();
+    for (label, types) in groups.iter() {
+        let indent = match label {
+            Some(label) => {
+                let _ = writeln!(out, "  namespace {} {{", mermaid_id(label));
+                "    "
+            }
+            None => "  ",
+        };
+        for named in types.iter() {
+            let _ = writeln!(
+                out,
+                "{}class {}[\"{}\"] {{",
+                indent,
+                mermaid_id(&named.fullname()),
+                named.name()
+            );
+            match named {
+                NamedSchema::Record(record) => {
+                    for field in record.fields() {
+                        let type_ = type_label(field.schema()).replace(['<', '>'], "~");
+                        let _ = writeln!(out, "{}  {} {}", indent, type_, field.name());
+                    }
+                }
+                NamedSchema::Enum(enum_) => {
+                    let _ = writeln!(out, "{}  <<enumeration>>", indent);
+                    for symbol in enum_.symbols() {
+                        let _ = writeln!(out, "{}  {}", indent, symbol);
+                    }
+                }
+            }
+            let _ = writeln!(out, "{}}}", indent);
+        }
+        if label.is_some() {
+            out.push_str("  }\n");
+        }
+    }
+    for edge in edges {
+        let multiplicity = edge
+            .multiplicity
+            .map(|multiplicity| format!("\"{}\" ", multiplicity))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "  {} --> {}{} : {}",
+            mermaid_id(&edge.from.fullname()),
+            multiplicity,
+            mermaid_id(&edge.to.fullname()),
+            edge.label
+        );
+    }
+    out
+}
+
+/// Identifier of a class or namespace, which can't contain dots or other punctuation
+fn mermaid_id(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiagramFormat, DiagramGenerator};
+    use crate::compiler::IdlCompiler;
+
+    const SRC: &str = "@namespace(\"org.example\")
+protocol Event {
+  enum Meal { DINNER, LUNCH }
+  record Address {
+    string city;
+  }
+  record Person {
+    string name;
+    union { null, Address } address;
+    array<Meal> meals;
+  }
+}";
+
+    #[test]
+    fn test_generate_dot() {
+        let protocol = IdlCompiler::new().compile(SRC).unwrap();
+        let expected = r#"// Generated by avro-idl, do not edit
+digraph "Event" {
+  node [shape=record];
+
+  "org.example.Meal" [label="{\<\<enum\>\> Meal|DINNER\lLUNCH\l}"];
+  "org.example.Person" [label="{Person|name: string\laddress: Address?\lmeals: array\<Meal\>\l}"];
+
+  subgraph cluster_1 {
+    label="common.avdl";
+    "org.example.Address" [label="{Address|city: string\l}"];
+  }
+
+  "org.example.Person" -> "org.example.Address" [label="address", headlabel="0..1"];
+  "org.example.Person" -> "org.example.Meal" [label="meals", headlabel="*"];
+}
+"#;
+        let generated = DiagramGenerator::new(protocol)
+            .with_clusters(vec![(
+                "common.avdl".to_string(),
+                vec!["org.example.Address".to_string()],
+            )])
+            .generate()
+            .unwrap();
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_generate_mermaid() {
+        let protocol = IdlCompiler::new().compile(SRC).unwrap();
+        let expected = r#"%% Generated by avro-idl, do not edit
+classDiagram
+  class org_example_Meal["Meal"] {
+    <<enumeration>>
+    DINNER
+    LUNCH
+  }
+  class org_example_Address["Address"] {
+    string city
+  }
+  class org_example_Person["Person"] {
+    string name
+    Address? address
+    array~Meal~ meals
+  }
+  org_example_Person --> "0..1" org_example_Address : address
+  org_example_Person --> "*" org_example_Meal : meals
+"#;
+        let generated = DiagramGenerator::new(protocol)
+            .with_format(DiagramFormat::Mermaid)
+            .generate()
+            .unwrap();
+        assert_eq!(generated, expected);
+    }
+}
@@ -0,0 +1,337 @@
+//! Structural differences between two versions of a protocol or schema file
+
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::ast::Field;
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, FieldDef, NamedSchema, RecordSchema, Schema};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// The part of a file which changed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Element {
+    Name,
+    Namespace,
+    Doc,
+    Record,
+    Enum,
+    Field,
+    Type,
+    Default,
+    Symbol,
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Element::Name => "name",
+            Element::Namespace => "namespace",
+            Element::Doc => "doc",
+            Element::Record => "record",
+            Element::Enum => "enum",
+            Element::Field => "field",
+            Element::Type => "type",
+            Element::Default => "default",
+            Element::Symbol => "symbol",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A difference at a path like `Person.age`, with the old and new value where there is one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    kind: ChangeKind,
+    element: Element,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new: Option<Value>,
+}
+
+impl Change {
+    pub fn kind(&self) -> ChangeKind {
+        self.kind
+    }
+
+    pub fn element(&self) -> Element {
+        self.element
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn old_value(&self) -> Option<&Value> {
+        self.old.as_ref()
+    }
+
+    pub fn new_value(&self) -> Option<&Value> {
+        self.new.as_ref()
+    }
+}
+
+/// Lines like `+ field Person.email: string` or `~ type Person.age: int -> long`
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |value: &Value| match value {
+            Value::String(text) if self.element != Element::Doc => text.clone(),
+            value => value.to_string(),
+        };
+        let sign = match self.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        };
+        write!(f, "{} {} {}", sign, self.element, self.path)?;
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, ": {} -> {}", value(old), value(new)),
+            (Some(value_), None) | (None, Some(value_)) => write!(f, ": {}", value(value_)),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Lists the types, fields, symbols, defaults and docs which were added, removed or
+/// changed between two versions. Types are paired by full name, or else by name, in
+/// which case their namespace changed. Paths use the name of types, e.g. `Person.age`.
+pub struct SchemaDiff {
+    old: Field,
+    new: Field,
+}
+
+impl SchemaDiff {
+    pub fn new(old: Field, new: Field) -> Self {
+        Self { old, new }
+    }
+
+    pub fn diff(self) -> Result<Vec<Change>, AvroError> {
+        let old = Document::try_from(self.old)?;
+        let new = Document::try_from(self.new)?;
+        let mut differ = Differ { changes: vec![] };
+        differ.document(&old, &new);
+        Ok(differ.changes)
+    }
+}
+
+struct Differ {
+    changes: Vec<Change>,
+}
+
+impl Differ {
+    fn document(&mut self, old: &Document, new: &Document) {
+        match (old, new) {
+            (Document::Protocol(old), Document::Protocol(new)) => {
+                let path = new.name();
+                self.compare(Element::Name, path, Some(old.name()), Some(new.name()));
+                self.compare(Element::Namespace, path, old.namespace(), new.namespace());
+                self.compare(Element::Doc, path, old.doc(), new.doc());
+            }
+            (Document::Schema(old), Document::Schema(new)) => {
+                let path = "schema";
+                self.compare(Element::Namespace, path, old.namespace(), new.namespace());
+                let main = |schema: Option<&Schema>| schema.map(|s| s.to_string());
+                self.compare(Element::Type, path, main(old.main()), main(new.main()));
+            }
+            // A protocol turned into a schema file or the reverse only compares its types
+            _ => {}
+        }
+
+        let mut paired = vec![];
+        for named in new.types() {
+            let previous = old
+                .types()
+                .iter()
+                .find(|o| o.fullname() == named.fullname())
+                .or_else(|| old.types().iter().find(|o| o.name() == named.name()));
+            match previous {
+                // A type which changed between record and enum is removed and added
+                Some(previous) if element(previous) == element(named) => {
+                    paired.push(previous.fullname());
+                    self.named(previous, named);
+                    match (previous, named) {
+                        (NamedSchema::Record(previous), NamedSchema::Record(record)) => {
+                            self.record(previous, record)
+                        }
+                        (NamedSchema::Enum(previous), NamedSchema::Enum(enum_)) => {
+                            self.enumeration(previous, enum_)
+                        }
+                        _ => {}
+                    }
+                }
+                _ => self.add(ChangeKind::Added, element(named), named.name(), None),
+            }
+        }
+        for named in old.types() {
+            if !paired.contains(&named.fullname()) {
+                self.add(ChangeKind::Removed, element(named), named.name(), None);
+            }
+        }
+    }
+
+    /// Changes of the namespace and doc of a named type
+    fn named(&mut self, old: &NamedSchema, new: &NamedSchema) {
+        self.compare(
+            Element::Namespace,
+            new.name(),
+            old.namespace(),
+            new.namespace(),
+        );
+        self.compare(Element::Doc, new.name(), old.doc(), new.doc());
+    }
+
+    fn record(&mut self, old: &RecordSchema, new: &RecordSchema) {
+        for field in new.fields() {
+            let path = format!("{}.{}", new.name(), field.name());
+            let Some(previous) = old.field(field.name()) else {
+                let type_ = json!(field.schema().to_string());
+                self.add(ChangeKind::Added, Element::Field, &path, Some(type_));
+                continue;
+            };
+            let type_ = |field: &FieldDef| Some(field.schema().to_string());
+            self.compare(Element::Type, &path, type_(previous), type_(field));
+            self.compare(Element::Default, &path, previous.default(), field.default());
+            self.compare(Element::Doc, &path, previous.doc(), field.doc());
+        }
+        for field in old.fields() {
+            if new.field(field.name()).is_none() {
+                let path = format!("{}.{}", new.name(), field.name());
+                let type_ = json!(field.schema().to_string());
+                self.add(ChangeKind::Removed, Element::Field, &path, Some(type_));
+            }
+        }
+    }
+
+    fn enumeration(&mut self, old: &EnumSchema, new: &EnumSchema) {
+        for symbol in new.symbols() {
+            if !old.symbols().contains(symbol) {
+                let path = format!("{}.{}", new.name(), symbol);
+                self.add(ChangeKind::Added, Element::Symbol, &path, None);
+            }
+        }
+        for symbol in old.symbols() {
+            if !new.symbols().contains(symbol) {
+                let path = format!("{}.{}", new.name(), symbol);
+                self.add(ChangeKind::Removed, Element::Symbol, &path, None);
+            }
+        }
+        self.compare(Element::Default, new.name(), old.default(), new.default());
+    }
+
+    /// Record the difference between two optional values, if any
+    fn compare<T: Serialize + PartialEq>(
+        &mut self,
+        element: Element,
+        path: &str,
+        old: Option<T>,
+        new: Option<T>,
+    ) {
+        if old == new {
+            return;
+        }
+        let old = old.map(|old| json!(old));
+        let new = new.map(|new| json!(new));
+        let kind = match (&old, &new) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            _ => ChangeKind::Changed,
+        };
+        self.changes.push(Change {
+            kind,
+            element,
+            path: path.to_string(),
+            old,
+            new,
+        });
+    }
+
+    fn add(&mut self, kind: ChangeKind, element: Element, path: &str, value: Option<Value>) {
+        let (old, new) = match kind {
+            ChangeKind::Removed => (value, None),
+            _ => (None, value),
+        };
+        self.changes.push(Change {
+            kind,
+            element,
+            path: path.to_string(),
+            old,
+            new,
+        });
+    }
+}
+
+fn element(named: &NamedSchema) -> Element {
+    match named {
+        NamedSchema::Record(_) => Element::Record,
+        NamedSchema::Enum(_) => Element::Enum,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::SchemaDiff;
+    use crate::compiler::IdlCompiler;
+
+    #[test]
+    fn test_diff() {
+        let old = "@namespace(\"org.example\")
+protocol Event {
+  enum Meal { DINNER, LUNCH }
+  record Address { string city; }
+  /** A person */
+  record Person {
+    string name;
+    int age = 0;
+    double height;
+  }
+}";
+        let new = "@namespace(\"org.example\")
+protocol Event {
+  enum Meal { DINNER, BREAKFAST } = DINNER;
+  /** Someone */
+  record Person {
+    /** Full name */
+    string name;
+    long age = 1;
+    string? email;
+  }
+}";
+        let old = IdlCompiler::new().compile(old).unwrap();
+        let new = IdlCompiler::new().compile(new).unwrap();
+        let changes = SchemaDiff::new(old, new).diff().unwrap();
+        let lines: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "+ symbol Meal.BREAKFAST",
+                "- symbol Meal.LUNCH",
+                "+ default Meal: DINNER",
+                "~ doc Person: \"A person\" -> \"Someone\"",
+                "+ doc Person.name: \"Full name\"",
+                "~ type Person.age: int -> long",
+                "~ default Person.age: 0 -> 1",
+                "+ field Person.email: union { string, null }",
+                "- field Person.height: double",
+                "- record Address",
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&changes[5]).unwrap(),
+            json!({"kind": "changed", "element": "type", "path": "Person.age", "old": "int", "new": "long"})
+        );
+    }
+}
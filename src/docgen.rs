@@ -0,0 +1,486 @@
+//! Reference documentation of compiled IDL files, rendered as Markdown or HTML pages
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::ast::Field;
+use crate::codegen::doc_lines;
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, NamedSchema, RecordSchema, Schema};
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+impl DocFormat {
+    /// File extension of the pages
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocFormat::Markdown => "md",
+            DocFormat::Html => "html",
+        }
+    }
+}
+
+/// Renders a page per protocol or schema file, with a section per type and a table of the
+/// fields of each record, plus an index of all types by namespace. References to records
+/// and enums link to their section on the same page, since every file includes the types
+/// it uses. The index links to the first page defining each type.
+pub struct DocGenerator {
+    format: DocFormat,
+    pages: Vec<(PathBuf, Document)>,
+}
+
+impl Default for DocGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DocGenerator {
+    pub fn new() -> Self {
+        Self {
+            format: DocFormat::Markdown,
+            pages: vec![],
+        }
+    }
+
+    pub fn with_format(mut self, format: DocFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Add a page for a linked protocol or schema file. The path is relative to the output
+    /// directory, the extension is replaced by the one of the format.
+    pub fn add(&mut self, path: impl AsRef<Path>, field: Field) -> Result<(), AvroError> {
+        let path = path.as_ref().with_extension(self.format.extension());
+        self.pages.push((path, Document::try_from(field)?));
+        Ok(())
+    }
+
+    /// Render the pages and the index, as paths relative to the output directory with
+    /// their content
+    pub fn generate(&self) -> Vec<(PathBuf, String)> {
+        let mut pages: Vec<(PathBuf, String)> = self
+            .pages
+            .iter()
+            .map(|(path, document)| (path.clone(), self.page(path, document)))
+            .collect();
+        let index = PathBuf::from("index").with_extension(self.format.extension());
+        pages.push((index, self.index()));
+        pages
+    }
+
+    fn page(&self, path: &Path, document: &Document) -> String {
+        let mut page = PageWriter::new(self.format);
+        let (title, namespace, doc) = match document {
+            Document::Protocol(protocol) => (
+                format!("Protocol {}", protocol.name()),
+                protocol.namespace(),
+                protocol.doc(),
+            ),
+            Document::Schema(schema) => {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                (format!("Schema {}", name), schema.namespace(), None)
+            }
+        };
+        page.start(&title);
+        page.heading(1, &title, None);
+
+        // Pages in subdirectories link back up to the index
+        let depth = path.components().count().saturating_sub(1);
+        let index = format!("{}index.{}", "../".repeat(depth), self.format.extension());
+        page.paragraph(&page.link("Index", &index));
+        if let Some(namespace) = namespace {
+            page.paragraph(&format!("Namespace: {}", page.code(namespace)));
+        }
+        if let Some(doc) = doc {
+            page.doc(doc);
+        }
+        if let Document::Schema(schema) = document {
+            if let Some(main) = schema.main() {
+                let main = page.schema(document, main);
+                page.paragraph(&format!("Main schema: {}", main));
+            }
+        }
+
+        if !document.types().is_empty() {
+            page.heading(2, "Types", None);
+            let items: Vec<String> = document
+                .types()
+                .iter()
+                .map(|named| page.link(named.name(), &format!("#{}", named.fullname())))
+                .collect();
+            page.list(&items);
+        }
+        for named in document.types() {
+            match named {
+                NamedSchema::Record(record) => page.record(document, record),
+                NamedSchema::Enum(enum_) => page.enumeration(enum_),
+            }
+        }
+        page.finish()
+    }
+
+    fn index(&self) -> String {
+        // Types by namespace and full name, linking to the first page defining them
+        let mut namespaces: BTreeMap<Option<&str>, BTreeMap<String, (&NamedSchema, &Path)>> =
+            BTreeMap::new();
+        for (path, document) in self.pages.iter() {
+            for named in document.types() {
+                namespaces
+                    .entry(named.namespace())
+                    .or_default()
+                    .entry(named.fullname())
+                    .or_insert((named, path));
+            }
+        }
+
+        let mut page = PageWriter::new(self.format);
+        page.start("Index");
+        page.heading(1, "Index", None);
+        page.heading(2, "Files", None);
+        let files: Vec<String> = self
+            .pages
+            .iter()
+            .map(|(path, _)| {
+                let href = link_path(path);
+                page.link(&path.with_extension("").to_string_lossy(), &href)
+            })
+            .collect();
+        page.list(&files);
+
+        for (namespace, types) in namespaces.iter() {
+            match namespace {
+                Some(namespace) => page.heading(2, namespace, None),
+                None => page.heading(2, "No namespace", None),
+            }
+            let items: Vec<String> = types
+                .iter()
+                .map(|(fullname, (named, path))| {
+                    let kind = match named {
+                        NamedSchema::Record(_) => "record",
+                        NamedSchema::Enum(_) => "enum",
+                    };
+                    let href = format!("{}#{}", link_path(path), fullname);
+                    format!("{} ({})", page.link(named.name(), &href), kind)
+                })
+                .collect();
+            page.list(&items);
+        }
+        page.finish()
+    }
+}
+
+/// Path of a page as used in links, with forward slashes on every platform
+fn link_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+struct PageWriter {
+    format: DocFormat,
+    out: String,
+}
+
+impl PageWriter {
+    fn new(format: DocFormat) -> Self {
+        Self {
+            format,
+            out: String::new(),
+        }
+    }
+
+    fn start(&mut self, title: &str) {
+        if self.format == DocFormat::Html {
+            let _ = writeln!(
+                self.out,
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>",
+                escape_html(title)
+            );
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if self.format == DocFormat::Html {
+            self.out.push_str("</body>\n</html>\n");
+        }
+        self.out
+    }
+
+    fn record(&mut self, document: &Document, record: &RecordSchema) {
+        let title = format!("Record {}", record.name());
+        self.heading(2, &title, Some(&record.fullname()));
+        self.paragraph(&format!("Full name: {}", self.code(&record.fullname())));
+        if let Some(doc) = record.doc() {
+            self.doc(doc);
+        }
+        let rows: Vec<[String; 4]> = record
+            .fields()
+            .iter()
+            .map(|field| {
+                [
+                    self.code(field.name()),
+                    self.schema(document, field.schema()),
+                    field
+                        .default()
+                        .map(|default| self.code(&default.to_string()))
+                        .unwrap_or_default(),
+                    field.doc().map(|doc| self.inline(doc)).unwrap_or_default(),
+                ]
+            })
+            .collect();
+        self.table(["Field", "Type", "Default", "Description"], &rows);
+    }
+
+    fn enumeration(&mut self, enum_: &EnumSchema) {
+        let title = format!("Enum {}", enum_.name());
+        self.heading(2, &title, Some(&enum_.fullname()));
+        self.paragraph(&format!("Full name: {}", self.code(&enum_.fullname())));
+        if let Some(doc) = enum_.doc() {
+            self.doc(doc);
+        }
+        let symbols: Vec<String> = enum_.symbols().iter().map(|s| self.code(s)).collect();
+        self.paragraph(&format!("Symbols: {}", symbols.join(", ")));
+        if let Some(default) = enum_.default() {
+            self.paragraph(&format!("Default: {}", self.code(default)));
+        }
+    }
+
+    /// A type, where records and enums link to their section
+    fn schema(&self, document: &Document, schema: &Schema) -> String {
+        match schema {
+            Schema::Array(inner) => format!(
+                "{}{}{}",
+                self.text("array<"),
+                self.schema(document, inner),
+                self.text(">")
+            ),
+            Schema::Union(types) => {
+                let types: Vec<String> = types.iter().map(|t| self.schema(document, t)).collect();
+                format!("union {{ {} }}", types.join(", "))
+            }
+            Schema::Record(name) | Schema::Enum(name) => match document.named_type(name) {
+                Some(named) => self.link(named.name(), &format!("#{}", named.fullname())),
+                None => self.code(name),
+            },
+            primitive => self.code(primitive.type_name()),
+        }
+    }
+
+    fn heading(&mut self, level: usize, text: &str, anchor: Option<&str>) {
+        match self.format {
+            DocFormat::Markdown => {
+                if let Some(anchor) = anchor {
+                    let _ = writeln!(self.out, "<a id=\"{}\"></a>\n", escape_html(anchor));
+                }
+                let _ = writeln!(self.out, "{} {}\n", "#".repeat(level), text);
+            }
+            DocFormat::Html => {
+                let id = anchor
+                    .map(|anchor| format!(" id=\"{}\"", escape_html(anchor)))
+                    .unwrap_or_default();
+                let _ = writeln!(self.out, "<h{level}{}>{}</h{level}>", id, escape_html(text));
+            }
+        }
+    }
+
+    /// A paragraph of already formatted text
+    fn paragraph(&mut self, text: &str) {
+        match self.format {
+            DocFormat::Markdown => {
+                let _ = writeln!(self.out, "{}\n", text);
+            }
+            DocFormat::Html => {
+                let _ = writeln!(self.out, "<p>{}</p>", text);
+            }
+        }
+    }
+
+    /// A docstring as a paragraph, keeping its line breaks
+    fn doc(&mut self, doc: &str) {
+        let lines: Vec<String> = doc_lines(doc).into_iter().map(|l| self.text(l)).collect();
+        match self.format {
+            DocFormat::Markdown => self.paragraph(&lines.join("\n")),
+            DocFormat::Html => self.paragraph(&lines.join("<br>\n")),
+        }
+    }
+
+    fn list(&mut self, items: &[String]) {
+        match self.format {
+            DocFormat::Markdown => {
+                for item in items {
+                    let _ = writeln!(self.out, "- {}", item);
+                }
+                self.out.push('\n');
+            }
+            DocFormat::Html => {
+                self.out.push_str("<ul>\n");
+                for item in items {
+                    let _ = writeln!(self.out, "<li>{}</li>", item);
+                }
+                self.out.push_str("</ul>\n");
+            }
+        }
+    }
+
+    fn table(&mut self, headers: [&str; 4], rows: &[[String; 4]]) {
+        match self.format {
+            DocFormat::Markdown => {
+                let _ = writeln!(self.out, "| {} |", headers.join(" | "));
+                let _ = writeln!(self.out, "|{}", " --- |".repeat(headers.len()));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|c| c.replace('|', "\\|")).collect();
+                    let _ = writeln!(self.out, "| {} |", cells.join(" | "));
+                }
+                self.out.push('\n');
+            }
+            DocFormat::Html => {
+                self.out.push_str("<table>\n<tr>");
+                for header in headers {
+                    let _ = write!(self.out, "<th>{}</th>", header);
+                }
+                self.out.push_str("</tr>\n");
+                for row in rows {
+                    self.out.push_str("<tr>");
+                    for cell in row {
+                        let _ = write!(self.out, "<td>{}</td>", cell);
+                    }
+                    self.out.push_str("</tr>\n");
+                }
+                self.out.push_str("</table>\n");
+            }
+        }
+    }
+
+    /// A docstring on a single line, e.g. for a table cell
+    fn inline(&self, doc: &str) -> String {
+        self.text(&doc_lines(doc).join(" "))
+    }
+
+    fn link(&self, text: &str, href: &str) -> String {
+        match self.format {
+            DocFormat::Markdown => format!("[{}]({})", self.text(text), href),
+            DocFormat::Html => format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(href),
+                escape_html(text)
+            ),
+        }
+    }
+
+    fn code(&self, text: &str) -> String {
+        match self.format {
+            DocFormat::Markdown => format!("`{}`", text.replace('`', "'")),
+            DocFormat::Html => format!("<code>{}</code>", escape_html(text)),
+        }
+    }
+
+    /// Plain text, escaping what would be taken as markup
+    fn text(&self, text: &str) -> String {
+        match self.format {
+            DocFormat::Markdown => text.replace('<', "&lt;").replace('>', "&gt;"),
+            DocFormat::Html => escape_html(text),
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::DocGenerator;
+    use crate::compiler::IdlCompiler;
+
+    #[test]
+    fn test_generate_markdown() {
+        let src = "@namespace(\"org.example\")
+/** Events of a person */
+protocol Event {
+  enum Meal { DINNER, LUNCH } = LUNCH;
+  /** A person */
+  record Person {
+    /** Full name */
+    string name = \"Bob\";
+    union { null, Meal } meal = null;
+    array<Person> friends;
+  }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        let mut generator = DocGenerator::new();
+        generator.add("events/event.avdl", protocol).unwrap();
+        let pages = generator.generate();
+
+        let expected_page = r#"# Protocol Event
+
+[Index](../index.md)
+
+Namespace: `org.example`
+
+Events of a person
+
+## Types
+
+- [Meal](#org.example.Meal)
+- [Person](#org.example.Person)
+
+<a id="org.example.Meal"></a>
+
+## Enum Meal
+
+Full name: `org.example.Meal`
+
+Symbols: `DINNER`, `LUNCH`
+
+Default: `LUNCH`
+
+<a id="org.example.Person"></a>
+
+## Record Person
+
+Full name: `org.example.Person`
+
+A person
+
+| Field | Type | Default | Description |
+| --- | --- | --- | --- |
+| `name` | `string` | `"Bob"` | Full name |
+| `meal` | union { `null`, [Meal](#org.example.Meal) } | `null` |  |
+| `friends` | array&lt;[Person](#org.example.Person)&gt; |  |  |
+
+"#;
+        let expected_index = "# Index
+
+## Files
+
+- [events/event](events/event.md)
+
+## org.example
+
+- [Meal](events/event.md#org.example.Meal) (enum)
+- [Person](events/event.md#org.example.Person) (record)
+
+";
+        assert_eq!(
+            pages,
+            vec![
+                (PathBuf::from("events/event.md"), expected_page.to_string()),
+                (PathBuf::from("index.md"), expected_index.to_string())
+            ]
+        );
+    }
+}
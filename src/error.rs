@@ -1,23 +1,102 @@
 use std::error::Error;
 use std::fmt;
+use std::ops::Range;
+use std::path::Path;
 
 #[derive(Clone, Debug)]
 pub enum AvroError {
     InvalidASTDataType(String),
-    FailedParsing(String),
+    /// Every error found while parsing a file
+    FailedParsing(Vec<ParseError>),
     MissingName(String),
-    UndefinedReference(String),
+    /// Message and the location of the reference, if it was parsed from a file
+    UndefinedReference(String, Option<Location>),
+    FailedImport(String),
+    FailedReading(String),
+    FailedWriting(String),
+    InvalidJson(String),
+}
+
+impl AvroError {
+    /// Name of the kind of error
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AvroError::InvalidASTDataType(_) => "InvalidASTDataType",
+            AvroError::FailedParsing(_) => "FailedParsing",
+            AvroError::MissingName(_) => "MissingName",
+            AvroError::UndefinedReference(..) => "UndefinedReference",
+            AvroError::FailedImport(_) => "FailedImport",
+            AvroError::FailedReading(_) => "FailedReading",
+            AvroError::FailedWriting(_) => "FailedWriting",
+            AvroError::InvalidJson(_) => "InvalidJson",
+        }
+    }
 }
 
 impl fmt::Display for AvroError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AvroError::InvalidASTDataType(message) => write!(f, "{}", message),
-            AvroError::FailedParsing(message) => write!(f, "{}", message),
+            AvroError::FailedParsing(errors) => {
+                let lines: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            }
             AvroError::MissingName(message) => write!(f, "{}", message),
-            AvroError::UndefinedReference(message) => write!(f, "{}", message),
+            AvroError::UndefinedReference(message, Some(location)) => {
+                write!(f, "{}: {}", location, message)
+            }
+            AvroError::UndefinedReference(message, None) => write!(f, "{}", message),
+            AvroError::FailedImport(message) => write!(f, "{}", message),
+            AvroError::FailedReading(message) => write!(f, "{}", message),
+            AvroError::FailedWriting(message) => write!(f, "{}", message),
+            AvroError::InvalidJson(message) => write!(f, "{}", message),
         }
     }
 }
 
 impl Error for AvroError {}
+
+/// A position in a source file, where lines and columns start at 1 and the span holds the
+/// offsets of the characters
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub span: Range<usize>,
+}
+
+impl Location {
+    /// Locate a span of the characters of a source
+    pub fn new(path: &Path, src: &str, span: Range<usize>) -> Self {
+        let preceding = src.chars().take(span.start).collect::<String>();
+        let line = preceding.matches('\n').count() + 1;
+        let column = preceding.chars().rev().take_while(|c| *c != '\n').count() + 1;
+        Self {
+            path: path.display().to_string(),
+            line,
+            column,
+            span,
+        }
+    }
+}
+
+/// Formatted as `path:line:column`
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path, self.line, self.column)
+    }
+}
+
+/// A syntax error at a location of a file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub location: Location,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
@@ -0,0 +1,187 @@
+//! Export to JSON Schema draft 2020-12, e.g. to validate JSON payloads with the same schemas
+
+use serde_json::{json, Map, Value};
+
+use crate::ast::Field;
+use crate::error::AvroError;
+use crate::schema::{Document, EnumSchema, NamedSchema, RecordSchema, Schema};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Serialize a protocol or schema file to JSON Schema. Named types are defined in `$defs`
+/// by their full name and referenced with `$ref`. The main schema of a schema file is the
+/// root schema, while a protocol only defines its types. Fields are required unless they
+/// have a default or are nullable.
+pub struct JsonSchemaSerializer {
+    field: Field,
+}
+
+impl JsonSchemaSerializer {
+    pub fn new(field: Field) -> Self {
+        Self { field }
+    }
+
+    pub fn serialize(self) -> Result<Value, AvroError> {
+        let document = Document::try_from(self.field)?;
+        let serializer = JsonSchemaWriter;
+
+        let mut root = json!({"$schema": DRAFT});
+        match &document {
+            Document::Protocol(protocol) => {
+                root["title"] = json!(protocol.name());
+                if let Some(doc) = protocol.doc() {
+                    root["description"] = json!(doc);
+                }
+            }
+            Document::Schema(schema) => {
+                if let Some(main) = schema.main() {
+                    let Value::Object(main) = serializer.schema(main) else {
+                        unreachable!("Schemas are serialized to objects");
+                    };
+                    root.as_object_mut().unwrap().extend(main);
+                }
+            }
+        }
+
+        let defs: Map<String, Value> = document
+            .types()
+            .iter()
+            .map(|named| {
+                let schema = match named {
+                    NamedSchema::Record(record) => serializer.record(record),
+                    NamedSchema::Enum(enum_) => serializer.enumeration(enum_),
+                };
+                (named.fullname(), schema)
+            })
+            .collect();
+        if !defs.is_empty() {
+            root["$defs"] = Value::Object(defs);
+        }
+        Ok(root)
+    }
+}
+
+struct JsonSchemaWriter;
+
+impl JsonSchemaWriter {
+    fn record(&self, record: &RecordSchema) -> Value {
+        let mut schema = json!({"type": "object", "title": record.name()});
+        if let Some(doc) = record.doc() {
+            schema["description"] = json!(doc);
+        }
+
+        let mut properties = Map::new();
+        let mut required = vec![];
+        for field in record.fields() {
+            let mut property = self.schema(field.schema());
+            if let Some(doc) = field.doc() {
+                property["description"] = json!(doc);
+            }
+            if let Some(default) = field.default() {
+                property["default"] = default.clone();
+            }
+            let nullable = match field.schema() {
+                Schema::Null => true,
+                Schema::Union(types) => types.contains(&Schema::Null),
+                _ => false,
+            };
+            if field.default().is_none() && !nullable {
+                required.push(json!(field.name()));
+            }
+            properties.insert(field.name().to_string(), property);
+        }
+        schema["properties"] = Value::Object(properties);
+        if !required.is_empty() {
+            schema["required"] = Value::Array(required);
+        }
+        schema["additionalProperties"] = json!(false);
+        schema
+    }
+
+    fn enumeration(&self, enum_: &EnumSchema) -> Value {
+        let mut schema = json!({"type": "string", "title": enum_.name()});
+        if let Some(doc) = enum_.doc() {
+            schema["description"] = json!(doc);
+        }
+        schema["enum"] = json!(enum_.symbols());
+        schema
+    }
+
+    fn schema(&self, schema: &Schema) -> Value {
+        match schema {
+            Schema::Null => json!({"type": "null"}),
+            Schema::Boolean => json!({"type": "boolean"}),
+            Schema::Int => json!({"type": "integer", "format": "int32"}),
+            Schema::Long => json!({"type": "integer", "format": "int64"}),
+            Schema::Float | Schema::Double => json!({"type": "number"}),
+            Schema::String => json!({"type": "string"}),
+            Schema::Array(inner) => json!({"type": "array", "items": self.schema(inner)}),
+            Schema::Union(types) => {
+                json!({"oneOf": types.iter().map(|t| self.schema(t)).collect::<Vec<Value>>()})
+            }
+            Schema::Record(name) | Schema::Enum(name) => {
+                json!({"$ref": format!("#/$defs/{}", name)})
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::JsonSchemaSerializer;
+    use crate::compiler::IdlCompiler;
+
+    #[test]
+    fn test_json_schema() {
+        let src = "namespace org.example;
+schema Person;
+
+enum Meal { DINNER, LUNCH }
+/** A person */
+record Person {
+  string name;
+  int age = 0;
+  union { null, Meal } meal = null;
+  array<Person> friends;
+  double? height;
+}";
+        let schema = IdlCompiler::new().compile(src).unwrap();
+        let expected = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$ref": "#/$defs/org.example.Person",
+            "$defs": {
+                "org.example.Meal": {
+                    "type": "string",
+                    "title": "Meal",
+                    "enum": ["DINNER", "LUNCH"]
+                },
+                "org.example.Person": {
+                    "type": "object",
+                    "title": "Person",
+                    "description": "A person",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "age": {"type": "integer", "format": "int32", "default": 0},
+                        "meal": {
+                            "oneOf": [{"type": "null"}, {"$ref": "#/$defs/org.example.Meal"}],
+                            "default": null
+                        },
+                        "friends": {
+                            "type": "array",
+                            "items": {"$ref": "#/$defs/org.example.Person"}
+                        },
+                        "height": {"oneOf": [{"type": "number"}, {"type": "null"}]}
+                    },
+                    "required": ["name", "friends"],
+                    "additionalProperties": false
+                }
+            }
+        });
+        assert_eq!(
+            JsonSchemaSerializer::new(schema).serialize().unwrap(),
+            expected
+        );
+    }
+}
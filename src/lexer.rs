@@ -1,12 +1,26 @@
-use crate::ast::{HasDefault, Literal, RawField};
-use crate::error::AvroError;
+use crate::ast::{is_valid_name, HasDefault, Literal, Position, Properties, RawField};
+use crate::error::{AvroError, Location, ParseError};
+use crate::resolver::{FileSystemResolver, ImportResolver};
+use chumsky::error::SimpleReason;
 use chumsky::prelude::*;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Imported files which have already been parsed, by their canonical path
+pub type ImportCache = Rc<RefCell<HashMap<PathBuf, RawField>>>;
 
 /// Parser for the Avro IDL language
 pub struct AvroIdlLexer {
     path: PathBuf,
+    resolver: Rc<dyn ImportResolver>,
+    import_cache: ImportCache,
+    imported_files: RefCell<Vec<PathBuf>>,
+    imported_types: RefCell<Vec<(String, Vec<String>)>>,
 }
 
 impl AvroIdlLexer {
@@ -14,71 +28,442 @@ impl AvroIdlLexer {
     pub fn new(path: String) -> Self {
         let mut buf = PathBuf::new();
         buf.push(path);
-        AvroIdlLexer { path: buf }
+        AvroIdlLexer {
+            path: buf,
+            resolver: Rc::new(FileSystemResolver::default()),
+            import_cache: ImportCache::default(),
+            imported_files: RefCell::new(vec![]),
+            imported_types: RefCell::new(vec![]),
+        }
+    }
+
+    /// Share parsed imports with other parsers, so files imported by several files
+    /// are only parsed once
+    pub fn with_import_cache(mut self, import_cache: ImportCache) -> Self {
+        self.import_cache = import_cache;
+        self
+    }
+
+    /// Set the directories searched for imports which cannot be found relative to the
+    /// importing file
+    pub fn with_import_paths(mut self, import_paths: Vec<String>) -> Self {
+        let import_paths = import_paths.into_iter().map(PathBuf::from).collect();
+        self.resolver = Rc::new(FileSystemResolver::new(import_paths));
+        self
+    }
+
+    /// Resolve imports through the given resolver instead of the file system
+    pub fn with_resolver(mut self, resolver: impl ImportResolver + 'static) -> Self {
+        self.resolver = Rc::new(resolver);
+        self
     }
 
     /// Parse the content of the path given when instantiating the IDLParser
     pub fn parse(&self) -> Result<RawField, AvroError> {
-        let src = read_to_string(self.path.to_str().unwrap()).expect("Failed to Avro IDL file!");
+        self.imported_files.borrow_mut().clear();
+        self.imported_types.borrow_mut().clear();
+        let src = read_to_string(&self.path)
+            .map_err(|err| AvroError::FailedReading(format!("{}: {}", self.path.display(), err)))?;
+        self.parse_idl(src, self.path.clone())
+    }
+
+    /// Parse Avro IDL which has been read elsewhere e.g. from stdin. The path given when
+    /// instantiating the IDLParser is only used for error messages and for resolving
+    /// imports relative to its parent folder.
+    pub fn parse_source(&self, src: String) -> Result<RawField, AvroError> {
+        self.imported_files.borrow_mut().clear();
+        self.imported_types.borrow_mut().clear();
         self.parse_idl(src, self.path.clone())
     }
 
+    /// Paths, canonical if they exist on disk, of the files imported, directly or transitively, by the last parsed
+    /// file, including imports which failed to parse. Imports of a file which was taken from
+    /// a shared import cache aren't listed.
+    pub fn imported_files(&self) -> Vec<PathBuf> {
+        self.imported_files.borrow().clone()
+    }
+
+    /// Full names of the types each import of the last parsed file brought in, directly or
+    /// transitively, by the import path as written in the file
+    pub fn imported_types(&self) -> Vec<(String, Vec<String>)> {
+        self.imported_types.borrow().clone()
+    }
+
+    /// Find an import through the resolver
+    fn find_import(&self, import_path: &str, path: &Path) -> Result<PathBuf, AvroError> {
+        self.resolver.find(import_path, path).ok_or_else(|| {
+            AvroError::FailedImport(format!(
+                "{}: Failed to find import '{}'",
+                path.display(),
+                import_path
+            ))
+        })
+    }
+
+    /// Parse Avro IDL without resolving imports and keeping comments, e.g. for formatting.
+    /// Returns the comments before the protocol or schema, the protocol or schema itself
+    /// and the comments after it.
+    pub fn parse_syntax(&self, src: String) -> Result<Vec<RawField>, AvroError> {
+        let (leading, document, trailing) = self.parse_document(&src, &self.path)?;
+        Ok(leading
+            .into_iter()
+            .chain(std::iter::once(document))
+            .chain(trailing)
+            .collect())
+    }
+
+    /// Run the parser on a string and locate its errors
+    fn parse_document(
+        &self,
+        src: &str,
+        path: &Path,
+    ) -> Result<(Vec<RawField>, RawField, Vec<RawField>), AvroError> {
+        let lexer = self.create_chumsky_parser(src, path);
+        lexer.parse(src).map_err(|errors| {
+            AvroError::FailedParsing(
+                errors
+                    .iter()
+                    .map(|err| Self::parse_error(src, path, err))
+                    .collect(),
+            )
+        })
+    }
+
     /// Parse a string containing Avro IDL
     fn parse_idl(&self, src: String, path: PathBuf) -> Result<RawField, AvroError> {
-        let lexer = self.create_chumsky_parser();
-        let parse_res = lexer.parse(src.clone());
-        if parse_res.is_err() {
-            // TODO: Fix this weird bit
-            lexer.parse(src).unwrap();
+        let (_, top_level_parse, _) = self.parse_document(&src, &path)?;
+
+        match top_level_parse {
+            RawField::Protocol(name, values, namespace, docstring, props) => {
+                let res = self.resolve_imports(values, &namespace, &path)?;
+                Ok(RawField::Protocol(name, res, namespace, docstring, props))
+            }
+            RawField::Schema(main, values, namespace) => {
+                let res = self.resolve_imports(values, &namespace, &path)?;
+                Ok(RawField::Schema(main, res, namespace))
+            }
+            _ => Err(AvroError::InvalidASTDataType(
+                "Didn't extract protocol or schema".to_string(),
+            )),
         }
-        let top_level_parse = parse_res.map_err(|err| {
-            AvroError::FailedParsing(err.into_iter().map(|c| c.to_string()).collect())
-        })?;
-
-        let RawField::Protocol(name, values, namespace, docstring) = top_level_parse else {
-            return Err(AvroError::InvalidASTDataType(
-                "Didn't extract protocol".to_string(),
-            ));
-        };
+    }
+
+    /// Replace imports with the types they declare, drop comments and attach the namespace
+    /// of the file
+    fn resolve_imports(
+        &self,
+        values: Vec<RawField>,
+        namespace: &Option<String>,
+        path: &Path,
+    ) -> Result<Vec<RawField>, AvroError> {
         let mut res = vec![];
         for val in values.into_iter() {
-            // If DataType is Import then load the Protocol and get the values
+            // If DataType is Import then load the Protocol or Schema and get the values
             match val {
                 RawField::Import(import_path) => {
-                    // Remove file name from path
-                    let mut cur_path = path.parent().expect("No parent folder").to_path_buf();
-
-                    cur_path.push(import_path);
-                    let import_src = read_to_string(cur_path.to_str().unwrap())
-                        .expect("Failed to load import reference");
-
-                    let import = self.parse_idl(import_src, cur_path)?;
+                    let cur_path = self.find_import(&import_path, path)?;
+                    let cache_key = cur_path.canonicalize().unwrap_or(cur_path.clone());
+                    if !self.imported_files.borrow().contains(&cache_key) {
+                        self.imported_files.borrow_mut().push(cache_key.clone());
+                    }
+                    let cached = self.import_cache.borrow().get(&cache_key).cloned();
+                    let import = match cached {
+                        Some(import) => import,
+                        None => {
+                            let import_src = self.resolver.read(&cur_path)?;
+                            let import = self.parse_idl(import_src, cur_path)?;
+                            self.import_cache
+                                .borrow_mut()
+                                .insert(cache_key, import.clone());
+                            import
+                        }
+                    };
 
-                    let RawField::Protocol(_, im_values, ..) = import else {
+                    let (RawField::Protocol(_, im_values, ..) | RawField::Schema(_, im_values, ..)) =
+                        import
+                    else {
                         return Err(AvroError::InvalidASTDataType(
-                            "Didn't extract protocol".to_string(),
+                            "Didn't extract protocol or schema".to_string(),
                         ));
                     };
+                    if path == self.path {
+                        let names = im_values.iter().filter_map(RawField::fullname).collect();
+                        self.imported_types.borrow_mut().push((import_path, names));
+                    }
                     for v in im_values.into_iter() {
                         res.push(v);
                     }
                 }
-                // Attach protocol namespace
-                RawField::Record(rname, rfields, _, ds) => {
-                    res.push(RawField::Record(rname, rfields, namespace.clone(), ds))
-                }
-                // Attach protocol namespace
-                RawField::Enum(ename, evalues, edefault, _, ds) => res.push(RawField::Enum(
-                    ename,
-                    evalues,
-                    edefault,
-                    namespace.clone(),
+                // Attach file namespace unless the record sets its own
+                RawField::Record(rname, rfields, rns, ds, rprops) => res.push(RawField::Record(
+                    rname,
+                    rfields
+                        .into_iter()
+                        .filter(|f| !matches!(f, RawField::Comment(..)))
+                        .collect(),
+                    rns.or_else(|| namespace.clone()),
                     ds,
+                    rprops,
                 )),
+                // Attach file namespace unless the enum sets its own
+                RawField::Enum(ename, ebody, edefault, ens, ds, eprops) => {
+                    res.push(RawField::Enum(
+                        ename,
+                        ebody
+                            .into_iter()
+                            .filter(|f| !matches!(f, RawField::Comment(..)))
+                            .collect(),
+                        edefault,
+                        ens.or_else(|| namespace.clone()),
+                        ds,
+                        eprops,
+                    ))
+                }
+                RawField::Comment(..) => {}
                 _ => res.push(val),
             }
         }
-        Ok(RawField::Protocol(name, res, namespace, docstring))
+        Ok(res)
+    }
+
+    /// Locate a parse error in the source
+    fn parse_error(src: &str, path: &Path, err: &Simple<char>) -> ParseError {
+        let message = match err.reason() {
+            SimpleReason::Custom(message) => message.clone(),
+            _ => err.to_string(),
+        };
+        ParseError {
+            location: Location::new(path, src, err.span()),
+            message,
+        }
+    }
+
+    /// Check that enum symbols are valid names, are unique and contain the default
+    fn validate_enum(
+        name: &str,
+        symbols: &[(String, Range<usize>)],
+        default: &Option<(String, Range<usize>)>,
+    ) -> Result<(), Simple<char>> {
+        let mut seen = HashSet::new();
+        for (symbol, span) in symbols.iter() {
+            if !is_valid_name(symbol) {
+                return Err(Simple::custom(
+                    span.clone(),
+                    format!(
+                        "Invalid symbol '{}' in enum '{}', symbols must match [A-Za-z_][A-Za-z0-9_]*",
+                        symbol, name
+                    ),
+                ));
+            }
+            if !seen.insert(symbol.as_str()) {
+                return Err(Simple::custom(
+                    span.clone(),
+                    format!("Duplicate symbol '{}' in enum '{}'", symbol, name),
+                ));
+            }
+        }
+        if let Some((default, span)) = default {
+            if !seen.contains(default.as_str()) {
+                return Err(Simple::custom(
+                    span.clone(),
+                    format!(
+                        "Default '{}' of enum '{}' is not one of its symbols",
+                        default, name
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Split the `@namespace` annotation from the remaining properties
+    fn split_namespace(mut props: Properties) -> (Option<String>, Properties) {
+        let namespace = props
+            .shift_remove("namespace")
+            .and_then(|ns| ns.as_str().map(|ns| ns.to_string()));
+        (namespace, props)
+    }
+
+    /// Annotations before a type belong to the type, but references and unions can't have
+    /// attributes of their own
+    fn check_untyped_annotations(
+        kind: &str,
+        type_props: &Properties,
+        span: Range<usize>,
+    ) -> Result<(), Simple<char>> {
+        if type_props.is_empty() {
+            return Ok(());
+        }
+        Err(Simple::custom(
+            span,
+            format!(
+                "{} can't be annotated, annotations of the field go before its name",
+                kind
+            ),
+        ))
+    }
+
+    /// Normalise a docstring like the Java IDL compiler. If every line after the first
+    /// starts with a `*`, the `*` and a single space after it are removed. Otherwise the
+    /// indentation common to those lines is removed. An escaped end of comment `*\/` becomes
+    /// `*/`.
+    fn normalize_docstring(raw: &str) -> String {
+        let mut lines = raw.lines();
+        let first = lines.next().unwrap_or_default().trim().to_string();
+        let rest = lines.collect::<Vec<&str>>();
+        let mut content = rest.iter().filter(|line| !line.trim().is_empty());
+        let rest = if content.all(|line| line.trim_start().starts_with('*')) {
+            rest.iter()
+                .map(|line| {
+                    let line = line.trim_start();
+                    let line = line.strip_prefix('*').unwrap_or(line);
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<String>>()
+        } else {
+            let indent = rest
+                .iter()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.chars().take_while(|c| c.is_whitespace()).count())
+                .min()
+                .unwrap_or(0);
+            rest.iter()
+                .map(|line| {
+                    line.chars()
+                        .skip(indent)
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<String>>()
+        };
+        std::iter::once(first)
+            .chain(rest)
+            .collect::<Vec<String>>()
+            .join("\n")
+            .trim()
+            .replace("*\\/", "*/")
+    }
+
+    /// Create a parser for docstrings e.g. `/** My docstring */`
+    fn docstring_parser(&self) -> impl Parser<char, String, Error = Simple<char>> + Clone {
+        just('/')
+            .then_ignore(just('*'))
+            .then_ignore(just('*'))
+            .ignore_then(just("*/").not().repeated().collect::<String>())
+            .then_ignore(just('*'))
+            .then_ignore(just('/'))
+            .padded()
+            .map(|raw| Self::normalize_docstring(&raw))
+    }
+
+    /// Create a parser for the docstring and annotations preceding a declaration. The
+    /// docstring may be placed either before or after the annotations.
+    fn declaration_parser(
+        &self,
+    ) -> impl Parser<char, (Option<String>, Properties), Error = Simple<char>> + Clone {
+        self.docstring_parser()
+            .or_not()
+            .then(self.properties_parser())
+            .then(self.docstring_parser().or_not())
+            .map(|((before, props), after)| (after.or(before).filter(|ds| !ds.is_empty()), props))
+    }
+
+    /// Create a parser for JSON values used as annotation arguments
+    fn json_parser(&self) -> impl Parser<char, Value, Error = Simple<char>> + Clone {
+        recursive(|value| {
+            let frac = just('.').chain(text::digits(10));
+
+            let exp = one_of("eE")
+                .chain(one_of("+-").or_not())
+                .chain::<char, _, _>(text::digits(10));
+
+            let number = just('-')
+                .or_not()
+                .chain::<char, _, _>(text::int(10))
+                .chain::<char, _, _>(frac.or_not().flatten())
+                .chain::<char, _, _>(exp.or_not().flatten())
+                .collect::<String>()
+                .try_map(|number, span| {
+                    serde_json::from_str::<Value>(&number)
+                        .map_err(|err| Simple::custom(span, err.to_string()))
+                })
+                .labelled("number");
+
+            let escape = just('\\')
+                .then(any())
+                .map(|(slash, c)| format!("{}{}", slash, c));
+
+            let string = just('"')
+                .ignore_then(
+                    filter(|c: &char| *c != '\\' && *c != '"')
+                        .map(|c| c.to_string())
+                        .or(escape)
+                        .repeated()
+                        .collect::<String>(),
+                )
+                .then_ignore(just('"'))
+                .try_map(|raw, span| {
+                    serde_json::from_str::<String>(&format!("\"{}\"", raw))
+                        .map_err(|err| Simple::custom(span, err.to_string()))
+                })
+                .labelled("string");
+
+            let array = value
+                .clone()
+                .separated_by(just(','))
+                .padded()
+                .delimited_by(just('['), just(']'))
+                .map(Value::Array)
+                .labelled("array");
+
+            let member = string.padded().then_ignore(just(':')).then(value);
+            let object = member
+                .separated_by(just(','))
+                .padded()
+                .delimited_by(just('{'), just('}'))
+                .map(|members| Value::Object(members.into_iter().collect()))
+                .labelled("object");
+
+            choice((
+                text::keyword("null").to(Value::Null),
+                text::keyword("true").to(Value::Bool(true)),
+                text::keyword("false").to(Value::Bool(false)),
+                number,
+                string.map(Value::String),
+                array,
+                object,
+            ))
+            .padded()
+        })
+    }
+
+    /// Create a parser for annotations e.g. `@namespace("org.example")` or `@java-class("Foo")`
+    fn annotation_parser(
+        &self,
+    ) -> impl Parser<char, (String, Value), Error = Simple<char>> + Clone {
+        let name = filter(|c: &char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            .repeated()
+            .at_least(1)
+            .collect::<String>();
+
+        just('@')
+            .ignore_then(name)
+            .then_ignore(just('(').padded())
+            .then(self.json_parser())
+            .then_ignore(just(')'))
+            .labelled("annotation")
+    }
+
+    /// Create a parser for zero or more annotations collected as properties
+    fn properties_parser(&self) -> impl Parser<char, Properties, Error = Simple<char>> + Clone {
+        self.annotation_parser()
+            .padded()
+            .repeated()
+            .map(|annotations| annotations.into_iter().collect::<Properties>())
     }
 
     /// Create a parser which can handle a type and the same type as nullable
@@ -90,17 +475,21 @@ impl AvroIdlLexer {
         &self,
         keyword: String,
         default_value_parser: impl Parser<char, HasDefault<String>, Error = Simple<char>> + Clone,
-        primitive_field_factory: fn(String, HasDefault<String>, Option<String>) -> RawField,
-        union_field_factory: fn(String, HasDefault<String>, Option<String>) -> RawField,
+        primitive_field_factory: fn(
+            String,
+            HasDefault<String>,
+            Option<String>,
+            Properties,
+            Properties,
+        ) -> RawField,
+        union_field_factory: fn(
+            String,
+            HasDefault<String>,
+            Option<String>,
+            Properties,
+            Properties,
+        ) -> RawField,
     ) -> impl Parser<char, RawField, Error = Simple<char>> {
-        let docstring_parser = just('/')
-            .then_ignore(just('*'))
-            .then_ignore(just('*'))
-            .then(just("*/").not().repeated().collect::<String>())
-            .then_ignore(just('*'))
-            .then_ignore(just('/'))
-            .padded();
-
         let default_parser = just('=')
             .padded()
             .ignore_then(default_value_parser)
@@ -109,64 +498,50 @@ impl AvroIdlLexer {
 
         let no_default_parser = just(';').ignored();
 
-        let keyword_parser = text::keyword(keyword.clone())
-            .padded()
-            .ignore_then(text::ident().padded());
+        // Annotations before the type belong to the type and the ones before the name to
+        // the field
+        let keyword_parser = self
+            .declaration_parser()
+            .then_ignore(text::keyword(keyword.clone()).padded())
+            .then(self.properties_parser())
+            .then(text::ident().padded())
+            .map(|(((docstring, type_props), props), name)| (docstring, name, props, type_props));
 
         // Parser for nullable shorthand : string?, int?, float? ...
-        let keyword_nullable_parser = text::keyword(keyword)
-            .then_ignore(just('?'))
-            .padded()
-            .ignore_then(text::ident().padded());
+        let keyword_nullable_parser = self
+            .declaration_parser()
+            .then_ignore(text::keyword(keyword).then_ignore(just('?')).padded())
+            .then(self.properties_parser())
+            .then(text::ident().padded())
+            .map(|(((docstring, type_props), props), name)| (docstring, name, props, type_props));
 
         // Regular primitive with default
-        let primitive_with_default = docstring_parser
-            .clone()
-            .or_not()
-            .then(keyword_parser.clone())
-            .then(default_parser.clone())
-            //then(text::ident())
-            .map(move |((docstring, name), value)| {
-                primitive_field_factory(name, value, docstring.map(|(_, x)| x.trim().to_string()))
-            });
+        let primitive_with_default = keyword_parser.clone().then(default_parser.clone()).map(
+            move |((docstring, name, props, type_props), value)| {
+                primitive_field_factory(name, value, docstring, props, type_props)
+            },
+        );
 
         // Regular primitive no default
-        let primitive_no_default = docstring_parser
-            .clone()
-            .or_not()
-            .then(keyword_parser)
-            .then(no_default_parser)
-            .map(move |((docstring, name), _)| {
-                primitive_field_factory(
-                    name,
-                    HasDefault::None,
-                    docstring.map(|(_, x)| x.trim().to_string()),
-                )
-            });
+        let primitive_no_default = keyword_parser.then(no_default_parser).map(
+            move |((docstring, name, props, type_props), _)| {
+                primitive_field_factory(name, HasDefault::None, docstring, props, type_props)
+            },
+        );
 
         // Nullable primitive with default
-        let primitive_nullable_default = docstring_parser
-            .clone()
-            .or_not()
-            .then(keyword_nullable_parser.clone())
-            .then(default_parser)
-            .map(move |((docstring, name), value)| {
-                union_field_factory(name, value, docstring.map(|(_, x)| x.trim().to_string()))
-            });
+        let primitive_nullable_default = keyword_nullable_parser.clone().then(default_parser).map(
+            move |((docstring, name, props, type_props), value)| {
+                union_field_factory(name, value, docstring, props, type_props)
+            },
+        );
 
         // Nullable primitive no default
-        let primitive_nullable_no_default = docstring_parser
-            .clone()
-            .or_not()
-            .then(keyword_nullable_parser)
-            .then(no_default_parser)
-            .map(move |((docstring, name), _)| {
-                union_field_factory(
-                    name,
-                    HasDefault::None,
-                    docstring.map(|(_, x)| x.trim().to_string()),
-                )
-            });
+        let primitive_nullable_no_default = keyword_nullable_parser.then(no_default_parser).map(
+            move |((docstring, name, props, type_props), _)| {
+                union_field_factory(name, HasDefault::None, docstring, props, type_props)
+            },
+        );
 
         choice((
             primitive_nullable_default,
@@ -183,17 +558,21 @@ impl AvroIdlLexer {
                 .map(|v| HasDefault::Default(Some(v)))
                 .or(text::keyword("null").to(HasDefault::Default(None)))
                 .or_else(|_| Ok(HasDefault::None)),
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| v.parse::<i32>().unwrap());
-                RawField::Int(Some(name), default, docstring)
+                RawField::Int(Some(name), default, docstring, props, type_props)
             },
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| Literal::Int(v.parse::<i32>().unwrap()));
                 RawField::Union(
                     Some(name),
-                    vec![RawField::Int(None, HasDefault::None, None), RawField::Null],
+                    vec![
+                        RawField::Int(None, HasDefault::None, None, Properties::new(), type_props),
+                        RawField::Null,
+                    ],
                     default,
                     docstring,
+                    props,
                 )
             },
         )
@@ -206,17 +585,21 @@ impl AvroIdlLexer {
                 .map(|v| HasDefault::Default(Some(v)))
                 .or(text::keyword("null").to(HasDefault::Default(None)))
                 .or_else(|_| Ok(HasDefault::None)),
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| v.parse::<i64>().unwrap());
-                RawField::Long(Some(name), default, docstring)
+                RawField::Long(Some(name), default, docstring, props, type_props)
             },
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| Literal::Long(v.parse::<i64>().unwrap()));
                 RawField::Union(
                     Some(name),
-                    vec![RawField::Long(None, HasDefault::None, None), RawField::Null],
+                    vec![
+                        RawField::Long(None, HasDefault::None, None, Properties::new(), type_props),
+                        RawField::Null,
+                    ],
                     default,
                     docstring,
+                    props,
                 )
             },
         )
@@ -240,20 +623,27 @@ impl AvroIdlLexer {
                 .map(|v| HasDefault::Default(Some(v)))
                 .or(text::keyword("null").to(HasDefault::Default(None)))
                 .or_else(|_| Ok(HasDefault::None)),
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| v.parse::<f32>().unwrap());
-                RawField::Float(Some(name), default, docstring)
+                RawField::Float(Some(name), default, docstring, props, type_props)
             },
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| Literal::Float(v.parse::<f32>().unwrap()));
                 RawField::Union(
                     Some(name),
                     vec![
-                        RawField::Float(None, HasDefault::None, None),
+                        RawField::Float(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            type_props,
+                        ),
                         RawField::Null,
                     ],
                     default,
                     docstring,
+                    props,
                 )
             },
         )
@@ -277,20 +667,27 @@ impl AvroIdlLexer {
                 .map(|v| HasDefault::Default(Some(v)))
                 .or(text::keyword("null").to(HasDefault::Default(None)))
                 .or_else(|_| Ok(HasDefault::None)),
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| v.parse::<f64>().unwrap());
-                RawField::Double(Some(name), default, docstring)
+                RawField::Double(Some(name), default, docstring, props, type_props)
             },
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| Literal::Double(v.parse::<f64>().unwrap()));
                 RawField::Union(
                     Some(name),
                     vec![
-                        RawField::Double(None, HasDefault::None, None),
+                        RawField::Double(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            type_props,
+                        ),
                         RawField::Null,
                     ],
                     default,
                     docstring,
+                    props,
                 )
             },
         )
@@ -305,20 +702,27 @@ impl AvroIdlLexer {
                 .map(|v| HasDefault::Default(Some(v)))
                 .or(text::keyword("null").to(HasDefault::Default(None)))
                 .or_else(|_| Ok(HasDefault::None)),
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| v.parse::<bool>().unwrap());
-                RawField::Boolean(Some(name), default, docstring)
+                RawField::Boolean(Some(name), default, docstring, props, type_props)
             },
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
                 let default = value.map(|v| Literal::Boolean(v.parse::<bool>().unwrap()));
                 RawField::Union(
                     Some(name),
                     vec![
-                        RawField::Boolean(None, HasDefault::None, None),
+                        RawField::Boolean(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            type_props,
+                        ),
                         RawField::Null,
                     ],
                     default,
                     docstring,
+                    props,
                 )
             },
         )
@@ -335,17 +739,26 @@ impl AvroIdlLexer {
                 .map(|v| HasDefault::Default(Some(v)))
                 .or(text::keyword("null").to(HasDefault::Default(None)))
                 .or_else(|_| Ok(HasDefault::None)),
-            |name, value, docstring| RawField::String(Some(name), value, docstring),
-            |name, value, docstring| {
+            |name, value, docstring, props, type_props| {
+                RawField::String(Some(name), value, docstring, props, type_props)
+            },
+            |name, value, docstring, props, type_props| {
                 let default = value.map(Literal::String);
                 RawField::Union(
                     Some(name),
                     vec![
-                        RawField::String(None, HasDefault::None, None),
+                        RawField::String(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            type_props,
+                        ),
                         RawField::Null,
                     ],
                     default,
                     docstring,
+                    props,
                 )
             },
         )
@@ -377,34 +790,81 @@ impl AvroIdlLexer {
             .labelled("number")
     }
 
+    /// Create a parser for regular comments e.g. `// comment` or `/* comment */`. Comments
+    /// which follow other content on the same line of the source are marked as trailing.
+    fn comment_parser(
+        &self,
+        src: &str,
+    ) -> impl Parser<char, RawField, Error = Simple<char>> + Clone {
+        let chars = Rc::new(src.chars().collect::<Vec<char>>());
+
+        let line_comment = just("//")
+            .ignore_then(none_of("\r\n").repeated().collect::<String>())
+            .map(|text| format!("//{}", text));
+        // A block comment must not start with `/**`, which starts a docstring
+        let block_comment = just("/*")
+            .ignore_then(none_of("*"))
+            .then(just("*/").not().repeated().collect::<String>())
+            .then_ignore(just("*/"))
+            .map(|(first, text)| format!("/*{}{}*/", first, text));
+
+        line_comment
+            .or(block_comment)
+            .map_with_span(move |text, span: Range<usize>| {
+                let line_start = chars[..span.start]
+                    .iter()
+                    .rposition(|c| *c == '\n')
+                    .map_or(0, |i| i + 1);
+                let trailing = chars[line_start..span.start]
+                    .iter()
+                    .any(|c| !c.is_whitespace());
+                RawField::Comment(text.trim_end().to_string(), trailing)
+            })
+            .padded()
+            .labelled("comment")
+    }
+
     /// Create the actual text parser
-    fn create_chumsky_parser(&self) -> impl Parser<char, RawField, Error = Simple<char>> {
+    fn create_chumsky_parser(
+        &self,
+        src: &str,
+        path: &Path,
+    ) -> impl Parser<char, (Vec<RawField>, RawField, Vec<RawField>), Error = Simple<char>> {
+        let source = Rc::new((path.to_path_buf(), src.to_string()));
+
+        // Parser for regular comments, which are kept for formatting
+        let comment_parser = self.comment_parser(src);
+
         // Parser for docstrings
-        let docstring_parser = just('/')
-            .then_ignore(just('*'))
-            .then_ignore(just('*'))
-            .then(just("*/").not().repeated().collect::<String>())
-            .then_ignore(just('*'))
-            .then_ignore(just('/'))
-            .padded();
+        let docstring_parser = self.docstring_parser();
+
+        // Parser for the docstring and annotations preceding a declaration
+        let declaration_parser = self.declaration_parser();
+
+        // Annotations e.g. @namespace(), @logicalType()
+        let properties_parser = self.properties_parser();
 
         // Parser to identify the protocol and get the protocol name
-        let protocol_start = text::keyword("protocol")
+        let protocol_start = text::ident()
+            .or_not()
+            .try_map(|keyword: Option<String>, span| match keyword.as_deref() {
+                Some("protocol") => Ok(()),
+                Some(found) => Err(Simple::custom(
+                    span,
+                    format!(
+                        "Expected a protocol or schema declaration, found '{}'",
+                        found
+                    ),
+                )),
+                None => Err(Simple::custom(
+                    span,
+                    "Expected a protocol or schema declaration",
+                )),
+            })
             .padded()
-            .ignored()
             .then(text::ident())
             .then_ignore(just('{').padded());
 
-        // Annotations e.g. @namespace(), @logicalType()
-        let namespace = just('@')
-            .ignored()
-            .then_ignore(text::keyword("namespace"))
-            .then_ignore(just('('))
-            .then_ignore(just('"'))
-            .then(none_of('"').repeated().collect::<String>())
-            .then_ignore(just('"'))
-            .then_ignore(just(')'));
-
         let path = none_of('"').repeated();
 
         // Check for imports
@@ -418,131 +878,223 @@ impl AvroIdlLexer {
             .then_ignore(just(';').padded())
             .map(|(_, path)| RawField::Import(path.into_iter().collect()));
 
+        // Name of a referenced record or enum, which may be a full name e.g. `org.example.Meal`,
+        // with its position to report references which can't be resolved
+        let reference_name = text::ident()
+            .separated_by(just('.'))
+            .at_least(1)
+            .map_with_span(move |parts, span| {
+                let (path, src) = &*source;
+                let position = Position(Some(Location::new(path, src, span)));
+                (parts.join("."), position)
+            });
+
         // Record/Enum reference parser: Handle references to other records
-        let ref_parser = docstring_parser
+        let ref_parser = declaration_parser
             .clone()
-            .or_not()
-            .then(text::ident())
+            .then(reference_name.clone())
             .padded()
+            .then(properties_parser.clone())
             .then(text::ident().padded())
             .then_ignore(just(';'))
-            .map(|((docstring, type_), name)| {
-                RawField::Unresolved(
-                    Some(name),
-                    type_,
-                    docstring.map(|(_, x)| x.trim().to_string()),
-                )
-            });
+            .try_map(
+                |((((docstring, type_props), (type_, position)), props), name), span| {
+                    Self::check_untyped_annotations("Type references", &type_props, span)?;
+                    Ok(RawField::Unresolved(
+                        Some(name),
+                        type_,
+                        docstring,
+                        props,
+                        position,
+                    ))
+                },
+            );
 
         // Record/Enum optional reference parser: Handle references to other records
-        let ref_parser_optional = docstring_parser
+        let ref_parser_optional = declaration_parser
             .clone()
-            .or_not()
-            .then(text::ident())
+            .then(reference_name.clone())
             .then_ignore(just('?'))
             .padded()
+            .then(properties_parser.clone())
             .then(text::ident().padded())
             .then_ignore(just(';'))
-            .map(|((docstring, type_), name)| {
-                RawField::Union(
-                    Some(name.clone()),
-                    vec![RawField::Unresolved(None, type_, None), RawField::Null],
-                    HasDefault::None,
-                    docstring.map(|(_, x)| x.trim().to_string()),
-                )
-            });
+            .try_map(
+                |((((docstring, type_props), (type_, position)), props), name), span| {
+                    Self::check_untyped_annotations("Type references", &type_props, span)?;
+                    Ok(RawField::Union(
+                        Some(name.clone()),
+                        vec![
+                            RawField::Unresolved(None, type_, None, Properties::new(), position),
+                            RawField::Null,
+                        ],
+                        HasDefault::None,
+                        docstring,
+                        props,
+                    ))
+                },
+            );
+
+        // Enum symbol with its span
+        let enum_symbol =
+            filter(|c: &char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '$'))
+                .repeated()
+                .at_least(1)
+                .collect::<String>()
+                .map_with_span(|symbol, span| (symbol, span))
+                .padded();
+
+        // Enum symbol with the comments and the docstring before it, which are only kept for
+        // formatting
+        let enum_item = comment_parser
+            .clone()
+            .repeated()
+            .then(docstring_parser.clone().or_not())
+            .then(enum_symbol);
 
-        // Enum (Working parser but with trailing comma)
-        let enum_parser_plain = docstring_parser
+        // Enum with optional trailing comma and optional default
+        let enum_parser = declaration_parser
             .clone()
-            .or_not()
             .then_ignore(text::keyword("enum"))
             .padded()
             .then(text::ident()) // name
             .then_ignore(just('{').padded())
-            .then(text::ident().padded().then_ignore(just(',')).repeated())
-            .then(text::ident().padded()); // Inner enum values;
-
-        let enum_parser = enum_parser_plain
-            .clone()
+            .then(
+                enum_item
+                    .separated_by(just(','))
+                    .allow_trailing()
+                    .then(comment_parser.clone().repeated()),
+            ) // Inner enum values
             .then_ignore(just('}').padded())
-            .then_ignore(just('=').padded())
-            .then(text::ident().padded()) // Default value
-            .then_ignore(just(';').padded())
-            .map(
-                |((((docstring, name), mut values), last_value), default_value)| {
-                    values.push(last_value);
-                    RawField::Enum(
-                        Some(name),
-                        values,
-                        HasDefault::Default(Some(default_value)),
-                        None,
-                        docstring.map(|(_, x)| x.trim().to_string()),
-                    )
-                },
-            )
-            .or(enum_parser_plain.then_ignore(just('}').padded()).map(
-                |(((docstring, name), mut values), last_value)| {
-                    values.push(last_value);
-                    RawField::Enum(
+            .then(
+                just('=')
+                    .padded()
+                    .ignore_then(enum_symbol)
+                    .then_ignore(just(';').padded())
+                    .or_not(),
+            ) // Default value
+            .try_map(
+                |((((docstring, props), name), (items, comments)), default), _| {
+                    let symbols = items
+                        .iter()
+                        .map(|(_, symbol)| symbol.clone())
+                        .collect::<Vec<(String, Range<usize>)>>();
+                    Self::validate_enum(&name, &symbols, &default)?;
+                    let (namespace, props) = Self::split_namespace(props);
+                    let mut body = vec![];
+                    for ((leading, symbol_docstring), (symbol, _)) in items.into_iter() {
+                        body.extend(leading);
+                        body.push(RawField::Symbol(symbol, symbol_docstring));
+                    }
+                    body.extend(comments);
+                    Ok(RawField::Enum(
                         Some(name),
-                        values,
-                        HasDefault::None,
-                        None,
-                        docstring.map(|(_, x)| x.trim().to_string()),
-                    )
+                        body,
+                        match default {
+                            Some((default, _)) => HasDefault::Default(Some(default)),
+                            None => HasDefault::None,
+                        },
+                        namespace,
+                        docstring,
+                        props,
+                    ))
                 },
-            ));
+            );
 
         // Unnamed type parser
         let unnamed_type_parser = text::keyword("int")
             .padded()
-            .to(RawField::Int(None, HasDefault::None, None))
-            .or(text::keyword("long")
-                .padded()
-                .to(RawField::Long(None, HasDefault::None, None)))
-            .or(text::keyword("float")
-                .padded()
-                .to(RawField::Float(None, HasDefault::None, None)))
-            .or(text::keyword("double")
-                .padded()
-                .to(RawField::Double(None, HasDefault::None, None)))
+            .to(RawField::Int(
+                None,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            ))
+            .or(text::keyword("long").padded().to(RawField::Long(
+                None,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )))
+            .or(text::keyword("float").padded().to(RawField::Float(
+                None,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )))
+            .or(text::keyword("double").padded().to(RawField::Double(
+                None,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            )))
             .or(text::keyword("boolean").padded().to(RawField::Boolean(
                 None,
                 HasDefault::None,
                 None,
+                Properties::new(),
+                Properties::new(),
+            )))
+            .or(text::keyword("string").padded().to(RawField::String(
+                None,
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
             )))
-            .or(text::keyword("string")
-                .padded()
-                .to(RawField::String(None, HasDefault::None, None)))
             .or(text::keyword("null").padded().to(RawField::Null))
-            .or(text::ident()
-                .padded()
-                .map(|value| RawField::Unresolved(None, value, None)));
+            .or(reference_name.padded().map(|(value, position)| {
+                RawField::Unresolved(None, value, None, Properties::new(), position)
+            }));
+
+        // Unnamed array parser e.g. array<int>
+        let unnamed_array_parser = text::keyword("array")
+            .padded()
+            .ignore_then(
+                unnamed_type_parser
+                    .clone()
+                    .delimited_by(just('<').padded(), just('>').padded()),
+            )
+            .map(|field| {
+                RawField::Array(
+                    None,
+                    Box::new(field),
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )
+            });
 
         // Multiple comma separated unnamed type parameters
         let mult_unnamed_type_parser = unnamed_type_parser.clone().separated_by(just(',').padded());
 
         // Array parser
-        let array_parser_plain = docstring_parser
+        let array_parser_plain = declaration_parser
             .clone()
-            .or_not()
-            .then_ignore(text::keyword("array"))
-            .then_ignore(just('<'))
+            .then_ignore(text::keyword("array").padded())
+            .then_ignore(just('<').padded())
             .then(unnamed_type_parser.clone())
-            .then_ignore(just('>'));
+            .then_ignore(just('>').padded());
 
         let array_parser = array_parser_plain
             .clone()
+            .then(properties_parser.clone())
             .then(text::ident().padded())
             .then_ignore(just(';'))
             .map(|values| {
-                let ((docstring, field), name) = values;
+                let ((((docstring, type_props), field), props), name) = values;
                 RawField::Array(
                     Some(name),
                     Box::new(field),
                     HasDefault::None,
-                    docstring.map(|(_, x)| x.trim().to_string()),
+                    docstring,
+                    props,
+                    type_props,
                 )
             });
 
@@ -562,53 +1114,60 @@ impl AvroIdlLexer {
             .padded();
 
         // Union parser
-        let union_parser_plain = docstring_parser
+        let union_parser_plain = declaration_parser
             .clone()
-            .or_not()
             .then_ignore(text::keyword("union").padded())
             .then_ignore(just('{').padded())
             .then(mult_unnamed_type_parser)
             .then_ignore(just('}').padded())
+            .then(properties_parser.clone())
             .then(text::ident().padded());
 
         let union_parser = union_parser_plain
             .clone()
             .then_ignore(just(';').padded())
-            .map(|((docstring, values), name)| {
-                RawField::Union(
+            .try_map(|((((docstring, type_props), values), props), name), span| {
+                Self::check_untyped_annotations("Unions", &type_props, span)?;
+                Ok(RawField::Union(
                     Some(name),
                     values,
                     HasDefault::None,
-                    docstring.map(|(_, x)| x.trim().to_string()),
-                )
+                    docstring,
+                    props,
+                ))
             })
             .or(union_parser_plain
                 .then_ignore(just('=').padded())
                 .then(union_literal_parser)
                 .then_ignore(just(';').padded())
-                .map(|(((docstring, values), name), default_value)| {
-                    RawField::Union(
-                        Some(name),
-                        values,
-                        if let Literal::Null = default_value {
-                            HasDefault::Default(None)
-                        } else {
-                            HasDefault::Default(Some(default_value))
-                        },
-                        docstring.map(|(_, x)| x.trim().to_string()),
-                    )
-                }));
+                .try_map(
+                    |(((((docstring, type_props), values), props), name), default_value), span| {
+                        Self::check_untyped_annotations("Unions", &type_props, span)?;
+                        Ok(RawField::Union(
+                            Some(name),
+                            values,
+                            if let Literal::Null = default_value {
+                                HasDefault::Default(None)
+                            } else {
+                                HasDefault::Default(Some(default_value))
+                            },
+                            docstring,
+                            props,
+                        ))
+                    },
+                ));
 
         // Record parser
-        let record_parser = docstring_parser
+        let record_parser = declaration_parser
             .clone()
-            .or_not()
             .then_ignore(text::keyword("record"))
             .padded()
             .then(text::ident()) // Record name
             .then_ignore(just('{').padded())
             .then(
-                self.create_primitive_parser()
+                comment_parser
+                    .clone()
+                    .or(self.create_primitive_parser())
                     .or(array_parser)
                     .or(ref_parser)
                     .or(ref_parser_optional)
@@ -616,37 +1175,120 @@ impl AvroIdlLexer {
                     .repeated(),
             ) // Parse content
             .then_ignore(just('}').padded())
-            .map(|((docstring, name), primitives)| {
-                RawField::Record(
-                    Some(name),
-                    primitives,
-                    None,
-                    docstring.map(|(_, x)| x.trim().to_string()),
-                )
+            .map(|(((docstring, props), name), primitives)| {
+                let (namespace, props) = Self::split_namespace(props);
+                RawField::Record(Some(name), primitives, namespace, docstring, props)
             });
 
-        // Put the whole thing together and notice check for ending of the file
-        namespace
-            .or_not()
+        // Imports and named types which can be declared in both protocols and schema files
+        let named_declaration =
+            choice((comment_parser.clone(), import, record_parser, enum_parser)).boxed();
+
+        // Protocol with the types it declares
+        let protocol_parser = declaration_parser
+            .clone()
             .then(protocol_start)
-            .then(choice((import, record_parser, enum_parser)).repeated())
+            .then(named_declaration.clone().repeated())
             .then_ignore(just('}').padded())
+            .map(|(((docstring, props), (_, name)), fields)| {
+                let (namespace, props) = Self::split_namespace(props);
+                RawField::Protocol(Some(name), fields, namespace, docstring, props)
+            });
+
+        // Namespace declaration of a schema file e.g. `namespace org.example;`
+        let namespace_declaration = text::keyword("namespace")
+            .padded()
+            .ignore_then(text::ident().separated_by(just('.')).at_least(1))
+            .then_ignore(just(';').padded())
+            .map(|parts| parts.join("."));
+
+        // Main schema declaration of a schema file e.g. `schema Person;`
+        let main_schema_declaration = text::keyword("schema")
+            .padded()
+            .ignore_then(unnamed_array_parser.or(unnamed_type_parser))
+            .then_ignore(just(';').padded());
+
+        // Schema file with an optional main schema and the types it declares
+        let schema_parser = namespace_declaration
+            .or_not()
+            .then(main_schema_declaration.or_not())
+            .then(named_declaration.repeated())
+            .padded()
+            .map(|((namespace, main), fields)| {
+                RawField::Schema(main.map(Box::new), fields, namespace)
+            });
+
+        // A schema file starts with its namespace, main schema or a declaration, anything
+        // else is parsed as a protocol so that its errors are reported
+        let schema_start = choice((
+            text::keyword("namespace").padded(),
+            text::keyword("schema").padded(),
+            text::keyword("import").padded(),
+        ))
+        .or(declaration_parser
+            .then_ignore(text::keyword("record").or(text::keyword("enum")))
+            .ignored());
+
+        // Put the whole thing together with the comments around it and notice check for
+        // ending of the file
+        let comments = comment_parser.repeated();
+        comments
+            .clone()
+            .then(protocol_parser.or(schema_start.rewind().ignore_then(schema_parser)))
+            .then(comments)
             .then_ignore(end())
-            .map(|((namespace, (_, name)), fields)| {
-                RawField::Protocol(Some(name), fields, namespace.map(|(_, ns)| ns), None)
-                // TODO: Docstring
-            })
+            .map(|((leading, document), trailing)| (leading, document, trailing))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+
+    use serde_json::json;
 
-    use crate::ast::{HasDefault, Literal, RawField};
+    use crate::ast::{HasDefault, Literal, Position, Properties, RawField};
+    use crate::error::AvroError;
 
     use super::AvroIdlLexer;
 
+    fn symbol(name: &str) -> RawField {
+        RawField::Symbol(name.to_string(), None)
+    }
+
+    /// Clear the positions of references, which tests don't spell out
+    fn without_positions(field: RawField) -> RawField {
+        let clear = |fields: Vec<RawField>| fields.into_iter().map(without_positions).collect();
+        match field {
+            RawField::Unresolved(name, type_, docstring, props, _) => {
+                RawField::Unresolved(name, type_, docstring, props, Position::default())
+            }
+            RawField::Protocol(name, fields, namespace, docstring, props) => {
+                RawField::Protocol(name, clear(fields), namespace, docstring, props)
+            }
+            RawField::Schema(main, fields, namespace) => RawField::Schema(
+                main.map(|main| Box::new(without_positions(*main))),
+                clear(fields),
+                namespace,
+            ),
+            RawField::Record(name, fields, namespace, docstring, props) => {
+                RawField::Record(name, clear(fields), namespace, docstring, props)
+            }
+            RawField::Union(name, fields, default, docstring, props) => {
+                RawField::Union(name, clear(fields), default, docstring, props)
+            }
+            RawField::Array(name, inner, default, docstring, props, type_props) => RawField::Array(
+                name,
+                Box::new(without_positions(*inner)),
+                default,
+                docstring,
+                props,
+                type_props,
+            ),
+            field => field,
+        }
+    }
+
     #[test]
     fn test_single_protocol() {
         let src = "protocol Event {
@@ -663,29 +1305,44 @@ mod tests {
 
 }";
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![
                 RawField::Enum(
                     Some("Meal".to_string()),
-                    vec!["Dinner".to_string(), "Lunch".to_string()],
+                    vec![symbol("Dinner"), symbol("Lunch")],
                     HasDefault::Default(Some("Dinner".to_string())),
                     None,
                     None,
+                    Properties::new(),
                 ),
                 RawField::Record(
                     Some("Lol".to_string()),
                     vec![
-                        RawField::Int(Some("a".to_string()), HasDefault::None, None),
-                        RawField::Unresolved(Some("tob".to_string()), "Tob".to_string(), None),
+                        RawField::Int(
+                            Some("a".to_string()),
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        ),
+                        RawField::Unresolved(
+                            Some("tob".to_string()),
+                            "Tob".to_string(),
+                            None,
+                            Properties::new(),
+                            Position::default(),
+                        ),
                     ],
                     None,
                     None,
+                    Properties::new(),
                 ),
             ],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -704,27 +1361,30 @@ mod tests {
         }
     }";
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![
                 RawField::Enum(
                     Some("Meal".to_string()),
-                    vec!["Dinner".to_string(), "Lunch".to_string()],
+                    vec![symbol("Dinner"), symbol("Lunch")],
                     HasDefault::Default(Some("Dinner".to_string())),
                     None,
                     None,
+                    Properties::new(),
                 ),
                 RawField::Enum(
                     Some("House".to_string()),
-                    vec!["Apartment".to_string(), "Cottage".to_string()],
+                    vec![symbol("Apartment"), symbol("Cottage")],
                     HasDefault::None,
                     None,
                     None,
+                    Properties::new(),
                 ),
             ],
             None,
             None,
+            Properties::new(),
         );
 
         assert_eq!(res, expected);
@@ -741,7 +1401,7 @@ mod tests {
         }    
     }";
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -749,28 +1409,60 @@ mod tests {
                 vec![
                     RawField::Union(
                         Some("a".to_string()),
-                        vec![RawField::Int(None, HasDefault::None, None), RawField::Null],
+                        vec![
+                            RawField::Int(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::Null,
+                        ],
                         HasDefault::Default(Some(Literal::Int(3))),
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("b".to_string()),
-                        vec![RawField::Int(None, HasDefault::None, None), RawField::Null],
+                        vec![
+                            RawField::Int(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::Null,
+                        ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("c".to_string()),
-                        vec![RawField::Int(None, HasDefault::None, None), RawField::Null],
+                        vec![
+                            RawField::Int(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::Null,
+                        ],
                         HasDefault::Default(None),
                         None,
+                        Properties::new(),
                     ),
                 ],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -787,7 +1479,7 @@ mod tests {
         }    
     }";
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -795,30 +1487,74 @@ mod tests {
                 vec![
                     RawField::Union(
                         Some("a".to_string()),
-                        vec![RawField::Long(None, HasDefault::None, None), RawField::Null],
+                        vec![
+                            RawField::Long(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::Null,
+                        ],
                         HasDefault::Default(Some(Literal::Long(3))),
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("b".to_string()),
-                        vec![RawField::Long(None, HasDefault::None, None), RawField::Null],
+                        vec![
+                            RawField::Long(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::Null,
+                        ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("c".to_string()),
-                        vec![RawField::Long(None, HasDefault::None, None), RawField::Null],
+                        vec![
+                            RawField::Long(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::Null,
+                        ],
                         HasDefault::Default(None),
                         None,
+                        Properties::new(),
+                    ),
+                    RawField::Long(
+                        Some("d".to_string()),
+                        HasDefault::Default(Some(1)),
+                        None,
+                        Properties::new(),
+                        Properties::new(),
+                    ),
+                    RawField::Long(
+                        Some("e".to_string()),
+                        HasDefault::None,
+                        None,
+                        Properties::new(),
+                        Properties::new(),
                     ),
-                    RawField::Long(Some("d".to_string()), HasDefault::Default(Some(1)), None),
-                    RawField::Long(Some("e".to_string()), HasDefault::None, None),
                 ],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -836,7 +1572,7 @@ mod tests {
         }    
     }";
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -845,38 +1581,73 @@ mod tests {
                     RawField::Union(
                         Some("a".to_string()),
                         vec![
-                            RawField::Float(None, HasDefault::None, None),
+                            RawField::Float(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(Some(Literal::Float(3.0))),
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("b".to_string()),
                         vec![
-                            RawField::Float(None, HasDefault::None, None),
+                            RawField::Float(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("c".to_string()),
                         vec![
-                            RawField::Float(None, HasDefault::None, None),
+                            RawField::Float(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(None),
                         None,
+                        Properties::new(),
+                    ),
+                    RawField::Float(
+                        Some("d".to_string()),
+                        HasDefault::Default(Some(1.0)),
+                        None,
+                        Properties::new(),
+                        Properties::new(),
+                    ),
+                    RawField::Float(
+                        Some("e".to_string()),
+                        HasDefault::None,
+                        None,
+                        Properties::new(),
+                        Properties::new(),
                     ),
-                    RawField::Float(Some("d".to_string()), HasDefault::Default(Some(1.0)), None),
-                    RawField::Float(Some("e".to_string()), HasDefault::None, None),
                 ],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -894,7 +1665,7 @@ mod tests {
         }    
     }";
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -903,38 +1674,73 @@ mod tests {
                     RawField::Union(
                         Some("a".to_string()),
                         vec![
-                            RawField::Double(None, HasDefault::None, None),
+                            RawField::Double(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(Some(Literal::Double(3.0))),
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("b".to_string()),
                         vec![
-                            RawField::Double(None, HasDefault::None, None),
+                            RawField::Double(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("c".to_string()),
                         vec![
-                            RawField::Double(None, HasDefault::None, None),
+                            RawField::Double(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(None),
                         None,
+                        Properties::new(),
+                    ),
+                    RawField::Double(
+                        Some("d".to_string()),
+                        HasDefault::Default(Some(1.0)),
+                        None,
+                        Properties::new(),
+                        Properties::new(),
+                    ),
+                    RawField::Double(
+                        Some("e".to_string()),
+                        HasDefault::None,
+                        None,
+                        Properties::new(),
+                        Properties::new(),
                     ),
-                    RawField::Double(Some("d".to_string()), HasDefault::Default(Some(1.0)), None),
-                    RawField::Double(Some("e".to_string()), HasDefault::None, None),
                 ],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -952,7 +1758,7 @@ mod tests {
         }    
     }";
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -961,42 +1767,73 @@ mod tests {
                     RawField::Union(
                         Some("a".to_string()),
                         vec![
-                            RawField::Boolean(None, HasDefault::None, None),
+                            RawField::Boolean(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(Some(Literal::Boolean(true))),
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("b".to_string()),
                         vec![
-                            RawField::Boolean(None, HasDefault::None, None),
+                            RawField::Boolean(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("c".to_string()),
                         vec![
-                            RawField::Boolean(None, HasDefault::None, None),
+                            RawField::Boolean(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(None),
                         None,
+                        Properties::new(),
                     ),
                     RawField::Boolean(
                         Some("d".to_string()),
                         HasDefault::Default(Some(false)),
                         None,
+                        Properties::new(),
+                        Properties::new(),
+                    ),
+                    RawField::Boolean(
+                        Some("e".to_string()),
+                        HasDefault::None,
+                        None,
+                        Properties::new(),
+                        Properties::new(),
                     ),
-                    RawField::Boolean(Some("e".to_string()), HasDefault::None, None),
                 ],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -1015,7 +1852,7 @@ mod tests {
     }";
         //
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -1024,42 +1861,73 @@ mod tests {
                     RawField::Union(
                         Some("a".to_string()),
                         vec![
-                            RawField::String(None, HasDefault::None, None),
+                            RawField::String(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(Some(Literal::String("hello there".to_string()))),
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("b".to_string()),
                         vec![
-                            RawField::String(None, HasDefault::None, None),
+                            RawField::String(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("c".to_string()),
                         vec![
-                            RawField::String(None, HasDefault::None, None),
+                            RawField::String(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(None),
                         None,
+                        Properties::new(),
                     ),
                     RawField::String(
                         Some("d".to_string()),
                         HasDefault::Default(Some(" what ?? is !! going //on<<z".to_string())),
                         None,
+                        Properties::new(),
+                        Properties::new(),
+                    ),
+                    RawField::String(
+                        Some("e".to_string()),
+                        HasDefault::None,
+                        None,
+                        Properties::new(),
+                        Properties::new(),
                     ),
-                    RawField::String(Some("e".to_string()), HasDefault::None, None),
                 ],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -1079,7 +1947,7 @@ mod tests {
     }";
         //
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![
@@ -1089,30 +1957,48 @@ mod tests {
                         Some("e".to_string()),
                         HasDefault::None,
                         None,
+                        Properties::new(),
+                        Properties::new(),
                     )],
                     None,
                     None,
+                    Properties::new(),
                 ),
                 RawField::Record(
                     Some("B".to_string()),
                     vec![
-                        RawField::Unresolved(Some("a".to_string()), "A".to_string(), None),
+                        RawField::Unresolved(
+                            Some("a".to_string()),
+                            "A".to_string(),
+                            None,
+                            Properties::new(),
+                            Position::default(),
+                        ),
                         RawField::Union(
                             Some("b".to_string()),
                             vec![
-                                RawField::Unresolved(None, "A".to_string(), None),
+                                RawField::Unresolved(
+                                    None,
+                                    "A".to_string(),
+                                    None,
+                                    Properties::new(),
+                                    Position::default(),
+                                ),
                                 RawField::Null,
                             ],
                             HasDefault::None,
                             None,
+                            Properties::new(),
                         ),
                     ],
                     None,
                     None,
+                    Properties::new(),
                 ),
             ],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -1127,22 +2013,32 @@ mod tests {
     }";
         //
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
                 Some("A".to_string()),
                 vec![RawField::Array(
                     Some("myints".to_string()),
-                    Box::new(RawField::Int(None, HasDefault::None, None)),
+                    Box::new(RawField::Int(
+                        None,
+                        HasDefault::None,
+                        None,
+                        Properties::new(),
+                        Properties::new(),
+                    )),
                     HasDefault::None,
                     None,
+                    Properties::new(),
+                    Properties::new(),
                 )],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -1161,7 +2057,7 @@ mod tests {
     }";
         //
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -1169,28 +2065,54 @@ mod tests {
                 vec![
                     RawField::Array(
                         Some("myints".to_string()),
-                        Box::new(RawField::Int(None, HasDefault::None, None)),
+                        Box::new(RawField::Int(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        )),
                         HasDefault::None,
                         None,
+                        Properties::new(),
+                        Properties::new(),
                     ),
                     RawField::Array(
                         Some("mystrs".to_string()),
-                        Box::new(RawField::String(None, HasDefault::None, None)),
+                        Box::new(RawField::String(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        )),
                         HasDefault::None,
                         Some(String::from("hi")),
+                        Properties::new(),
+                        Properties::new(),
                     ),
                     RawField::Array(
                         Some("myfloats".to_string()),
-                        Box::new(RawField::Float(None, HasDefault::None, None)),
+                        Box::new(RawField::Float(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        )),
                         HasDefault::None,
                         Some(String::from("hi")),
+                        Properties::new(),
+                        Properties::new(),
                     ),
                 ],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -1205,7 +2127,7 @@ mod tests {
     }";
         //
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -1213,17 +2135,32 @@ mod tests {
                 vec![RawField::Union(
                     Some("mynum".to_string()),
                     vec![
-                        RawField::Int(None, HasDefault::None, None),
-                        RawField::Float(None, HasDefault::None, None),
+                        RawField::Int(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        ),
+                        RawField::Float(
+                            None,
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        ),
                     ],
                     HasDefault::None,
                     None,
+                    Properties::new(),
                 )],
                 None,
                 None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -1242,7 +2179,7 @@ mod tests {
     }";
         //
         let idl = AvroIdlLexer::new("none".to_string());
-        let res = idl.parse_idl(src.to_string(), PathBuf::new()).unwrap();
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
         let expected = RawField::Protocol(
             Some("Event".to_string()),
             vec![RawField::Record(
@@ -1251,51 +2188,544 @@ mod tests {
                     RawField::Union(
                         Some("mynum".to_string()),
                         vec![
-                            RawField::Int(None, HasDefault::None, None),
-                            RawField::Float(None, HasDefault::None, None),
+                            RawField::Int(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::Float(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                         ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("myval".to_string()),
                         vec![
-                            RawField::Int(None, HasDefault::None, None),
-                            RawField::String(None, HasDefault::None, None),
+                            RawField::Int(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::String(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                         ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("myval2".to_string()),
-                        vec![RawField::Int(None, HasDefault::None, None), RawField::Null],
+                        vec![
+                            RawField::Int(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
+                            RawField::Null,
+                        ],
                         HasDefault::None,
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("myval3".to_string()),
                         vec![
-                            RawField::Double(None, HasDefault::None, None),
+                            RawField::Double(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(None),
                         None,
+                        Properties::new(),
                     ),
                     RawField::Union(
                         Some("myval4".to_string()),
                         vec![
-                            RawField::Double(None, HasDefault::None, None),
+                            RawField::Double(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            ),
                             RawField::Null,
                         ],
                         HasDefault::Default(Some(Literal::Double(-0.21))),
                         None,
+                        Properties::new(),
                     ),
                 ],
                 None,
                 None,
+                Properties::new(),
+            )],
+            None,
+            None,
+            Properties::new(),
+        );
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_annotations() {
+        let src = "@namespace(\"org.example\")
+    @version(\"1.0\")
+    protocol Event {
+        @java-class(\"org.example.Person\")
+        record Person {
+            @pii(true) string name;
+            int @owner(\"team\") age;
+            Meal @meta({\"tags\": [\"a\", \"b\"], \"level\": -1.5e2, \"null\": null}) meal;
+        }
+
+        @namespace(\"org.meals\")
+        enum Meal {
+            Dinner,
+            Lunch
+        }
+    }";
+        let idl = AvroIdlLexer::new("none".to_string());
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
+
+        let props = |value: serde_json::Value| value.as_object().unwrap().clone();
+        let expected = RawField::Protocol(
+            Some("Event".to_string()),
+            vec![
+                RawField::Record(
+                    Some("Person".to_string()),
+                    vec![
+                        RawField::String(
+                            Some("name".to_string()),
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            props(json!({"pii": true})),
+                        ),
+                        RawField::Int(
+                            Some("age".to_string()),
+                            HasDefault::None,
+                            None,
+                            props(json!({"owner": "team"})),
+                            Properties::new(),
+                        ),
+                        RawField::Unresolved(
+                            Some("meal".to_string()),
+                            "Meal".to_string(),
+                            None,
+                            props(
+                                json!({"meta": {"tags": ["a", "b"], "level": -150.0, "null": null}}),
+                            ),
+                            Position::default(),
+                        ),
+                    ],
+                    Some("org.example".to_string()),
+                    None,
+                    props(json!({"java-class": "org.example.Person"})),
+                ),
+                RawField::Enum(
+                    Some("Meal".to_string()),
+                    vec![symbol("Dinner"), symbol("Lunch")],
+                    HasDefault::None,
+                    Some("org.meals".to_string()),
+                    None,
+                    Properties::new(),
+                ),
+            ],
+            Some("org.example".to_string()),
+            None,
+            props(json!({"version": "1.0"})),
+        );
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_annotated_reference() {
+        let src = "protocol Event {
+        record Person {
+            @pii(true) Meal meal;
+        }
+    }";
+        let idl = AvroIdlLexer::new("none".to_string());
+        let Err(AvroError::FailedParsing(errors)) = idl.parse_idl(src.to_string(), PathBuf::new())
+        else {
+            panic!("Annotations before a reference must be rejected");
+        };
+        assert_eq!(
+            errors[0].message,
+            "Type references can't be annotated, annotations of the field go before its name"
+        );
+    }
+
+    #[test]
+    fn test_enum_trailing_comma_and_symbol_docstrings() {
+        let src = "protocol Event {
+        enum Meal {
+            /** Evening */
+            Dinner, // Late
+            // Noon
+            Lunch,
+        } = Lunch;
+
+        enum Empty {}
+    }";
+        let idl = AvroIdlLexer::new("none".to_string());
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
+        let expected = RawField::Protocol(
+            Some("Event".to_string()),
+            vec![
+                RawField::Enum(
+                    Some("Meal".to_string()),
+                    vec![
+                        RawField::Symbol("Dinner".to_string(), Some("Evening".to_string())),
+                        symbol("Lunch"),
+                    ],
+                    HasDefault::Default(Some("Lunch".to_string())),
+                    None,
+                    None,
+                    Properties::new(),
+                ),
+                RawField::Enum(
+                    Some("Empty".to_string()),
+                    vec![],
+                    HasDefault::None,
+                    None,
+                    None,
+                    Properties::new(),
+                ),
+            ],
+            None,
+            None,
+            Properties::new(),
+        );
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_protocol_errors_unless_schema_file() {
+        let idl = AvroIdlLexer::new("none".to_string());
+        let cases = [
+            (
+                "protocl Event {\n}",
+                "none:1:1: Expected a protocol or schema declaration, found 'protocl'",
+            ),
+            ("", "none:1:1: Expected a protocol or schema declaration"),
+            (
+                "// Only a comment\n",
+                "none:2:1: Expected a protocol or schema declaration",
+            ),
+        ];
+        for (src, message) in cases {
+            let err = idl
+                .parse_idl(src.to_string(), PathBuf::from("none"))
+                .unwrap_err();
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_enum_validation() {
+        let idl = AvroIdlLexer::new("none".to_string());
+        let cases = [
+            (
+                "protocol Event {\n  enum Meal { Dinner, 2nd }\n}",
+                "none:2:23: Invalid symbol '2nd' in enum 'Meal'",
+            ),
+            (
+                "protocol Event {\n  enum Meal { Dinner, Lunch, Dinner }\n}",
+                "none:2:30: Duplicate symbol 'Dinner' in enum 'Meal'",
+            ),
+            (
+                "protocol Event {\n  enum Meal { Dinner, Lunch } = Breakfast;\n}",
+                "none:2:33: Default 'Breakfast' of enum 'Meal' is not one of its symbols",
+            ),
+        ];
+        for (src, message) in cases {
+            let err = idl
+                .parse_idl(src.to_string(), PathBuf::from("none"))
+                .unwrap_err();
+            assert!(err.to_string().starts_with(message), "{}", err);
+        }
+
+        let err = idl
+            .parse_idl(cases[1].0.to_string(), PathBuf::from("none"))
+            .unwrap_err();
+        let AvroError::FailedParsing(errors) = err else {
+            panic!("Expected a parse error, found {:?}", err);
+        };
+        assert_eq!(errors[0].location.span, 46..52);
+    }
+
+    #[test]
+    fn test_docstrings() {
+        let src = "/**
+     * The events.
+     *
+     * Sent by the frontend.
+     */
+    protocol Event {
+        @namespace(\"org.meals\")
+        /** Meals of the day */
+        enum Meal {
+            Dinner
+        }
+
+        /** A record
+              with an indented
+            second line */
+        record A {
+            int a;
+            array<int> myints;
+            /** Meal */ Meal meal;
+            /** Union */ union { null, int } b;
+        }
+    }";
+        let idl = AvroIdlLexer::new("none".to_string());
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
+        let expected = RawField::Protocol(
+            Some("Event".to_string()),
+            vec![
+                RawField::Enum(
+                    Some("Meal".to_string()),
+                    vec![symbol("Dinner")],
+                    HasDefault::None,
+                    Some("org.meals".to_string()),
+                    Some("Meals of the day".to_string()),
+                    Properties::new(),
+                ),
+                RawField::Record(
+                    Some("A".to_string()),
+                    vec![
+                        RawField::Int(
+                            Some("a".to_string()),
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        ),
+                        RawField::Array(
+                            Some("myints".to_string()),
+                            Box::new(RawField::Int(
+                                None,
+                                HasDefault::None,
+                                None,
+                                Properties::new(),
+                                Properties::new(),
+                            )),
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        ),
+                        RawField::Unresolved(
+                            Some("meal".to_string()),
+                            "Meal".to_string(),
+                            Some("Meal".to_string()),
+                            Properties::new(),
+                            Position::default(),
+                        ),
+                        RawField::Union(
+                            Some("b".to_string()),
+                            vec![
+                                RawField::Null,
+                                RawField::Int(
+                                    None,
+                                    HasDefault::None,
+                                    None,
+                                    Properties::new(),
+                                    Properties::new(),
+                                ),
+                            ],
+                            HasDefault::None,
+                            Some("Union".to_string()),
+                            Properties::new(),
+                        ),
+                    ],
+                    None,
+                    Some("A record\n  with an indented\nsecond line".to_string()),
+                    Properties::new(),
+                ),
+            ],
+            None,
+            Some("The events.\n\nSent by the frontend.".to_string()),
+            Properties::new(),
+        );
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_schema_document() {
+        let src = "namespace org.example;
+    schema array<Person>;
+
+    record Person {
+        string name;
+    }";
+        let idl = AvroIdlLexer::new("none".to_string());
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
+        let expected = RawField::Schema(
+            Some(Box::new(RawField::Array(
+                None,
+                Box::new(RawField::Unresolved(
+                    None,
+                    "Person".to_string(),
+                    None,
+                    Properties::new(),
+                    Position::default(),
+                )),
+                HasDefault::None,
+                None,
+                Properties::new(),
+                Properties::new(),
+            ))),
+            vec![RawField::Record(
+                Some("Person".to_string()),
+                vec![RawField::String(
+                    Some("name".to_string()),
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )],
+                Some("org.example".to_string()),
+                None,
+                Properties::new(),
+            )],
+            Some("org.example".to_string()),
+        );
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_import_paths() {
+        let src = "protocol Event {
+        import idl \"child/ts.avdl\";
+    }";
+        let idl = AvroIdlLexer::new("<stdin>".to_string());
+        let err = idl.parse_source(src.to_string()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "<stdin>: Failed to find import 'child/ts.avdl'"
+        );
+
+        let idl = AvroIdlLexer::new("<stdin>".to_string())
+            .with_import_paths(vec!["examples".to_string()]);
+        let res = idl.parse_source(src.to_string()).unwrap();
+        assert_eq!(
+            idl.imported_files(),
+            vec![Path::new("examples/child/ts.avdl").canonicalize().unwrap()]
+        );
+        assert_eq!(
+            idl.imported_types(),
+            vec![("child/ts.avdl".to_string(), vec!["Date".to_string()])]
+        );
+        let expected = RawField::Protocol(
+            Some("Event".to_string()),
+            vec![RawField::Record(
+                Some("Date".to_string()),
+                vec![RawField::Int(
+                    Some("now".to_string()),
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )],
+                None,
+                None,
+                Properties::new(),
+            )],
+            None,
+            None,
+            Properties::new(),
+        );
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_comments() {
+        let src = "// Header
+protocol Event {
+    /* Block
+       comment */
+    record Person {
+        string name; // The name
+    }
+}";
+        let idl = AvroIdlLexer::new("none".to_string());
+        let res = idl.parse_syntax(src.to_string()).unwrap();
+        let person = RawField::Record(
+            Some("Person".to_string()),
+            vec![
+                RawField::String(
+                    Some("name".to_string()),
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                ),
+                RawField::Comment("// The name".to_string(), true),
+            ],
+            None,
+            None,
+            Properties::new(),
+        );
+        let expected = vec![
+            RawField::Comment("// Header".to_string(), false),
+            RawField::Protocol(
+                Some("Event".to_string()),
+                vec![
+                    RawField::Comment("/* Block\n       comment */".to_string(), false),
+                    person,
+                ],
+                None,
+                None,
+                Properties::new(),
+            ),
+        ];
+        assert_eq!(res, expected);
+
+        // Comments are dropped when parsing for compilation
+        let res = without_positions(idl.parse_idl(src.to_string(), PathBuf::new()).unwrap());
+        let expected = RawField::Protocol(
+            Some("Event".to_string()),
+            vec![RawField::Record(
+                Some("Person".to_string()),
+                vec![RawField::String(
+                    Some("name".to_string()),
+                    HasDefault::None,
+                    None,
+                    Properties::new(),
+                    Properties::new(),
+                )],
+                None,
+                None,
+                Properties::new(),
             )],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
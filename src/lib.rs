@@ -1,6 +1,20 @@
 pub mod ast;
+pub mod builder;
+pub mod codegen;
+pub mod compat;
+pub mod compiler;
+pub mod deserializer;
+pub mod diagnostics;
+pub mod diagram;
+pub mod diff;
+pub mod docgen;
 pub mod error;
+pub mod json_schema;
 pub mod lexer;
 pub mod linker;
+pub mod printer;
+pub mod resolver;
 pub mod runner;
+pub mod schema;
 pub mod serializer;
+pub mod visitor;
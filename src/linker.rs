@@ -1,97 +1,236 @@
-use crate::{ast::Field, ast::RawField, error::AvroError};
+use crate::ast::{Field, RawField};
+use crate::error::AvroError;
+use crate::schema::Document;
+use crate::visitor::{walk_children, walk_field, Node, Visitor};
 
 pub struct LinkParser {}
 
+impl Default for LinkParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LinkParser {
     pub fn new() -> Self {
         LinkParser {}
     }
 
+    /// Link a parsed protocol or schema file into the typed schema model
+    pub fn link(&self, protocol: RawField) -> Result<Document, AvroError> {
+        Document::try_from(self.parse(protocol)?)
+    }
+
     pub fn parse(&self, protocol: RawField) -> Result<Field, AvroError> {
-        let dup_protocol = protocol.clone();
+        let namespace = match &protocol {
+            RawField::Protocol(_, _, namespace, ..) | RawField::Schema(_, _, namespace) => {
+                namespace.clone()
+            }
+            _ => {
+                return Err(AvroError::InvalidASTDataType(
+                    "Expected a protocol or a schema".to_string(),
+                ))
+            }
+        };
+        let mut linker = Linker {
+            protocol: &protocol,
+            namespaces: vec![namespace],
+            linked: vec![],
+            error: None,
+        };
+        for field in protocol.children() {
+            linker.visit_field(field);
+        }
+        if let Some(err) = linker.error {
+            return Err(err);
+        }
+        let mut linked = linker.linked;
+
+        match protocol {
+            RawField::Protocol(name, _, namespace, docstring, props) => {
+                Ok(Field::Protocol(name, linked, namespace, docstring, props))
+            }
+            RawField::Schema(main, _, namespace) => {
+                let linked_main = main.map(|_| Box::new(linked.remove(0)));
+                Ok(Field::Schema(linked_main, linked, namespace))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Links the fields of a protocol or schema file, where references are resolved relative to
+/// the enclosing namespace. Every visited field pushes its linked field, so a field takes
+/// the ones its children pushed.
+struct Linker<'a> {
+    protocol: &'a RawField,
+    /// Namespaces of the enclosing records, innermost last
+    namespaces: Vec<Option<String>>,
+    linked: Vec<Field>,
+    error: Option<AvroError>,
+}
+
+impl Linker<'_> {
+    /// Link the children of a field
+    fn link_children(&mut self, field: &RawField) -> Vec<Field> {
+        let start = self.linked.len();
+        walk_children(self, field);
+        self.linked.split_off(start.min(self.linked.len()))
+    }
+
+    fn fail(&mut self, err: AvroError) {
+        self.error.get_or_insert(err);
+    }
+}
+
+impl Visitor<RawField> for Linker<'_> {
+    fn visit_field(&mut self, field: &RawField) {
+        if self.error.is_none() {
+            walk_field(self, field)
+        }
+    }
+
+    fn visit_protocol(&mut self, _protocol: &RawField) {
+        self.fail(AvroError::InvalidASTDataType(
+            "'Protocol' can only be declared once per file!".to_string(),
+        ))
+    }
+
+    fn visit_schema(&mut self, _schema: &RawField) {
+        self.fail(AvroError::InvalidASTDataType(
+            "'Schema' can only be declared once per file!".to_string(),
+        ))
+    }
 
-        let RawField::Protocol(name, fields, namespace, docstring) = protocol else {
-            return Err(AvroError::InvalidASTDataType(
-                "Expected a protocol".to_string(),
+    fn visit_record(&mut self, record: &RawField) {
+        let RawField::Record(name, _, namespace, docstring, props) = record else {
+            return;
+        };
+        let enclosing = namespace
+            .clone()
+            .or_else(|| self.namespaces.last().cloned().flatten());
+        self.namespaces.push(enclosing);
+        let fields = self.link_children(record);
+        self.namespaces.pop();
+        self.linked.push(Field::Record(
+            name.clone(),
+            fields,
+            namespace.clone(),
+            docstring.clone(),
+            props.clone(),
+        ));
+    }
+
+    fn visit_enum(&mut self, enum_: &RawField) {
+        if let RawField::Enum(name, _, default, namespace, docstring, props) = enum_ {
+            self.linked.push(Field::Enum(
+                name.clone(),
+                enum_.symbols(),
+                default.clone(),
+                namespace.clone(),
+                docstring.clone(),
+                props.clone(),
             ));
+        }
+    }
+
+    fn visit_union(&mut self, union: &RawField) {
+        let RawField::Union(name, _, default, docstring, props) = union else {
+            return;
         };
+        let fields = self.link_children(union);
+        self.linked.push(Field::Union(
+            name.clone(),
+            fields,
+            default.clone(),
+            docstring.clone(),
+            props.clone(),
+        ));
+    }
 
-        let linked_fields = fields
-            .into_iter()
-            .map(|field| self.parse_recurse(&dup_protocol, field).unwrap())
-            .collect();
-        Ok(Field::Protocol(name, linked_fields, namespace, docstring))
+    fn visit_array(&mut self, array: &RawField) {
+        let RawField::Array(name, _, default, docstring, props, type_props) = array else {
+            return;
+        };
+        let Some(inner) = self.link_children(array).pop() else {
+            return;
+        };
+        self.linked.push(Field::Array(
+            name.clone(),
+            Box::new(inner),
+            default.clone(),
+            docstring.clone(),
+            props.clone(),
+            type_props.clone(),
+        ));
     }
 
-    fn parse_recurse(&self, protocol: &RawField, field: RawField) -> Result<Field, AvroError> {
-        match field {
-            RawField::Int(name, default, docstring) => Ok(Field::Int(name, default, docstring)),
-            RawField::Long(name, default, docstring) => Ok(Field::Long(name, default, docstring)),
-            RawField::Float(name, default, docstring) => Ok(Field::Float(name, default, docstring)),
-            RawField::Double(name, default, docstring) => {
-                Ok(Field::Double(name, default, docstring))
+    fn visit_primitive(&mut self, primitive: &RawField) {
+        let linked = match primitive.clone() {
+            RawField::Int(name, default, docstring, props, type_props) => {
+                Field::Int(name, default, docstring, props, type_props)
+            }
+            RawField::Long(name, default, docstring, props, type_props) => {
+                Field::Long(name, default, docstring, props, type_props)
             }
-            RawField::Boolean(name, default, docstring) => {
-                Ok(Field::Boolean(name, default, docstring))
+            RawField::Float(name, default, docstring, props, type_props) => {
+                Field::Float(name, default, docstring, props, type_props)
             }
-            RawField::String(name, default, docstring) => {
-                Ok(Field::String(name, default, docstring))
+            RawField::Double(name, default, docstring, props, type_props) => {
+                Field::Double(name, default, docstring, props, type_props)
             }
-            RawField::Enum(name, values, default, namespace, docstring) => {
-                Ok(Field::Enum(name, values, default, namespace, docstring))
+            RawField::Boolean(name, default, docstring, props, type_props) => {
+                Field::Boolean(name, default, docstring, props, type_props)
             }
-            RawField::Record(name, fields, namespace, docstring) => {
-                let linked_fields = fields
-                    .into_iter()
-                    .map(|f| self.parse_recurse(protocol, f).unwrap())
-                    .collect();
-                Ok(Field::Record(name, linked_fields, namespace, docstring))
+            RawField::String(name, default, docstring, props, type_props) => {
+                Field::String(name, default, docstring, props, type_props)
             }
-            RawField::Unresolved(_name, value, docstring) => {
-                let Some(ref_field) = protocol.find_field_by_name(value.clone()) else {
-                    return Err(AvroError::UndefinedReference(format!(
-                        "Field of type '{}' cannot be found!",
-                        value
-                    )));
-                };
-                match ref_field {
-                    RawField::Record(..) => Ok(Field::RecordReference(_name, value, docstring)),
-                    RawField::Enum(_, _, default, ..) => {
-                        Ok(Field::EnumReference(_name, value, default, docstring))
-                    }
-                    _ => Err(AvroError::InvalidASTDataType(
-                        "Only Record and Enum are valid references!".to_string(),
-                    )),
-                }
+            _ => Field::Null,
+        };
+        self.linked.push(linked);
+    }
+
+    fn visit_reference(&mut self, reference: &RawField) {
+        let RawField::Unresolved(name, value, docstring, props, position) = reference.clone()
+        else {
+            return;
+        };
+        let namespace = self.namespaces.last().cloned().flatten();
+        match self
+            .protocol
+            .find_field_by_name(value.clone(), namespace.as_deref())
+        {
+            Some(RawField::Record(..)) => {
+                self.linked
+                    .push(Field::RecordReference(name, value, docstring, props));
             }
-            RawField::Union(name, fields, default, docstring) => {
-                let linked_fields = fields
-                    .into_iter()
-                    .map(|f| self.parse_recurse(protocol, f).unwrap())
-                    .collect();
-                Ok(Field::Union(name, linked_fields, default, docstring))
+            Some(RawField::Enum(_, _, default, ..)) => {
+                self.linked
+                    .push(Field::EnumReference(name, value, default, docstring, props));
             }
-            RawField::Protocol(..) => Err(AvroError::InvalidASTDataType(
-                "'Protocol' can only be declared once per file!".to_string(),
+            Some(_) => self.fail(AvroError::InvalidASTDataType(
+                "Only Record and Enum are valid references!".to_string(),
             )),
-            RawField::Array(name, inner_field, default, docstring) => Ok(Field::Array(
-                name,
-                Box::new(self.parse_recurse(protocol, *inner_field).unwrap()),
-                default,
-                docstring,
+            None => self.fail(AvroError::UndefinedReference(
+                format!("Field of type '{}' cannot be found!", value),
+                position.0,
             )),
-            RawField::Import(_) => Err(AvroError::InvalidASTDataType(
-                "'Import' should have been resolved previous to Linking!".to_string(),
-            )),
-            RawField::Null => Ok(Field::Null),
         }
     }
+
+    fn visit_other(&mut self, other: &RawField) {
+        let message = match other {
+            RawField::Import(_) => "'Import' should have been resolved previous to Linking!",
+            _ => "'Comment' should have been removed previous to Linking!",
+        };
+        self.fail(AvroError::InvalidASTDataType(message.to_string()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::ast::{Field, HasDefault, RawField};
+    use crate::ast::{Field, HasDefault, Position, Properties, RawField};
 
     use super::LinkParser;
 
@@ -102,23 +241,41 @@ mod tests {
             vec![
                 RawField::Enum(
                     Some("Meal".to_string()),
-                    vec!["Dinner".to_string(), "Lunch".to_string()],
+                    vec![
+                        RawField::Symbol("Dinner".to_string(), None),
+                        RawField::Symbol("Lunch".to_string(), None),
+                    ],
                     HasDefault::Default(Some("Dinner".to_string())),
                     None,
                     None,
+                    Properties::new(),
                 ),
                 RawField::Record(
                     Some("Lol".to_string()),
                     vec![
-                        RawField::Int(Some("a".to_string()), HasDefault::None, None),
-                        RawField::Unresolved(Some("meal".to_string()), "Meal".to_string(), None),
+                        RawField::Int(
+                            Some("a".to_string()),
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        ),
+                        RawField::Unresolved(
+                            Some("meal".to_string()),
+                            "Meal".to_string(),
+                            None,
+                            Properties::new(),
+                            Position::default(),
+                        ),
                     ],
                     None,
                     None,
+                    Properties::new(),
                 ),
             ],
             None,
             None,
+            Properties::new(),
         );
 
         let linker = LinkParser::new();
@@ -132,24 +289,34 @@ mod tests {
                     HasDefault::Default(Some("Dinner".to_string())),
                     None,
                     None,
+                    Properties::new(),
                 ),
                 Field::Record(
                     Some("Lol".to_string()),
                     vec![
-                        Field::Int(Some("a".to_string()), HasDefault::None, None),
+                        Field::Int(
+                            Some("a".to_string()),
+                            HasDefault::None,
+                            None,
+                            Properties::new(),
+                            Properties::new(),
+                        ),
                         Field::EnumReference(
                             Some("meal".to_string()),
                             "Meal".to_string(),
                             HasDefault::None,
                             None,
+                            Properties::new(),
                         ),
                     ],
                     None,
                     None,
+                    Properties::new(),
                 ),
             ],
             None,
             None,
+            Properties::new(),
         );
         assert_eq!(res, expected);
     }
@@ -1,22 +1,364 @@
-use avro_idl::runner::{AvroIdlParser, OutputFormat};
-use clap::Parser;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use avro_idl::codegen::SqlDialect;
+use avro_idl::compat::CompatMode;
+use avro_idl::diagnostics::DiagnosticFormat;
+use avro_idl::docgen::DocFormat;
+use avro_idl::runner::{
+    AvroIdlBatchParser, AvroIdlChecker, AvroIdlCompatChecker, AvroIdlDiffer, AvroIdlDocumenter,
+    AvroIdlFormatter, AvroIdlParser, AvroJsonConverter, OutputFormat,
+};
+use clap::{Args as ClapArgs, Parser, Subcommand};
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     //#[arg(value_enum, short, long, default_value_t = OutputFormat::AVPR)]
-    path: String,
-    output_path: String,
+    /// Path of the IDL file, use '-' to read from stdin
+    #[arg(required = true)]
+    path: Option<String>,
+    /// Path of the output file, use '-' to write to stdout
+    #[arg(required = true)]
+    output_path: Option<String>,
     format: Option<OutputFormat>,
+    #[command(flatten)]
+    options: OutputOptions,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compile every .avdl file of directories or globs into an output directory
+    Batch {
+        /// Directories, searched recursively, globs or files to compile
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Directory the output is written to, mirroring the layout of the inputs
+        #[arg(short, long, value_name = "DIR")]
+        out_dir: String,
+        /// Output format, by default avpr for protocols and avsc for schema files
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+        #[command(flatten)]
+        options: OutputOptions,
+    },
+    /// Compile an IDL file whenever it or one of its imports changes
+    Watch {
+        /// Path of the IDL file
+        path: String,
+        /// Path of the output file, use '-' to write to stdout
+        output_path: String,
+        /// Output format, by default avpr for protocols and avsc for schema files
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Milliseconds between checks for changes
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+        #[command(flatten)]
+        options: OutputOptions,
+    },
+    /// Format IDL files in place
+    Fmt {
+        /// Directories, searched recursively, globs or files to format, use '-' for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Only report the files which aren't formatted and exit with an error if there are any
+        #[arg(long)]
+        check: bool,
+        /// Sort consecutive imports by path
+        #[arg(long)]
+        sort_imports: bool,
+        /// Number of spaces to indent with
+        #[arg(long, default_value_t = 2)]
+        indent: usize,
+    },
+    /// Convert an avpr protocol or avsc schema into IDL
+    FromJson {
+        /// Path of the avpr or avsc file, use '-' to read from stdin
+        path: String,
+        /// Path of the IDL file, use '-' to write to stdout
+        output_path: String,
+        /// Number of spaces to indent with
+        #[arg(long, default_value_t = 2)]
+        indent: usize,
+    },
+    /// Validate IDL files without writing any output
+    Check {
+        /// Directories, searched recursively, globs or files to check, use '-' for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Directory searched for imports, can be given multiple times
+        #[arg(short = 'I', long = "import-path", value_name = "DIR")]
+        import_paths: Vec<String>,
+        /// Format of the diagnostics written to stdout
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: DiagnosticFormat,
+    },
+    /// Check whether a new version of an IDL file is compatible with the old one
+    Compat {
+        /// Path of the old version
+        old: String,
+        /// Path of the new version
+        new: String,
+        /// Backward checks that the new version can read data of the old one, forward the
+        /// reverse and full both
+        #[arg(short, long, value_enum, default_value = "backward")]
+        mode: CompatMode,
+        /// Directory searched for imports, can be given multiple times
+        #[arg(short = 'I', long = "import-path", value_name = "DIR")]
+        import_paths: Vec<String>,
+    },
+    /// List the added, removed and changed types, fields, symbols, defaults and docs
+    Diff {
+        /// Path of the old version
+        old: String,
+        /// Path of the new version
+        new: String,
+        /// Format of the changes written to stdout
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: DiagnosticFormat,
+        /// Directory searched for imports, can be given multiple times
+        #[arg(short = 'I', long = "import-path", value_name = "DIR")]
+        import_paths: Vec<String>,
+    },
+    /// Render reference documentation of IDL files with an index by namespace
+    Doc {
+        /// Directories, searched recursively, globs or files to document
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Directory the pages are written to, mirroring the layout of the inputs
+        #[arg(short, long, value_name = "DIR")]
+        out_dir: String,
+        /// Format of the pages
+        #[arg(short, long, value_enum, default_value = "markdown")]
+        format: DocFormat,
+        /// Directory searched for imports, can be given multiple times
+        #[arg(short = 'I', long = "import-path", value_name = "DIR")]
+        import_paths: Vec<String>,
+    },
+}
+
+#[derive(ClapArgs)]
+struct OutputOptions {
+    /// Directory searched for imports, can be given multiple times
+    #[arg(short = 'I', long = "import-path", value_name = "DIR")]
+    import_paths: Vec<String>,
+    /// Pretty print the output
+    #[arg(long)]
+    pretty: bool,
+    /// Number of spaces to indent with when pretty printing
+    #[arg(long, default_value_t = 2, requires = "pretty")]
+    indent: usize,
+    /// Emit the Parsing Canonical Form of each schema, one per line
+    #[arg(long, conflicts_with = "pretty")]
+    canonical: bool,
+    /// Generate pydantic models instead of dataclasses with the python format
+    #[arg(long)]
+    pydantic: bool,
+    /// Dialect of the CREATE TABLE statements with the sql format
+    #[arg(long, value_enum, default_value = "postgres")]
+    dialect: SqlDialect,
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
 
-    let runner = AvroIdlParser::new(
-        args.path,
-        args.output_path,
-        args.format.unwrap_or(OutputFormat::AVPR),
-    );
-    runner.parse();
+    match args.command {
+        Some(Command::Batch {
+            inputs,
+            out_dir,
+            format,
+            options,
+        }) => {
+            let mut runner = AvroIdlBatchParser::new(inputs, out_dir, format)
+                .with_import_paths(options.import_paths)
+                .with_canonical(options.canonical)
+                .with_pydantic(options.pydantic)
+                .with_dialect(options.dialect);
+            if options.pretty {
+                runner = runner.with_pretty(options.indent);
+            }
+            match runner.parse() {
+                Ok(_) => ExitCode::SUCCESS,
+                Err(errors) => {
+                    for error in errors.iter() {
+                        eprintln!("{}", error);
+                    }
+                    eprintln!("{} error(s) while compiling", errors.len());
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(Command::Watch {
+            path,
+            output_path,
+            format,
+            interval,
+            options,
+        }) => {
+            let mut runner = AvroIdlParser::new(path, output_path, format)
+                .with_import_paths(options.import_paths)
+                .with_canonical(options.canonical)
+                .with_pydantic(options.pydantic)
+                .with_dialect(options.dialect);
+            if options.pretty {
+                runner = runner.with_pretty(options.indent);
+            }
+            runner.watch(Duration::from_millis(interval))
+        }
+        Some(Command::Fmt {
+            inputs,
+            check,
+            sort_imports,
+            indent,
+        }) => {
+            let (changed, diagnostics) = AvroIdlFormatter::new(inputs)
+                .with_indent(indent)
+                .with_sorted_imports(sort_imports)
+                .with_check(check)
+                .format();
+            for diagnostic in diagnostics.iter() {
+                eprintln!("{}", diagnostic);
+            }
+            if check {
+                for path in changed.iter() {
+                    println!("{}", path);
+                }
+            }
+            if diagnostics.is_empty() && (!check || changed.is_empty()) {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Some(Command::FromJson {
+            path,
+            output_path,
+            indent,
+        }) => match AvroJsonConverter::new(path, output_path)
+            .with_indent(indent)
+            .convert()
+        {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        },
+        Some(Command::Check {
+            inputs,
+            import_paths,
+            format,
+        }) => {
+            let diagnostics = AvroIdlChecker::new(inputs)
+                .with_import_paths(import_paths)
+                .check();
+            match format {
+                DiagnosticFormat::Text => {
+                    for diagnostic in diagnostics.iter() {
+                        println!("{}", diagnostic);
+                    }
+                }
+                DiagnosticFormat::Json => {
+                    println!("{}", serde_json::to_string(&diagnostics).unwrap());
+                }
+            }
+            if diagnostics.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Some(Command::Compat {
+            old,
+            new,
+            mode,
+            import_paths,
+        }) => {
+            let checker = AvroIdlCompatChecker::new(old, new)
+                .with_mode(mode)
+                .with_import_paths(import_paths);
+            match checker.check() {
+                Ok(incompatibilities) if incompatibilities.is_empty() => ExitCode::SUCCESS,
+                Ok(incompatibilities) => {
+                    for incompatibility in incompatibilities.iter() {
+                        println!("{}", incompatibility);
+                    }
+                    ExitCode::FAILURE
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(Command::Diff {
+            old,
+            new,
+            format,
+            import_paths,
+        }) => {
+            let differ = AvroIdlDiffer::new(old, new).with_import_paths(import_paths);
+            match differ.diff() {
+                Ok(changes) => {
+                    match format {
+                        DiagnosticFormat::Text => {
+                            for change in changes.iter() {
+                                println!("{}", change);
+                            }
+                        }
+                        DiagnosticFormat::Json => {
+                            println!("{}", serde_json::to_string(&changes).unwrap());
+                        }
+                    }
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(Command::Doc {
+            inputs,
+            out_dir,
+            format,
+            import_paths,
+        }) => {
+            let documenter = AvroIdlDocumenter::new(inputs, out_dir)
+                .with_format(format)
+                .with_import_paths(import_paths);
+            match documenter.generate() {
+                Ok(_) => ExitCode::SUCCESS,
+                Err(errors) => {
+                    for error in errors.iter() {
+                        eprintln!("{}", error);
+                    }
+                    eprintln!("{} error(s) while documenting", errors.len());
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        None => {
+            let options = args.options;
+            let mut runner =
+                AvroIdlParser::new(args.path.unwrap(), args.output_path.unwrap(), args.format)
+                    .with_import_paths(options.import_paths)
+                    .with_canonical(options.canonical)
+                    .with_pydantic(options.pydantic)
+                    .with_dialect(options.dialect);
+            if options.pretty {
+                runner = runner.with_pretty(options.indent);
+            }
+            match runner.parse() {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,601 @@
+use crate::ast::{HasDefault, Literal, Properties, RawField};
+use crate::error::AvroError;
+
+/// Prints parsed Avro IDL back to IDL in a canonical layout: one declaration or field per
+/// line, normalised indentation and docstrings, defaults aligned within a record and
+/// comments kept where they were.
+pub struct IdlPrinter {
+    fields: Vec<RawField>,
+    indent: usize,
+    sort_imports: bool,
+}
+
+impl IdlPrinter {
+    /// Create a new printer for the comments and the protocol or schema of a file, as
+    /// returned by `AvroIdlLexer::parse_syntax`
+    pub fn new(fields: Vec<RawField>) -> Self {
+        Self {
+            fields,
+            indent: 2,
+            sort_imports: false,
+        }
+    }
+
+    /// Indent with the given number of spaces instead of two
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Sort consecutive imports by path instead of keeping their order
+    pub fn with_sorted_imports(mut self, sort_imports: bool) -> Self {
+        self.sort_imports = sort_imports;
+        self
+    }
+
+    pub fn print(&self) -> Result<String, AvroError> {
+        let mut lines = vec![];
+        self.print_declarations(&self.fields, 0, &mut lines)?;
+        Ok(lines.join("\n") + "\n")
+    }
+
+    /// Print declarations separated by blank lines. Comments stick to the declaration
+    /// after them and consecutive imports are kept together.
+    fn print_declarations(
+        &self,
+        declarations: &[RawField],
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) -> Result<(), AvroError> {
+        let declarations = self.sorted_imports(declarations);
+        let mut previous: Option<&RawField> = None;
+        for declaration in declarations.iter() {
+            if let RawField::Comment(text, true) = declaration {
+                Self::append_trailing_comment(text, lines);
+                continue;
+            }
+            if let Some(previous) = previous {
+                let attached = matches!(previous, RawField::Comment(..))
+                    || matches!(
+                        (previous, declaration),
+                        (RawField::Import(_), RawField::Import(_))
+                    );
+                if !attached {
+                    lines.push(String::new());
+                }
+            }
+            self.print_declaration(declaration, depth, lines)?;
+            previous = Some(declaration);
+        }
+        Ok(())
+    }
+
+    fn print_declaration(
+        &self,
+        declaration: &RawField,
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) -> Result<(), AvroError> {
+        let indent = self.indentation(depth);
+        match declaration {
+            RawField::Protocol(name, fields, namespace, docstring, props) => {
+                let name = Self::required_name(name, "Protocol")?;
+                lines.extend(Self::docstring_lines(docstring, &indent));
+                lines.extend(Self::annotation_lines(namespace, props, &indent));
+                if fields.is_empty() {
+                    lines.push(format!("{}protocol {} {{}}", indent, name));
+                } else {
+                    lines.push(format!("{}protocol {} {{", indent, name));
+                    self.print_declarations(fields, depth + 1, lines)?;
+                    lines.push(format!("{}}}", indent));
+                }
+            }
+            RawField::Schema(main, fields, namespace) => {
+                if let Some(namespace) = namespace {
+                    lines.push(format!("{}namespace {};", indent, namespace));
+                }
+                if let Some(main) = main {
+                    lines.push(format!("{}schema {};", indent, Self::type_name(main)?));
+                }
+                if !fields.is_empty() {
+                    if namespace.is_some() || main.is_some() {
+                        lines.push(String::new());
+                    }
+                    self.print_declarations(fields, depth, lines)?;
+                }
+            }
+            RawField::Record(name, fields, namespace, docstring, props) => {
+                let name = Self::required_name(name, "Record")?;
+                lines.extend(Self::docstring_lines(docstring, &indent));
+                lines.extend(Self::annotation_lines(namespace, props, &indent));
+                if fields.is_empty() {
+                    lines.push(format!("{}record {} {{}}", indent, name));
+                } else {
+                    lines.push(format!("{}record {} {{", indent, name));
+                    self.print_fields(fields, depth + 1, lines)?;
+                    lines.push(format!("{}}}", indent));
+                }
+            }
+            RawField::Enum(name, body, default, namespace, docstring, props) => {
+                let name = Self::required_name(name, "Enum")?;
+                lines.extend(Self::docstring_lines(docstring, &indent));
+                lines.extend(Self::annotation_lines(namespace, props, &indent));
+                let default = match default {
+                    HasDefault::Default(Some(default)) => format!(" = {};", default),
+                    _ => String::new(),
+                };
+                if body.is_empty() {
+                    lines.push(format!("{}enum {} {{}}{}", indent, name, default));
+                } else {
+                    lines.push(format!("{}enum {} {{", indent, name));
+                    self.print_symbols(body, depth + 1, lines)?;
+                    lines.push(format!("{}}}{}", indent, default));
+                }
+            }
+            RawField::Import(path) => lines.push(format!("{}import idl \"{}\";", indent, path)),
+            RawField::Comment(text, _) => lines.extend(Self::comment_lines(text, &indent)),
+            _ => return Err(AvroError::InvalidASTDataType(
+                "Only protocols, schemas, imports, records, enums and comments can be declared!"
+                    .to_string(),
+            )),
+        }
+        Ok(())
+    }
+
+    /// Print the symbols of an enum with their docstrings and the comments between them
+    fn print_symbols(
+        &self,
+        body: &[RawField],
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) -> Result<(), AvroError> {
+        let indent = self.indentation(depth);
+        let mut remaining = body
+            .iter()
+            .filter(|item| matches!(item, RawField::Symbol(..)))
+            .count();
+        for item in body.iter() {
+            match item {
+                RawField::Comment(text, true) => Self::append_trailing_comment(text, lines),
+                RawField::Comment(text, _) => lines.extend(Self::comment_lines(text, &indent)),
+                RawField::Symbol(symbol, docstring) => {
+                    remaining -= 1;
+                    let separator = if remaining > 0 { "," } else { "" };
+                    lines.extend(Self::docstring_lines(docstring, &indent));
+                    lines.push(format!("{}{}{}", indent, symbol, separator));
+                }
+                _ => {
+                    return Err(AvroError::InvalidASTDataType(
+                        "Enums can only contain symbols and comments!".to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the fields of a record, with the defaults of all fields aligned
+    fn print_fields(
+        &self,
+        fields: &[RawField],
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) -> Result<(), AvroError> {
+        let indent = self.indentation(depth);
+        let declarations = fields
+            .iter()
+            .filter(|field| !matches!(field, RawField::Comment(..)))
+            .map(Self::field_declaration)
+            .collect::<Result<Vec<(String, Option<String>)>, AvroError>>()?;
+        let width = declarations
+            .iter()
+            .filter(|(_, default)| default.is_some())
+            .map(|(declaration, _)| declaration.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut declarations = declarations.into_iter();
+        for field in fields.iter() {
+            match field {
+                RawField::Comment(text, true) => Self::append_trailing_comment(text, lines),
+                RawField::Comment(text, _) => lines.extend(Self::comment_lines(text, &indent)),
+                _ => {
+                    let Some((declaration, default)) = declarations.next() else {
+                        continue;
+                    };
+                    lines.extend(Self::docstring_lines(&Self::docstring(field), &indent));
+                    match default {
+                        Some(default) => lines.push(format!(
+                            "{}{:<width$} = {};",
+                            indent,
+                            declaration,
+                            default,
+                            width = width
+                        )),
+                        None => lines.push(format!("{}{};", indent, declaration)),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the declaration of a field without its default and the default if it has one
+    fn field_declaration(field: &RawField) -> Result<(String, Option<String>), AvroError> {
+        let (name, props) = match field {
+            RawField::Int(name, _, _, props, _)
+            | RawField::Long(name, _, _, props, _)
+            | RawField::Float(name, _, _, props, _)
+            | RawField::Double(name, _, _, props, _)
+            | RawField::Boolean(name, _, _, props, _)
+            | RawField::String(name, _, _, props, _)
+            | RawField::Union(name, _, _, _, props)
+            | RawField::Array(name, _, _, _, props, _)
+            | RawField::Unresolved(name, _, _, props, _) => (name, props),
+            _ => {
+                return Err(AvroError::InvalidASTDataType(
+                    "Records can only contain fields and comments!".to_string(),
+                ))
+            }
+        };
+        let name = Self::required_name(name, "Field")?;
+
+        let (type_name, default) = match field {
+            RawField::Int(_, default, ..) => ("int".to_string(), Self::default(default)),
+            RawField::Long(_, default, ..) => ("long".to_string(), Self::default(default)),
+            RawField::Float(_, default, ..) => ("float".to_string(), Self::float_default(default)),
+            RawField::Double(_, default, ..) => {
+                ("double".to_string(), Self::float_default(default))
+            }
+            RawField::Boolean(_, default, ..) => ("boolean".to_string(), Self::default(default)),
+            RawField::String(_, default, ..) => (
+                "string".to_string(),
+                Self::default(&default.clone().map(|value| format!("\"{}\"", value))),
+            ),
+            RawField::Union(_, types, default, ..) => {
+                let default = Self::default(&default.clone().map(Self::literal));
+                match types.as_slice() {
+                    // Nullable shorthand e.g. `int? a`, references can't have a default
+                    [inner, RawField::Null]
+                        if Self::is_primitive(inner)
+                            || (matches!(inner, RawField::Unresolved(..)) && default.is_none()) =>
+                    {
+                        (format!("{}?", Self::type_name(inner)?), default)
+                    }
+                    _ => (Self::type_name(field)?, default),
+                }
+            }
+            _ => (Self::type_name(field)?, None),
+        };
+
+        // Annotations of the type go before it, for `T?` those of `T`
+        let type_props = match field {
+            RawField::Union(_, types, ..) => match types.as_slice() {
+                [inner, RawField::Null] if Self::is_primitive(inner) => inner.type_properties(),
+                _ => None,
+            },
+            field => field.type_properties(),
+        };
+        let mut parts = type_props.map(Self::annotations).unwrap_or_default();
+        parts.push(type_name);
+        parts.extend(Self::annotations(props));
+        parts.push(name.to_string());
+        Ok((parts.join(" "), default))
+    }
+
+    /// Name of a type as it is written in a field, array or union
+    fn type_name(field: &RawField) -> Result<String, AvroError> {
+        match field {
+            RawField::Int(..) => Ok("int".to_string()),
+            RawField::Long(..) => Ok("long".to_string()),
+            RawField::Float(..) => Ok("float".to_string()),
+            RawField::Double(..) => Ok("double".to_string()),
+            RawField::Boolean(..) => Ok("boolean".to_string()),
+            RawField::String(..) => Ok("string".to_string()),
+            RawField::Null => Ok("null".to_string()),
+            RawField::Unresolved(_, type_, ..) => Ok(type_.clone()),
+            RawField::Array(_, inner, ..) => {
+                Ok(format!("array<{}>", Self::nested_type_name(inner)?))
+            }
+            RawField::Union(_, types, ..) => Ok(format!(
+                "union {{ {} }}",
+                types
+                    .iter()
+                    .map(Self::nested_type_name)
+                    .collect::<Result<Vec<String>, AvroError>>()?
+                    .join(", ")
+            )),
+            _ => Err(AvroError::InvalidASTDataType(
+                "Only primitives, arrays, unions and references can be used as types!".to_string(),
+            )),
+        }
+    }
+
+    /// Name of a type inside an array or union, where IDL has no place for its annotations
+    fn nested_type_name(field: &RawField) -> Result<String, AvroError> {
+        if field
+            .type_properties()
+            .is_some_and(|props| !props.is_empty())
+        {
+            return Err(AvroError::InvalidASTDataType(
+                "Annotations of types in arrays and unions can't be written in IDL!".to_string(),
+            ));
+        }
+        Self::type_name(field)
+    }
+
+    fn is_primitive(field: &RawField) -> bool {
+        matches!(
+            field,
+            RawField::Int(..)
+                | RawField::Long(..)
+                | RawField::Float(..)
+                | RawField::Double(..)
+                | RawField::Boolean(..)
+                | RawField::String(..)
+        )
+    }
+
+    fn docstring(field: &RawField) -> Option<String> {
+        match field {
+            RawField::Int(_, _, docstring, ..)
+            | RawField::Long(_, _, docstring, ..)
+            | RawField::Float(_, _, docstring, ..)
+            | RawField::Double(_, _, docstring, ..)
+            | RawField::Boolean(_, _, docstring, ..)
+            | RawField::String(_, _, docstring, ..)
+            | RawField::Union(_, _, _, docstring, _)
+            | RawField::Array(_, _, _, docstring, ..)
+            | RawField::Unresolved(_, _, docstring, ..) => docstring.clone(),
+            _ => None,
+        }
+    }
+
+    fn default<T: ToString>(default: &HasDefault<T>) -> Option<String> {
+        match default {
+            HasDefault::Default(Some(value)) => Some(value.to_string()),
+            HasDefault::Default(None) => Some("null".to_string()),
+            HasDefault::None => None,
+        }
+    }
+
+    /// Floats are printed with a fraction, since the parser requires one
+    fn float_default<T: std::fmt::Debug>(default: &HasDefault<T>) -> Option<String> {
+        match default {
+            HasDefault::Default(Some(value)) => Some(format!("{:?}", value)),
+            HasDefault::Default(None) => Some("null".to_string()),
+            HasDefault::None => None,
+        }
+    }
+
+    fn literal(literal: Literal) -> String {
+        match literal {
+            Literal::Int(value) => value.to_string(),
+            Literal::Long(value) => value.to_string(),
+            Literal::Float(value) => format!("{:?}", value),
+            Literal::Double(value) => format!("{:?}", value),
+            Literal::Boolean(value) => value.to_string(),
+            Literal::String(value) => format!("\"{}\"", value),
+            Literal::Null => "null".to_string(),
+        }
+    }
+
+    fn annotations(props: &Properties) -> Vec<String> {
+        props
+            .iter()
+            .map(|(name, value)| format!("@{}({})", name, value))
+            .collect()
+    }
+
+    /// Annotations of a named type on their own lines, starting with the namespace
+    fn annotation_lines(
+        namespace: &Option<String>,
+        props: &Properties,
+        indent: &str,
+    ) -> Vec<String> {
+        let namespace = namespace
+            .iter()
+            .map(|namespace| format!("@namespace(\"{}\")", namespace));
+        namespace
+            .chain(Self::annotations(props))
+            .map(|annotation| format!("{}{}", indent, annotation))
+            .collect()
+    }
+
+    fn docstring_lines(docstring: &Option<String>, indent: &str) -> Vec<String> {
+        let Some(docstring) = docstring else {
+            return vec![];
+        };
+        // The end of a comment is escaped, the parser turns it back into `*/`
+        let docstring = docstring.replace("*/", "*\\/");
+        if !docstring.contains('\n') {
+            return vec![format!("{}/** {} */", indent, docstring)];
+        }
+        std::iter::once(format!("{}/**", indent))
+            .chain(docstring.lines().map(|line| {
+                if line.is_empty() {
+                    format!("{} *", indent)
+                } else {
+                    format!("{} * {}", indent, line)
+                }
+            }))
+            .chain(std::iter::once(format!("{} */", indent)))
+            .collect()
+    }
+
+    /// Lines of a comment, where the continuation lines of block comments are re-indented
+    fn comment_lines(text: &str, indent: &str) -> Vec<String> {
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let line = line.trim();
+                match (i, line.starts_with('*')) {
+                    (0, _) => format!("{}{}", indent, line),
+                    (_, true) => format!("{} {}", indent, line),
+                    (_, false) => format!("{}   {}", indent, line),
+                }
+            })
+            .collect()
+    }
+
+    /// Append a comment to the last line which isn't blank
+    fn append_trailing_comment(text: &str, lines: &mut Vec<String>) {
+        let blank = lines.last().is_some_and(|line| line.is_empty());
+        if blank {
+            lines.pop();
+        }
+        match lines.last_mut() {
+            Some(last) => {
+                last.push(' ');
+                last.push_str(text);
+            }
+            None => lines.push(text.to_string()),
+        }
+        if blank {
+            lines.push(String::new());
+        }
+    }
+
+    /// Sort runs of consecutive imports if requested
+    fn sorted_imports(&self, declarations: &[RawField]) -> Vec<RawField> {
+        let mut declarations = declarations.to_vec();
+        if !self.sort_imports {
+            return declarations;
+        }
+        for run in declarations
+            .chunk_by_mut(|a, b| matches!((a, b), (RawField::Import(_), RawField::Import(_))))
+        {
+            run.sort_by_key(|declaration| match declaration {
+                RawField::Import(path) => path.clone(),
+                _ => String::new(),
+            });
+        }
+        declarations
+    }
+
+    fn required_name<'a>(name: &'a Option<String>, kind: &str) -> Result<&'a str, AvroError> {
+        name.as_deref()
+            .ok_or_else(|| AvroError::MissingName(format!("{} is missing a name!", kind)))
+    }
+
+    fn indentation(&self, depth: usize) -> String {
+        " ".repeat(self.indent * depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::AvroIdlLexer;
+
+    use super::IdlPrinter;
+
+    fn format(src: &str) -> String {
+        let fields = AvroIdlLexer::new("none".to_string())
+            .parse_syntax(src.to_string())
+            .unwrap();
+        IdlPrinter::new(fields).print().unwrap()
+    }
+
+    #[test]
+    fn test_print_protocol() {
+        let src = "// Header
+/** Events */
+@namespace(\"org.example\") @version(\"1\")
+protocol  Event {
+import idl \"b.avdl\";   import idl \"a.avdl\";
+    /** A person
+        with a name */
+  record Person {   string   name = \"Bob\"; // The name
+  int? age;
+     @pii(true) long income = 10;
+  array<string> tags;
+  Meal meal;
+  union { null, double } rate = null;
+  // Unused
+  float ratio = 0.5; }
+enum Meal { Dinner, Lunch, } = Dinner;
+}
+";
+        let expected = "// Header
+/** Events */
+@namespace(\"org.example\")
+@version(\"1\")
+protocol Event {
+  import idl \"b.avdl\";
+  import idl \"a.avdl\";
+
+  /**
+   * A person
+   * with a name
+   */
+  record Person {
+    string name                 = \"Bob\"; // The name
+    int? age;
+    @pii(true) long income      = 10;
+    array<string> tags;
+    Meal meal;
+    union { null, double } rate = null;
+    // Unused
+    float ratio                 = 0.5;
+  }
+
+  enum Meal {
+    Dinner,
+    Lunch
+  } = Dinner;
+}
+";
+        let formatted = format(src);
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted), formatted);
+    }
+
+    #[test]
+    fn test_print_enum_symbol_docstrings_and_comments() {
+        let src = "protocol Event {
+  enum Meal {   /** Evening */ Dinner, // Late
+  // Noon
+  Lunch, } = Lunch;
+}";
+        let expected = "protocol Event {
+  enum Meal {
+    /** Evening */
+    Dinner, // Late
+    // Noon
+    Lunch
+  } = Lunch;
+}
+";
+        let formatted = format(src);
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted), formatted);
+    }
+
+    #[test]
+    fn test_print_schema_with_sorted_imports() {
+        let src = "namespace org.example;
+schema   array<Person>;
+import idl \"b.avdl\";
+import idl \"a.avdl\";
+record Person { string name; }";
+        let fields = AvroIdlLexer::new("none".to_string())
+            .parse_syntax(src.to_string())
+            .unwrap();
+        let formatted = IdlPrinter::new(fields)
+            .with_indent(4)
+            .with_sorted_imports(true)
+            .print()
+            .unwrap();
+        let expected = "namespace org.example;
+schema array<Person>;
+
+import idl \"a.avdl\";
+import idl \"b.avdl\";
+
+record Person {
+    string name;
+}
+";
+        assert_eq!(formatted, expected);
+    }
+}
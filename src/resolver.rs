@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use crate::error::AvroError;
+
+/// Resolves imports of IDL files to the source of the imported files
+pub trait ImportResolver {
+    /// Find the file an import refers to, relative to the importing file. The returned
+    /// path identifies the file in error messages and is used to resolve its own imports.
+    fn find(&self, import: &str, importing: &Path) -> Option<PathBuf>;
+
+    /// Read the source of a file found by `find`
+    fn read(&self, path: &Path) -> Result<String, AvroError>;
+}
+
+impl<R: ImportResolver + ?Sized> ImportResolver for Rc<R> {
+    fn find(&self, import: &str, importing: &Path) -> Option<PathBuf> {
+        (**self).find(import, importing)
+    }
+
+    fn read(&self, path: &Path) -> Result<String, AvroError> {
+        (**self).read(path)
+    }
+}
+
+/// Resolves imports relative to the importing file on disk or else in one of the import
+/// paths
+#[derive(Default)]
+pub struct FileSystemResolver {
+    import_paths: Vec<PathBuf>,
+}
+
+impl FileSystemResolver {
+    pub fn new(import_paths: Vec<PathBuf>) -> Self {
+        Self { import_paths }
+    }
+}
+
+impl ImportResolver for FileSystemResolver {
+    fn find(&self, import: &str, importing: &Path) -> Option<PathBuf> {
+        // Remove file name from path
+        let parent = importing.parent().unwrap_or(Path::new(""));
+        std::iter::once(parent)
+            .chain(self.import_paths.iter().map(|p| p.as_path()))
+            .map(|folder| folder.join(import))
+            .find(|candidate| candidate.is_file())
+    }
+
+    fn read(&self, path: &Path) -> Result<String, AvroError> {
+        read_to_string(path)
+            .map_err(|err| AvroError::FailedImport(format!("{}: {}", path.display(), err)))
+    }
+}
+
+/// Resolves imports from files held in memory, e.g. for tests or schemas stored in a
+/// database. Imports are relative to the importing file or else to the root.
+#[derive(Default)]
+pub struct InMemoryResolver {
+    files: HashMap<PathBuf, String>,
+}
+
+impl InMemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file with the given path and source
+    pub fn with_file(mut self, path: &str, src: &str) -> Self {
+        self.files
+            .insert(normalize(Path::new(path)), src.to_string());
+        self
+    }
+}
+
+impl ImportResolver for InMemoryResolver {
+    fn find(&self, import: &str, importing: &Path) -> Option<PathBuf> {
+        let parent = importing.parent().unwrap_or(Path::new(""));
+        [parent.join(import), PathBuf::from(import)]
+            .iter()
+            .map(|candidate| normalize(candidate))
+            .find(|candidate| self.files.contains_key(candidate))
+    }
+
+    fn read(&self, path: &Path) -> Result<String, AvroError> {
+        self.files.get(&normalize(path)).cloned().ok_or_else(|| {
+            AvroError::FailedImport(format!("{}: File doesn't exist", path.display()))
+        })
+    }
+}
+
+/// Tries several resolvers in order, e.g. in-memory fixtures on top of the file system
+#[derive(Default)]
+pub struct LayeredResolver {
+    resolvers: Vec<Box<dyn ImportResolver>>,
+}
+
+impl LayeredResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a resolver, which is tried after the ones added before it
+    pub fn with_resolver(mut self, resolver: impl ImportResolver + 'static) -> Self {
+        self.resolvers.push(Box::new(resolver));
+        self
+    }
+}
+
+impl ImportResolver for LayeredResolver {
+    fn find(&self, import: &str, importing: &Path) -> Option<PathBuf> {
+        self.resolvers
+            .iter()
+            .find_map(|resolver| resolver.find(import, importing))
+    }
+
+    fn read(&self, path: &Path) -> Result<String, AvroError> {
+        let mut error = AvroError::FailedImport(format!("{}: File doesn't exist", path.display()));
+        for resolver in self.resolvers.iter() {
+            match resolver.read(path) {
+                Ok(src) => return Ok(src),
+                Err(err) => error = err,
+            }
+        }
+        Err(error)
+    }
+}
+
+/// Remove `.` and `..` from a path without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    #[test]
+    fn test_in_memory_resolver() {
+        let resolver = InMemoryResolver::new()
+            .with_file("common/date.avdl", "protocol Date {}")
+            .with_file("types.avdl", "protocol Types {}");
+
+        let importing = Path::new("events/event.avdl");
+        assert_eq!(
+            resolver.find("../common/date.avdl", importing),
+            Some(PathBuf::from("common/date.avdl"))
+        );
+        // Falls back to the root
+        assert_eq!(
+            resolver.find("types.avdl", importing),
+            Some(PathBuf::from("types.avdl"))
+        );
+        assert_eq!(resolver.find("missing.avdl", importing), None);
+        assert_eq!(
+            resolver.read(Path::new("./types.avdl")).unwrap(),
+            "protocol Types {}"
+        );
+    }
+
+    #[test]
+    fn test_layered_resolver() {
+        let resolver = LayeredResolver::new()
+            .with_resolver(InMemoryResolver::new().with_file("child/ts.avdl", "protocol Fake {}"))
+            .with_resolver(FileSystemResolver::new(vec![PathBuf::from("examples")]));
+
+        let importing = Path::new("<memory>");
+        let path = resolver.find("child/ts.avdl", importing).unwrap();
+        assert_eq!(resolver.read(&path).unwrap(), "protocol Fake {}");
+
+        let path = resolver.find("simple.avdl", importing).unwrap();
+        assert_eq!(path, PathBuf::from("examples/simple.avdl"));
+        assert!(resolver.read(&path).unwrap().contains("protocol Event"));
+    }
+}
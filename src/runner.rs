@@ -1,47 +1,958 @@
-use std::fs::File;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
-use crate::lexer::AvroIdlLexer;
+use crate::ast::{Field, RawField};
+use crate::codegen::{
+    ProtobufGenerator, PythonGenerator, RustGenerator, SqlDialect, SqlGenerator,
+    TypeScriptGenerator,
+};
+use crate::compat::{CompatChecker, CompatMode, Incompatibility};
+use crate::deserializer::JsonDeserializer;
+use crate::diagnostics::Diagnostic;
+use crate::diagram::{DiagramFormat, DiagramGenerator};
+use crate::diff::{Change, SchemaDiff};
+use crate::docgen::{DocFormat, DocGenerator};
+use crate::error::AvroError;
+use crate::json_schema::JsonSchemaSerializer;
+use crate::lexer::{AvroIdlLexer, ImportCache};
 use crate::linker::LinkParser;
-use crate::serializer::AvprSerializer;
+use crate::printer::IdlPrinter;
+use crate::serializer::{canonical_form, AvprSerializer, AvscSerializer};
 use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::Value;
 
 #[derive(Clone, ValueEnum)]
 pub enum OutputFormat {
     AVPR,
-    // AVSC, TODO: Implement avsc serializer
+    AVSC,
+    Rust,
+    Python,
+    #[value(name = "typescript")]
+    TypeScript,
+    JsonSchema,
+    Protobuf,
+    Sql,
+    Dot,
+    Mermaid,
 }
 
+impl OutputFormat {
+    /// File extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::AVPR => "avpr",
+            OutputFormat::AVSC => "avsc",
+            OutputFormat::Rust => "rs",
+            OutputFormat::Python => "py",
+            OutputFormat::TypeScript => "d.ts",
+            OutputFormat::JsonSchema => "schema.json",
+            OutputFormat::Protobuf => "proto",
+            OutputFormat::Sql => "sql",
+            OutputFormat::Dot => "dot",
+            OutputFormat::Mermaid => "mmd",
+        }
+    }
+}
+
+/// Serialized schemas or generated source code
+enum Output {
+    Json(Vec<Value>),
+    Text(String),
+}
+
+/// Path which refers to stdin when used as input and to stdout when used as output
+pub const STDIO_PATH: &str = "-";
+
 pub struct AvroIdlParser {
     path: String,
-    format: OutputFormat,
+    format: Option<OutputFormat>,
     output_path: String,
+    import_paths: Vec<String>,
+    import_cache: ImportCache,
+    indent: Option<usize>,
+    canonical: bool,
+    pydantic: bool,
+    dialect: SqlDialect,
 }
 
 impl AvroIdlParser {
-    pub fn new(path: String, output_path: String, format: OutputFormat) -> Self {
+    /// Create a new runner. If no format is given, protocols are emitted as avpr and
+    /// schema documents as avsc.
+    pub fn new(path: String, output_path: String, format: Option<OutputFormat>) -> Self {
         Self {
             path,
             format,
             output_path,
+            import_paths: vec![],
+            import_cache: ImportCache::default(),
+            indent: None,
+            canonical: false,
+            pydantic: false,
+            dialect: SqlDialect::Postgres,
         }
     }
 
-    pub fn parse(&self) {
-        let lexer = AvroIdlLexer::new(self.path.clone());
-        let linker = LinkParser::new();
+    /// Pretty print the output with the given number of spaces as indentation
+    pub fn with_pretty(mut self, indent: usize) -> Self {
+        self.indent = Some(indent);
+        self
+    }
+
+    /// Emit the Parsing Canonical Form of each schema instead, one schema per line. For
+    /// protocols every named type is emitted, for schema documents the main schema. Source
+    /// code and JSON Schema output aren't affected.
+    pub fn with_canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
 
-        let parsed_ast = lexer.parse().unwrap();
-        let linked_ast = linker.parse(parsed_ast).unwrap();
+    /// Generate pydantic models instead of dataclasses for Python
+    pub fn with_pydantic(mut self, pydantic: bool) -> Self {
+        self.pydantic = pydantic;
+        self
+    }
+
+    /// Dialect of the SQL output
+    pub fn with_dialect(mut self, dialect: SqlDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Set the directories searched for imports. Imports of IDL read from stdin are
+    /// resolved relative to the working directory and then to these directories.
+    pub fn with_import_paths(mut self, import_paths: Vec<String>) -> Self {
+        self.import_paths = import_paths;
+        self
+    }
+
+    /// Compile the file and write the output
+    pub fn parse(&self) -> Result<(), AvroError> {
+        let (_, content) = self.compile(&self.path)?;
+        self.write(&self.output_path, &content)
+    }
+
+    /// Compile the file whenever it or one of the files it imports changes, polling their
+    /// modification times at the given interval. Diagnostics are printed to stderr and
+    /// don't stop watching.
+    pub fn watch(&self, interval: Duration) -> ! {
+        let mut watched = vec![PathBuf::from(&self.path)];
+        loop {
+            // Read the times before compiling, so files changed while compiling are compiled
+            // again
+            let started = SystemTime::now();
+            let before = modification_times(&watched);
+
+            // Parse with a fresh lexer, so changed imports aren't taken from a cache
+            let lexer =
+                AvroIdlLexer::new(self.path.clone()).with_import_paths(self.import_paths.clone());
+            let result = self
+                .compile_with(&lexer, &self.path)
+                .and_then(|(_, content)| self.write(&self.output_path, &content));
+            match result {
+                Ok(()) => eprintln!("Compiled {} to {}", self.path, self.output_path),
+                Err(err) => {
+                    for diagnostic in Diagnostic::from_error(&self.path, &err) {
+                        eprintln!("{}", diagnostic);
+                    }
+                }
+            }
+
+            let mut files = vec![PathBuf::from(&self.path)];
+            files.extend(lexer.imported_files());
+            let modified: Vec<Option<SystemTime>> = files
+                .iter()
+                .zip(modification_times(&files))
+                .map(
+                    |(path, time)| match watched.iter().position(|w| w == path) {
+                        Some(index) => before[index],
+                        // New imports changed while compiling if written after it started
+                        None => time.filter(|time| *time < started),
+                    },
+                )
+                .collect();
+            watched = files;
+            while modification_times(&watched) == modified {
+                thread::sleep(interval);
+            }
+        }
+    }
+
+    /// Parse, link and serialize a file. Returns the format used and the serialized values.
+    fn compile(&self, path: &str) -> Result<(OutputFormat, Output), AvroError> {
+        if path == STDIO_PATH {
+            self.compile_with(&self.lexer("<stdin>"), path)
+        } else {
+            self.compile_with(&self.lexer(path), path)
+        }
+    }
 
-        let content = match self.format {
-            OutputFormat::AVPR => {
+    fn compile_with(
+        &self,
+        lexer: &AvroIdlLexer,
+        path: &str,
+    ) -> Result<(OutputFormat, Output), AvroError> {
+        let linked_ast = self.link_with(lexer, path)?;
+
+        let format = self.format.clone().unwrap_or(match linked_ast {
+            Field::Schema(..) => OutputFormat::AVSC,
+            _ => OutputFormat::AVPR,
+        });
+
+        let content = match (&format, self.canonical) {
+            (OutputFormat::AVPR, false) => {
                 let serializer = AvprSerializer::new(linked_ast);
-                serializer.serialize().unwrap()
-            } //OutputFormat::AVSC => panic!("Not supported AVSC"),
+                Output::Json(vec![serializer.serialize()?])
+            }
+            (OutputFormat::AVSC, false) => {
+                let serializer = AvscSerializer::new(linked_ast);
+                Output::Json(vec![serializer.serialize()?])
+            }
+            (OutputFormat::AVPR, true) => {
+                let serializer = AvscSerializer::new(linked_ast);
+                Output::Json(
+                    serializer
+                        .serialize_named_types()?
+                        .into_iter()
+                        .map(canonical_form)
+                        .collect(),
+                )
+            }
+            (OutputFormat::AVSC, true) => {
+                let serializer = AvscSerializer::new(linked_ast);
+                Output::Json(vec![canonical_form(serializer.serialize()?)])
+            }
+            (OutputFormat::Rust, _) => Output::Text(RustGenerator::new(linked_ast).generate()?),
+            (OutputFormat::Python, _) => Output::Text(
+                PythonGenerator::new(linked_ast)
+                    .with_pydantic(self.pydantic)
+                    .generate()?,
+            ),
+            (OutputFormat::TypeScript, _) => {
+                Output::Text(TypeScriptGenerator::new(linked_ast).generate()?)
+            }
+            (OutputFormat::JsonSchema, _) => {
+                Output::Json(vec![JsonSchemaSerializer::new(linked_ast).serialize()?])
+            }
+            (OutputFormat::Protobuf, _) => {
+                Output::Text(ProtobufGenerator::new(linked_ast).generate()?)
+            }
+            (OutputFormat::Sql, _) => Output::Text(
+                SqlGenerator::new(linked_ast)
+                    .with_dialect(self.dialect)
+                    .generate()?,
+            ),
+            (OutputFormat::Dot, _) => Output::Text(
+                DiagramGenerator::new(linked_ast)
+                    .with_clusters(lexer.imported_types())
+                    .generate()?,
+            ),
+            (OutputFormat::Mermaid, _) => Output::Text(
+                DiagramGenerator::new(linked_ast)
+                    .with_format(DiagramFormat::Mermaid)
+                    .with_clusters(lexer.imported_types())
+                    .generate()?,
+            ),
+        };
+        Ok((format, content))
+    }
+
+    /// Parse and link a file without serializing it
+    fn link(&self, path: &str) -> Result<Field, AvroError> {
+        self.link_with(&self.lexer(path), path)
+    }
+
+    fn link_with(&self, lexer: &AvroIdlLexer, path: &str) -> Result<Field, AvroError> {
+        let parsed_ast = if path == STDIO_PATH {
+            let mut src = String::new();
+            io::stdin()
+                .read_to_string(&mut src)
+                .map_err(|err| AvroError::FailedReading(format!("<stdin>: {}", err)))?;
+            lexer.parse_source(src)?
+        } else {
+            lexer.parse()?
+        };
+        LinkParser::new().parse(parsed_ast)
+    }
+
+    fn lexer(&self, path: &str) -> AvroIdlLexer {
+        AvroIdlLexer::new(path.to_string())
+            .with_import_paths(self.import_paths.clone())
+            .with_import_cache(self.import_cache.clone())
+    }
+
+    /// Write the serialized values or source code to a file or to stdout
+    fn write(&self, output_path: &str, content: &Output) -> Result<(), AvroError> {
+        let failed_writing =
+            |err: io::Error| AvroError::FailedWriting(format!("{}: {}", output_path, err));
+        let mut writer: Box<dyn Write> = if output_path == STDIO_PATH {
+            Box::new(io::stdout())
+        } else {
+            Box::new(File::create(output_path).map_err(failed_writing)?)
         };
 
-        let file = File::create(self.output_path.clone()).unwrap();
+        match content {
+            Output::Json(values) => self.write_json(&mut writer, values),
+            Output::Text(text) => writer.write_all(text.as_bytes()),
+        }
+        .map_err(failed_writing)
+    }
+
+    /// Write each value, pretty printed if requested, on its own line
+    fn write_json(&self, writer: &mut impl Write, content: &[Value]) -> io::Result<()> {
+        for value in content.iter() {
+            match self.indent {
+                Some(indent) if !self.canonical => {
+                    let indent = " ".repeat(indent);
+                    let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+                    let mut serializer =
+                        serde_json::Serializer::with_formatter(&mut *writer, formatter);
+                    value.serialize(&mut serializer)?;
+                }
+                _ => serde_json::to_writer(&mut *writer, value)?,
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compiles every `.avdl` file of directories, or matching globs, into an output directory
+/// which mirrors the layout of the inputs. Imports shared by several files are parsed once
+/// and a failing file doesn't stop the remaining files from compiling.
+pub struct AvroIdlBatchParser {
+    inputs: Vec<String>,
+    output_root: PathBuf,
+    runner: AvroIdlParser,
+}
+
+impl AvroIdlBatchParser {
+    /// Create a new batch runner. If no format is given, protocols are emitted as avpr and
+    /// schema documents as avsc.
+    pub fn new(inputs: Vec<String>, output_root: String, format: Option<OutputFormat>) -> Self {
+        Self {
+            inputs,
+            output_root: PathBuf::from(output_root),
+            runner: AvroIdlParser::new(String::new(), String::new(), format),
+        }
+    }
+
+    /// Pretty print the output with the given number of spaces as indentation
+    pub fn with_pretty(mut self, indent: usize) -> Self {
+        self.runner = self.runner.with_pretty(indent);
+        self
+    }
+
+    /// Emit the Parsing Canonical Form of each schema instead
+    pub fn with_canonical(mut self, canonical: bool) -> Self {
+        self.runner = self.runner.with_canonical(canonical);
+        self
+    }
+
+    /// Generate pydantic models instead of dataclasses for Python
+    pub fn with_pydantic(mut self, pydantic: bool) -> Self {
+        self.runner = self.runner.with_pydantic(pydantic);
+        self
+    }
+
+    /// Dialect of the SQL output
+    pub fn with_dialect(mut self, dialect: SqlDialect) -> Self {
+        self.runner = self.runner.with_dialect(dialect);
+        self
+    }
+
+    /// Set the directories searched for imports
+    pub fn with_import_paths(mut self, import_paths: Vec<String>) -> Self {
+        self.runner = self.runner.with_import_paths(import_paths);
+        self
+    }
+
+    /// Compile all files. Returns the number of compiled files, or the errors of every
+    /// input and file which failed. Files which would be written to the same output path
+    /// are reported and none of them is written.
+    pub fn parse(&self) -> Result<usize, Vec<AvroError>> {
+        let mut errors = vec![];
+        let mut files: Vec<(PathBuf, PathBuf)> = vec![];
+        for input in self.inputs.iter() {
+            match find_files(input) {
+                Ok(found) => files.extend(found),
+                Err(err) => errors.push(err),
+            }
+        }
+        // Inputs which overlap, e.g. a directory and a glob, find the same files
+        let mut seen = HashSet::new();
+        files.retain(|(path, _)| seen.insert(path.canonicalize().unwrap_or(path.clone())));
+
+        let mut outputs = vec![];
+        for (path, relative_path) in files.into_iter() {
+            match self.compile_file(&path, &relative_path) {
+                Ok((output_path, content)) => outputs.push((path, output_path, content)),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        let mut compiled = 0;
+        for (index, (_, output_path, content)) in outputs.iter().enumerate() {
+            let sources: Vec<String> = outputs
+                .iter()
+                .filter(|(_, other, _)| other == output_path)
+                .map(|(path, ..)| path.display().to_string())
+                .collect();
+            if sources.len() > 1 {
+                // Reported once, for the first of the files
+                if outputs
+                    .iter()
+                    .position(|(_, other, _)| other == output_path)
+                    == Some(index)
+                {
+                    errors.push(AvroError::FailedWriting(format!(
+                        "{}: would be written by each of {}",
+                        output_path.display(),
+                        sources.join(", ")
+                    )));
+                }
+                continue;
+            }
+            match self.write_file(output_path, content) {
+                Ok(()) => compiled += 1,
+                Err(err) => errors.push(err),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(compiled)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Compile a file. Returns the path of its output and the serialized values.
+    fn compile_file(
+        &self,
+        path: &Path,
+        relative_path: &Path,
+    ) -> Result<(PathBuf, Output), AvroError> {
+        let (format, content) = self.runner.compile(&path.to_string_lossy())?;
+        let output_path = self
+            .output_root
+            .join(relative_path)
+            .with_extension(format.extension());
+        Ok((output_path, content))
+    }
+
+    fn write_file(&self, output_path: &Path, content: &Output) -> Result<(), AvroError> {
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                AvroError::FailedWriting(format!("{}: {}", parent.display(), err))
+            })?;
+        }
+        self.runner.write(&output_path.to_string_lossy(), content)
+    }
+}
+
+/// Renders reference documentation of every `.avdl` file of directories or globs into an
+/// output directory, with a page per file mirroring the layout of the inputs and an index
+pub struct AvroIdlDocumenter {
+    inputs: Vec<String>,
+    output_root: PathBuf,
+    format: DocFormat,
+    runner: AvroIdlParser,
+}
+
+impl AvroIdlDocumenter {
+    pub fn new(inputs: Vec<String>, output_root: String) -> Self {
+        Self {
+            inputs,
+            output_root: PathBuf::from(output_root),
+            format: DocFormat::Markdown,
+            runner: AvroIdlParser::new(String::new(), String::new(), None),
+        }
+    }
+
+    /// Format of the pages, Markdown by default
+    pub fn with_format(mut self, format: DocFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the directories searched for imports
+    pub fn with_import_paths(mut self, import_paths: Vec<String>) -> Self {
+        self.runner = self.runner.with_import_paths(import_paths);
+        self
+    }
+
+    /// Render the documentation. Returns the number of documented files, or the errors of
+    /// every input and file which failed, in which case nothing is written.
+    pub fn generate(&self) -> Result<usize, Vec<AvroError>> {
+        let mut errors = vec![];
+        let mut generator = DocGenerator::new().with_format(self.format);
+        let mut documented = 0;
+        for input in self.inputs.iter() {
+            let files = match find_files(input) {
+                Ok(files) => files,
+                Err(err) => {
+                    errors.push(err);
+                    continue;
+                }
+            };
+            for (path, relative_path) in files.into_iter() {
+                let added = self
+                    .runner
+                    .link(&path.to_string_lossy())
+                    .and_then(|field| generator.add(&relative_path, field));
+                match added {
+                    Ok(()) => documented += 1,
+                    Err(err) => errors.push(err),
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        for (relative_path, content) in generator.generate() {
+            let path = self.output_root.join(relative_path);
+            let failed_writing =
+                |err: io::Error| AvroError::FailedWriting(format!("{}: {}", path.display(), err));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|err| vec![failed_writing(err)])?;
+            }
+            fs::write(&path, content).map_err(|err| vec![failed_writing(err)])?;
+        }
+        Ok(documented)
+    }
+}
+
+/// Checks whether two versions of an IDL file are compatible
+pub struct AvroIdlCompatChecker {
+    old_path: String,
+    new_path: String,
+    mode: CompatMode,
+    runner: AvroIdlParser,
+}
+
+impl AvroIdlCompatChecker {
+    pub fn new(old_path: String, new_path: String) -> Self {
+        Self {
+            old_path,
+            new_path,
+            mode: CompatMode::Backward,
+            runner: AvroIdlParser::new(String::new(), String::new(), None),
+        }
+    }
+
+    /// Kind of compatibility to check, backward by default
+    pub fn with_mode(mut self, mode: CompatMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the directories searched for imports
+    pub fn with_import_paths(mut self, import_paths: Vec<String>) -> Self {
+        self.runner = self.runner.with_import_paths(import_paths);
+        self
+    }
+
+    /// Link both versions and return the changes breaking compatibility
+    pub fn check(&self) -> Result<Vec<Incompatibility>, AvroError> {
+        let old = self.runner.link(&self.old_path)?;
+        let new = self.runner.link(&self.new_path)?;
+        CompatChecker::new(old, new).with_mode(self.mode).check()
+    }
+}
+
+/// Lists the structural differences between two versions of an IDL file
+pub struct AvroIdlDiffer {
+    old_path: String,
+    new_path: String,
+    runner: AvroIdlParser,
+}
+
+impl AvroIdlDiffer {
+    pub fn new(old_path: String, new_path: String) -> Self {
+        Self {
+            old_path,
+            new_path,
+            runner: AvroIdlParser::new(String::new(), String::new(), None),
+        }
+    }
+
+    /// Set the directories searched for imports
+    pub fn with_import_paths(mut self, import_paths: Vec<String>) -> Self {
+        self.runner = self.runner.with_import_paths(import_paths);
+        self
+    }
+
+    /// Link both versions and return the changes between them
+    pub fn diff(&self) -> Result<Vec<Change>, AvroError> {
+        let old = self.runner.link(&self.old_path)?;
+        let new = self.runner.link(&self.new_path)?;
+        SchemaDiff::new(old, new).diff()
+    }
+}
+
+/// Validates files by parsing, linking and serializing them without writing any output
+pub struct AvroIdlChecker {
+    inputs: Vec<String>,
+    runner: AvroIdlParser,
+}
+
+impl AvroIdlChecker {
+    /// Create a new checker. Inputs are files, directories, globs or '-' for stdin.
+    pub fn new(inputs: Vec<String>) -> Self {
+        Self {
+            inputs,
+            runner: AvroIdlParser::new(String::new(), String::new(), None),
+        }
+    }
+
+    /// Set the directories searched for imports
+    pub fn with_import_paths(mut self, import_paths: Vec<String>) -> Self {
+        self.runner = self.runner.with_import_paths(import_paths);
+        self
+    }
+
+    /// Check all files and return the diagnostics of the ones which failed
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        for input in self.inputs.iter() {
+            let files = if input == STDIO_PATH {
+                Ok(vec![(PathBuf::from(STDIO_PATH), PathBuf::new())])
+            } else {
+                find_files(input)
+            };
+            let files = match files {
+                Ok(files) => files,
+                Err(err) => {
+                    diagnostics.extend(Diagnostic::from_error(input, &err));
+                    continue;
+                }
+            };
+            for (path, _) in files.into_iter() {
+                let path = path.to_string_lossy();
+                if let Err(err) = self.runner.compile(&path) {
+                    let path = if path == STDIO_PATH { "<stdin>" } else { &path };
+                    diagnostics.extend(Diagnostic::from_error(path, &err));
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Formats IDL files in place, or only reports the files which aren't formatted
+pub struct AvroIdlFormatter {
+    inputs: Vec<String>,
+    indent: usize,
+    sort_imports: bool,
+    check: bool,
+}
+
+impl AvroIdlFormatter {
+    /// Create a new formatter. Inputs are files, directories, globs or '-' to format stdin
+    /// to stdout.
+    pub fn new(inputs: Vec<String>) -> Self {
+        Self {
+            inputs,
+            indent: 2,
+            sort_imports: false,
+            check: false,
+        }
+    }
+
+    /// Indent with the given number of spaces instead of two
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Sort consecutive imports by path instead of keeping their order
+    pub fn with_sorted_imports(mut self, sort_imports: bool) -> Self {
+        self.sort_imports = sort_imports;
+        self
+    }
+
+    /// Only report the files which aren't formatted instead of rewriting them
+    pub fn with_check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// Format all files. Returns the files which were reformatted, or would be when
+    /// checking, and the diagnostics of the files which couldn't be formatted.
+    pub fn format(&self) -> (Vec<String>, Vec<Diagnostic>) {
+        let mut changed = vec![];
+        let mut diagnostics = vec![];
+        for input in self.inputs.iter() {
+            let files = if input == STDIO_PATH {
+                Ok(vec![(PathBuf::from(STDIO_PATH), PathBuf::new())])
+            } else {
+                find_files(input)
+            };
+            let files = match files {
+                Ok(files) => files,
+                Err(err) => {
+                    diagnostics.extend(Diagnostic::from_error(input, &err));
+                    continue;
+                }
+            };
+            for (path, _) in files.into_iter() {
+                let path = path.to_string_lossy();
+                let path = if path == STDIO_PATH { "<stdin>" } else { &path };
+                match self.format_file(path) {
+                    Ok(true) => changed.push(path.to_string()),
+                    Ok(false) => {}
+                    Err(err) => diagnostics.extend(Diagnostic::from_error(path, &err)),
+                }
+            }
+        }
+        (changed, diagnostics)
+    }
+
+    /// Format a file, returns whether its formatting changed
+    fn format_file(&self, path: &str) -> Result<bool, AvroError> {
+        let mut src = String::new();
+        if path == "<stdin>" {
+            io::stdin()
+                .read_to_string(&mut src)
+                .map_err(|err| AvroError::FailedReading(format!("{}: {}", path, err)))?;
+        } else {
+            src = fs::read_to_string(path)
+                .map_err(|err| AvroError::FailedReading(format!("{}: {}", path, err)))?;
+        }
+
+        let formatted = self.format_source(path, src.clone())?;
+        let changed = formatted != src;
+        if self.check {
+            return Ok(changed);
+        }
+        let failed_writing =
+            |err: io::Error| AvroError::FailedWriting(format!("{}: {}", path, err));
+        if path == "<stdin>" {
+            io::stdout()
+                .write_all(formatted.as_bytes())
+                .map_err(failed_writing)?;
+        } else if changed {
+            fs::write(path, formatted).map_err(failed_writing)?;
+        }
+        Ok(changed)
+    }
+
+    /// Format IDL, the path is only used for error messages
+    pub fn format_source(&self, path: &str, src: String) -> Result<String, AvroError> {
+        let fields = AvroIdlLexer::new(path.to_string()).parse_syntax(src)?;
+        IdlPrinter::new(fields)
+            .with_indent(self.indent)
+            .with_sorted_imports(self.sort_imports)
+            .print()
+    }
+}
+
+/// Converts an avpr protocol or avsc schema into IDL
+pub struct AvroJsonConverter {
+    path: String,
+    output_path: String,
+    indent: usize,
+}
+
+impl AvroJsonConverter {
+    /// Create a new converter, where '-' refers to stdin or stdout
+    pub fn new(path: String, output_path: String) -> Self {
+        Self {
+            path,
+            output_path,
+            indent: 2,
+        }
+    }
+
+    /// Indent with the given number of spaces instead of two
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn convert(&self) -> Result<(), AvroError> {
+        let path = if self.path == STDIO_PATH {
+            "<stdin>"
+        } else {
+            &self.path
+        };
+        let mut src = String::new();
+        let read = if self.path == STDIO_PATH {
+            io::stdin().read_to_string(&mut src).map(|_| ())
+        } else {
+            fs::read_to_string(path).map(|content| src = content)
+        };
+        read.map_err(|err| AvroError::FailedReading(format!("{}: {}", path, err)))?;
+
+        let field = serde_json::from_str::<Value>(&src)
+            .map_err(|err| AvroError::InvalidJson(err.to_string()))
+            .and_then(|json| JsonDeserializer::new(json).deserialize())
+            .map_err(|err| AvroError::InvalidJson(format!("{}: {}", path, err)))?;
+        let idl = IdlPrinter::new(vec![RawField::from(field)])
+            .with_indent(self.indent)
+            .print()?;
+
+        let failed_writing =
+            |err: io::Error| AvroError::FailedWriting(format!("{}: {}", self.output_path, err));
+        if self.output_path == STDIO_PATH {
+            io::stdout()
+                .write_all(idl.as_bytes())
+                .map_err(failed_writing)
+        } else {
+            fs::write(&self.output_path, idl).map_err(failed_writing)
+        }
+    }
+}
+
+/// Find the `.avdl` files of an input together with their path relative to the input. The
+/// input is either a file, a directory which is searched recursively or a glob, in which
+/// case paths are relative to the part before the first wildcard.
+pub fn find_files(input: &str) -> Result<Vec<(PathBuf, PathBuf)>, AvroError> {
+    let input_path = Path::new(input);
+    if input_path.is_file() {
+        let file_name = PathBuf::from(input_path.file_name().unwrap_or_default());
+        return Ok(vec![(input_path.to_path_buf(), file_name)]);
+    }
+
+    let (root, mut files) = if input_path.is_dir() {
+        let mut files = vec![];
+        walk_directory(input_path, &mut files)
+            .map_err(|err| AvroError::FailedReading(format!("{}: {}", input, err)))?;
+        (input_path.to_path_buf(), files)
+    } else {
+        let root = input_path
+            .components()
+            .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+            .collect::<PathBuf>();
+        let paths = glob::glob(input)
+            .map_err(|err| AvroError::FailedReading(format!("{}: {}", input, err)))?;
+        let mut files = vec![];
+        for path in paths {
+            let path = path.map_err(|err| AvroError::FailedReading(err.to_string()))?;
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "avdl") {
+                files.push(path);
+            }
+        }
+        (root, files)
+    };
+
+    if files.is_empty() {
+        return Err(AvroError::FailedReading(format!(
+            "{}: No .avdl files found",
+            input
+        )));
+    }
+    files.sort();
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            let relative = file.strip_prefix(&root).unwrap_or(&file).to_path_buf();
+            (file, relative)
+        })
+        .collect())
+}
+
+/// Modification time of each path, or `None` for paths which can't be read
+fn modification_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+fn walk_directory(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk_directory(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "avdl") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_files() {
+        let files = find_files("examples").unwrap();
+        let relative = files
+            .iter()
+            .map(|(_, relative)| relative.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert!(relative.contains(&"child/ts.avdl".to_string()));
+
+        let globbed = find_files("examples/*/*.avdl").unwrap();
+        assert!(globbed.iter().any(
+            |(path, relative)| path == Path::new("examples/child/ts.avdl")
+                && relative == Path::new("child/ts.avdl")
+        ));
+
+        assert!(find_files("examples/*.missing").is_err());
+    }
+
+    #[test]
+    fn test_batch_duplicate_outputs() {
+        let root = std::env::temp_dir().join(format!("avro-idl-batch-{}", std::process::id()));
+        for dir in ["a", "b"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::copy("examples/simple.avdl", root.join(dir).join("simple.avdl")).unwrap();
+        }
+        let input = |dir: &str| root.join(dir).to_string_lossy().to_string();
+        let out_dir = root.join("out");
+        let runner = AvroIdlBatchParser::new(
+            vec![input("a"), input("b"), input("a/simple.avdl")],
+            out_dir.to_string_lossy().to_string(),
+            None,
+        );
+        let errors = runner.parse().unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![format!(
+                "{}: would be written by each of {}, {}",
+                out_dir.join("simple.avpr").display(),
+                root.join("a").join("simple.avdl").display(),
+                root.join("b").join("simple.avdl").display()
+            )]
+        );
+        assert!(!out_dir.exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_protocol_to_avsc() {
+        let runner = AvroIdlParser::new(
+            "examples/protocol.avdl".to_string(),
+            STDIO_PATH.to_string(),
+            Some(OutputFormat::AVSC),
+        );
+        let err = runner.parse().unwrap_err();
+        assert_eq!(err.kind(), "InvalidASTDataType");
+        assert_eq!(
+            err.to_string(),
+            "Only schema documents can be serialized to avsc!"
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let checker = AvroIdlChecker::new(vec!["examples".to_string()]);
+        assert_eq!(checker.check(), vec![]);
 
-        serde_json::to_writer(file, &content).unwrap();
+        let checker = AvroIdlChecker::new(vec!["examples/missing.avdl".to_string()]);
+        let diagnostics = checker.check();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "examples/missing.avdl");
+        assert_eq!(diagnostics[0].kind, "FailedReading");
     }
 }
@@ -0,0 +1,637 @@
+//! Typed schema model, which is the public output of the linker. Unlike `ast::Field` every
+//! part of a schema is a named struct, and the model can be serialized with serde e.g. to
+//! cache compiled schemas.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::ast::{qualify, reference_fullnames, Field, HasDefault, Literal, Properties};
+use crate::error::AvroError;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// A compiled IDL file, either a protocol or a schema file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Document {
+    Protocol(Protocol),
+    Schema(SchemaFile),
+}
+
+impl Document {
+    /// Named types declared by the file, including imported ones
+    pub fn types(&self) -> &[NamedSchema] {
+        match self {
+            Document::Protocol(protocol) => protocol.types(),
+            Document::Schema(schema) => schema.types(),
+        }
+    }
+
+    /// Namespace of the protocol or schema file
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            Document::Protocol(protocol) => protocol.namespace(),
+            Document::Schema(schema) => schema.namespace(),
+        }
+    }
+
+    /// Find a named type by its name or full name
+    pub fn find_type(&self, name: &str) -> Option<&NamedSchema> {
+        self.types()
+            .iter()
+            .find(|named| named.name() == name || named.fullname() == name)
+    }
+
+    /// The named type a `Schema::Record` or `Schema::Enum` stands for
+    pub fn named_type(&self, fullname: &str) -> Option<&NamedSchema> {
+        self.types()
+            .iter()
+            .find(|named| named.fullname() == fullname)
+    }
+}
+
+/// A protocol with the types it declares
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Protocol {
+    pub(crate) name: String,
+    pub(crate) namespace: Option<String>,
+    pub(crate) doc: Option<String>,
+    pub(crate) properties: Properties,
+    pub(crate) types: Vec<NamedSchema>,
+}
+
+impl Protocol {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    /// Custom properties set through annotations
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    pub fn types(&self) -> &[NamedSchema] {
+        &self.types
+    }
+}
+
+/// A schema file with an optional main schema and the types it declares
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaFile {
+    pub(crate) namespace: Option<String>,
+    pub(crate) main: Option<Schema>,
+    pub(crate) types: Vec<NamedSchema>,
+}
+
+impl SchemaFile {
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// The schema declared with `schema <type>;`
+    pub fn main(&self) -> Option<&Schema> {
+        self.main.as_ref()
+    }
+
+    pub fn types(&self) -> &[NamedSchema] {
+        &self.types
+    }
+}
+
+/// A record or an enum
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NamedSchema {
+    Record(RecordSchema),
+    Enum(EnumSchema),
+}
+
+impl NamedSchema {
+    pub fn name(&self) -> &str {
+        match self {
+            NamedSchema::Record(record) => record.name(),
+            NamedSchema::Enum(enum_) => enum_.name(),
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            NamedSchema::Record(record) => record.namespace(),
+            NamedSchema::Enum(enum_) => enum_.namespace(),
+        }
+    }
+
+    /// Name prefixed with the namespace if there is one
+    pub fn fullname(&self) -> String {
+        qualify(self.name(), self.namespace())
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        match self {
+            NamedSchema::Record(record) => record.doc(),
+            NamedSchema::Enum(enum_) => enum_.doc(),
+        }
+    }
+
+    pub fn properties(&self) -> &Properties {
+        match self {
+            NamedSchema::Record(record) => record.properties(),
+            NamedSchema::Enum(enum_) => enum_.properties(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordSchema {
+    pub(crate) name: String,
+    pub(crate) namespace: Option<String>,
+    pub(crate) doc: Option<String>,
+    pub(crate) properties: Properties,
+    pub(crate) fields: Vec<FieldDef>,
+}
+
+impl RecordSchema {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Name prefixed with the namespace if there is one
+    pub fn fullname(&self) -> String {
+        qualify(self.name(), self.namespace())
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    pub fn fields(&self) -> &[FieldDef] {
+        &self.fields
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldDef> {
+        self.fields.iter().find(|field| field.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumSchema {
+    pub(crate) name: String,
+    pub(crate) namespace: Option<String>,
+    pub(crate) doc: Option<String>,
+    pub(crate) properties: Properties,
+    pub(crate) symbols: Vec<String>,
+    pub(crate) default: Option<String>,
+}
+
+impl EnumSchema {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Name prefixed with the namespace if there is one
+    pub fn fullname(&self) -> String {
+        qualify(self.name(), self.namespace())
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    /// Symbol used when reading an unknown symbol
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+}
+
+/// A field of a record or a parameter of a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDef {
+    pub(crate) name: String,
+    pub(crate) schema: Schema,
+    /// Omitted when there is no default, so that a default of null survives a round trip
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_default"
+    )]
+    pub(crate) default: Option<Value>,
+    pub(crate) doc: Option<String>,
+    pub(crate) properties: Properties,
+    /// Annotations before the type, e.g. a `logicalType`
+    #[serde(default)]
+    pub(crate) type_properties: Properties,
+}
+
+impl FieldDef {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Default as JSON, where a default of null is `Some(Value::Null)`
+    pub fn default(&self) -> Option<&Value> {
+        self.default.as_ref()
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    /// Properties of the type of the field, for `T?` those of `T`
+    pub fn type_properties(&self) -> &Properties {
+        &self.type_properties
+    }
+}
+
+/// The type of a field, an array item, a union branch or a main schema. Records and enums
+/// are referenced by their full name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+    Array(Box<Schema>),
+    Union(Vec<Schema>),
+    Record(String),
+    Enum(String),
+}
+
+impl Schema {
+    /// Replace the names of records and enums, which are relative to the enclosing
+    /// namespace, by the full names of the types they stand for
+    fn resolve(self, namespace: Option<&str>, fullnames: &HashSet<String>) -> Schema {
+        let resolve = |name: String| {
+            reference_fullnames(&name, namespace)
+                .into_iter()
+                .find(|fullname| fullnames.contains(fullname))
+                .unwrap_or_else(|| qualify(&name, namespace))
+        };
+        match self {
+            Schema::Array(inner) => Schema::Array(Box::new(inner.resolve(namespace, fullnames))),
+            Schema::Union(types) => Schema::Union(
+                types
+                    .into_iter()
+                    .map(|type_| type_.resolve(namespace, fullnames))
+                    .collect(),
+            ),
+            Schema::Record(name) => Schema::Record(resolve(name)),
+            Schema::Enum(name) => Schema::Enum(resolve(name)),
+            schema => schema,
+        }
+    }
+
+    /// Name of the type in Avro e.g. `int`, `array` or the full name of a record
+    pub fn type_name(&self) -> &str {
+        match self {
+            Schema::Null => "null",
+            Schema::Boolean => "boolean",
+            Schema::Int => "int",
+            Schema::Long => "long",
+            Schema::Float => "float",
+            Schema::Double => "double",
+            Schema::String => "string",
+            Schema::Array(_) => "array",
+            Schema::Union(_) => "union",
+            Schema::Record(name) | Schema::Enum(name) => name,
+        }
+    }
+
+    pub fn is_primitive(&self) -> bool {
+        matches!(
+            self,
+            Schema::Null
+                | Schema::Boolean
+                | Schema::Int
+                | Schema::Long
+                | Schema::Float
+                | Schema::Double
+                | Schema::String
+        )
+    }
+
+    /// The other type of a union of null and one other type e.g. `int?`
+    pub fn nullable_inner(&self) -> Option<&Schema> {
+        match self {
+            Schema::Union(types) => match types.as_slice() {
+                [Schema::Null, inner] | [inner, Schema::Null] if *inner != Schema::Null => {
+                    Some(inner)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The type as written in IDL, e.g. `array<int>` or `union { null, Person }`
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schema::Array(inner) => write!(f, "array<{}>", inner),
+            Schema::Union(types) => {
+                let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "union {{ {} }}", types.join(", "))
+            }
+            schema => write!(f, "{}", schema.type_name()),
+        }
+    }
+}
+
+impl TryFrom<Field> for Document {
+    type Error = AvroError;
+
+    fn try_from(field: Field) -> Result<Self, Self::Error> {
+        match field {
+            Field::Protocol(name, types, namespace, doc, properties) => {
+                Ok(Document::Protocol(Protocol {
+                    name: required_name(name, "Protocol")?,
+                    namespace,
+                    doc,
+                    properties,
+                    types: named_schemas(types)?,
+                }))
+            }
+            Field::Schema(main, types, namespace) => {
+                let types = named_schemas(types)?;
+                let main = main
+                    .map(|main| Schema::try_from(&*main))
+                    .transpose()?
+                    .map(|main| main.resolve(namespace.as_deref(), &fullnames(&types)));
+                Ok(Document::Schema(SchemaFile {
+                    namespace,
+                    main,
+                    types,
+                }))
+            }
+            _ => Err(AvroError::InvalidASTDataType(
+                "Expected a protocol or a schema".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<Field> for NamedSchema {
+    type Error = AvroError;
+
+    fn try_from(field: Field) -> Result<Self, Self::Error> {
+        match field {
+            Field::Record(name, fields, namespace, doc, properties) => {
+                Ok(NamedSchema::Record(RecordSchema {
+                    name: required_name(name, "Record")?,
+                    namespace,
+                    doc,
+                    properties,
+                    fields: fields
+                        .into_iter()
+                        .map(FieldDef::try_from)
+                        .collect::<Result<Vec<FieldDef>, AvroError>>()?,
+                }))
+            }
+            Field::Enum(name, symbols, default, namespace, doc, properties) => {
+                Ok(NamedSchema::Enum(EnumSchema {
+                    name: required_name(name, "Enum")?,
+                    namespace,
+                    doc,
+                    properties,
+                    symbols,
+                    default: match default {
+                        HasDefault::Default(default) => default,
+                        HasDefault::None => None,
+                    },
+                }))
+            }
+            _ => Err(AvroError::InvalidASTDataType(
+                "Only records and enums can be declared!".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<Field> for FieldDef {
+    type Error = AvroError;
+
+    fn try_from(field: Field) -> Result<Self, Self::Error> {
+        let schema = Schema::try_from(&field)?;
+        let type_properties = match &field {
+            Field::Union(_, types, ..) => match types
+                .iter()
+                .filter(|type_| !matches!(type_, Field::Null))
+                .collect::<Vec<_>>()[..]
+            {
+                [type_] => type_.type_properties().cloned().unwrap_or_default(),
+                _ => Properties::new(),
+            },
+            field => field.type_properties().cloned().unwrap_or_default(),
+        };
+        let (name, default, doc, properties) = match field {
+            Field::Int(name, default, doc, props, _) => (name, json_default(default), doc, props),
+            Field::Long(name, default, doc, props, _) => (name, json_default(default), doc, props),
+            Field::Float(name, default, doc, props, _) => (name, json_default(default), doc, props),
+            Field::Double(name, default, doc, props, _) => {
+                (name, json_default(default), doc, props)
+            }
+            Field::Boolean(name, default, doc, props, _) => {
+                (name, json_default(default), doc, props)
+            }
+            Field::String(name, default, doc, props, _) => {
+                (name, json_default(default), doc, props)
+            }
+            Field::Union(name, _, default, doc, props)
+            | Field::Array(name, _, default, doc, props, _) => {
+                (name, json_default(default.map(literal_value)), doc, props)
+            }
+            Field::EnumReference(name, _, default, doc, props) => {
+                (name, json_default(default), doc, props)
+            }
+            Field::RecordReference(name, _, doc, props) => (name, None, doc, props),
+            _ => {
+                return Err(AvroError::InvalidASTDataType(
+                    "Records can only contain fields!".to_string(),
+                ))
+            }
+        };
+        Ok(FieldDef {
+            name: required_name(name, "Field")?,
+            schema,
+            default,
+            doc,
+            properties,
+            type_properties,
+        })
+    }
+}
+
+impl TryFrom<&Field> for Schema {
+    type Error = AvroError;
+
+    fn try_from(field: &Field) -> Result<Self, Self::Error> {
+        match field {
+            Field::Null => Ok(Schema::Null),
+            Field::Boolean(..) => Ok(Schema::Boolean),
+            Field::Int(..) => Ok(Schema::Int),
+            Field::Long(..) => Ok(Schema::Long),
+            Field::Float(..) => Ok(Schema::Float),
+            Field::Double(..) => Ok(Schema::Double),
+            Field::String(..) => Ok(Schema::String),
+            Field::Array(_, inner, ..) => Ok(Schema::Array(Box::new(Schema::try_from(&**inner)?))),
+            Field::Union(_, types, ..) => Ok(Schema::Union(
+                types
+                    .iter()
+                    .map(Schema::try_from)
+                    .collect::<Result<Vec<Schema>, AvroError>>()?,
+            )),
+            Field::RecordReference(_, type_, ..) => Ok(Schema::Record(type_.clone())),
+            Field::EnumReference(_, type_, ..) => Ok(Schema::Enum(type_.clone())),
+            _ => Err(AvroError::InvalidASTDataType(
+                "Named types can only be used through a reference!".to_string(),
+            )),
+        }
+    }
+}
+
+fn deserialize_default<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+/// Named types with the references of their fields resolved to full names
+fn named_schemas(types: Vec<Field>) -> Result<Vec<NamedSchema>, AvroError> {
+    let mut types = types
+        .into_iter()
+        .map(NamedSchema::try_from)
+        .collect::<Result<Vec<NamedSchema>, AvroError>>()?;
+    let fullnames = fullnames(&types);
+    for named in types.iter_mut() {
+        if let NamedSchema::Record(record) = named {
+            for field in record.fields.iter_mut() {
+                field.schema = field
+                    .schema
+                    .clone()
+                    .resolve(record.namespace.as_deref(), &fullnames);
+            }
+        }
+    }
+    Ok(types)
+}
+
+fn fullnames(types: &[NamedSchema]) -> HashSet<String> {
+    types.iter().map(NamedSchema::fullname).collect()
+}
+
+fn required_name(name: Option<String>, kind: &str) -> Result<String, AvroError> {
+    name.ok_or_else(|| AvroError::MissingName(format!("{} is missing a name!", kind)))
+}
+
+fn json_default<T: Serialize>(default: HasDefault<T>) -> Option<Value> {
+    match default {
+        HasDefault::Default(Some(value)) => Some(json!(value)),
+        HasDefault::Default(None) => Some(Value::Null),
+        HasDefault::None => None,
+    }
+}
+
+fn literal_value(literal: Literal) -> Value {
+    match literal {
+        Literal::Int(value) => json!(value),
+        Literal::Long(value) => json!(value),
+        Literal::Float(value) => json!(value),
+        Literal::Double(value) => json!(value),
+        Literal::Boolean(value) => json!(value),
+        Literal::String(value) => json!(value),
+        Literal::Null => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Document, NamedSchema, Schema};
+    use crate::compiler::IdlCompiler;
+
+    #[test]
+    fn test_document_from_protocol() {
+        let src = "@namespace(\"org.example\")
+protocol Event {
+  enum Meal { Dinner, Lunch } = Dinner;
+  /** A person */
+  record Person {
+    string name = \"Bob\";
+    int? age = null;
+    array<Meal> meals;
+    Person @pii(true) partner;
+  }
+}";
+        let document = IdlCompiler::new().compile_document(src).unwrap();
+        let Document::Protocol(protocol) = &document else {
+            panic!("Expected a protocol");
+        };
+        assert_eq!(protocol.name(), "Event");
+        assert_eq!(protocol.namespace(), Some("org.example"));
+
+        let Some(NamedSchema::Record(person)) = document.find_type("org.example.Person") else {
+            panic!("Expected a record");
+        };
+        assert_eq!(person.doc(), Some("A person"));
+        assert_eq!(person.fields().len(), 4);
+        assert_eq!(person.fields()[0].default(), Some(&json!("Bob")));
+
+        let age = person.field("age").unwrap();
+        assert_eq!(age.schema().nullable_inner(), Some(&Schema::Int));
+        assert_eq!(age.default(), Some(&json!(null)));
+
+        let meals = person.field("meals").unwrap();
+        assert_eq!(
+            meals.schema(),
+            &Schema::Array(Box::new(Schema::Enum("org.example.Meal".to_string())))
+        );
+        let partner = person.field("partner").unwrap();
+        assert_eq!(
+            partner.schema(),
+            &Schema::Record("org.example.Person".to_string())
+        );
+        assert_eq!(partner.properties()["pii"], json!(true));
+
+        // The model can be cached as JSON
+        let cached = serde_json::to_string(&document).unwrap();
+        assert_eq!(serde_json::from_str::<Document>(&cached).unwrap(), document);
+    }
+}
@@ -1,189 +1,96 @@
-use crate::{ast::HasDefault, error::AvroError};
+use crate::{
+    ast::{qualify, reference_fullnames, HasDefault, Properties},
+    error::AvroError,
+};
 
 /// Serialize a Protocol to avsc format
 ///
 ///
 use super::ast::Field;
-use serde_json::{json, Value};
+use crate::visitor::{walk_children, walk_field, Visitor};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
 
 pub struct AvprSerializer {
     protocol: Field,
+    /// Full names of the named types, to write references relative to their namespace
+    fullnames: Vec<String>,
 }
 
 impl AvprSerializer {
     pub fn new(protocol: Field) -> Self {
-        Self { protocol }
+        let fullnames = match &protocol {
+            Field::Protocol(_, types, namespace, ..) | Field::Schema(_, types, namespace) => types
+                .iter()
+                .filter_map(|type_| type_fullname(type_, namespace.as_deref()))
+                .collect(),
+            _ => vec![],
+        };
+        Self {
+            protocol,
+            fullnames,
+        }
     }
 
     pub fn serialize(&self) -> Result<Value, AvroError> {
-        self.serialize_field(self.protocol.clone())
+        let json_data = self.serialize_field(&self.protocol, None)?;
+        Ok(order_keys(json_data, ObjectKind::Schema))
     }
 
-    fn serialize_field(&self, field: Field) -> Result<Value, AvroError> {
-        let cf = field.clone();
-        match field {
-            Field::Protocol(name, inner_fields, namespace, docstring) => match name {
-                Some(n) => {
-                    let mut json_data = json!({"protocol": n, "types": inner_fields.into_iter().map(|f| self.serialize_field(f).unwrap()).collect::<Vec<Value>>()});
-                    if let Some(ns) = namespace {
-                        json_data["namespace"] = json!(ns);
-                    }
-                    if let Some(ds) = docstring {
-                        json_data["doc"] = json!(ds);
-                    }
-                    Ok(json_data)
-                }
-                None => Err(AvroError::MissingName(
-                    "Protocol doesn't have a name, but this is required!".to_string(),
-                )),
-            },
-            Field::Int(name, default, docstring) => {
-                let mut json_data = json!({"name": name, "type": cf.get_avro_type_name().unwrap()});
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Long(name, default, docstring) => {
-                let mut json_data = json!({"name": name, "type": cf.get_avro_type_name().unwrap()});
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Float(name, default, docstring) => {
-                let mut json_data = json!({"name": name, "type": cf.get_avro_type_name().unwrap()});
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Double(name, default, docstring) => {
-                let mut json_data = json!({"name": name, "type": cf.get_avro_type_name().unwrap()});
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Boolean(name, default, docstring) => {
-                let mut json_data = json!({"name": name, "type": cf.get_avro_type_name().unwrap()});
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::String(name, default, docstring) => {
-                let mut json_data = json!({"name": name, "type": cf.get_avro_type_name().unwrap()});
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Record(name, inner_fields, namespace, docstring) => {
-                let mut json_data = json!({"type": cf.get_avro_type_name().unwrap(), "name": name, "fields": inner_fields
-                .into_iter()
-                .map(|f| self.serialize_field(f).unwrap())
-                .collect::<Vec<Value>>()});
-                if let Some(ns) = namespace {
-                    json_data["namespace"] = json!(ns);
-                }
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Enum(name, symbols, default, namespace, docstring) => {
-                let mut json_data = json!({"type": cf.get_avro_type_name().unwrap(), "name": name, "symbols": symbols});
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ns) = namespace {
-                    json_data["namespace"] = json!(ns);
-                }
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Union(name, inner_fields, default, docstring) => {
-                let mut json_data = json!({
-                    "type": "Union",
-                    "name": name,
-                    "type": inner_fields.into_iter()
-                        .filter_map(|f| f.get_avro_type_name())
-                        .collect::<Vec<String>>()
-                });
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Array(name, inner_field, _, docstring) => {
-                let mut json_data = json!({"type": "array", "name": name, "items": inner_field.get_avro_type_name().unwrap()});
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::RecordReference(name, type_, docstring) => {
-                let mut json_data = json!({"name": name, "type": type_});
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::EnumReference(name, type_, default, docstring) => {
-                let mut json_data = json!({"name": name, "type": type_});
-                match default {
-                    HasDefault::Default(Some(v)) => json_data["default"] = json!(v),
-                    HasDefault::Default(None) => json_data["default"] = json!(None::<String>), // TODO: This case cannot happen, since this Field is not nullable
-                    HasDefault::None => (),
-                };
-                if let Some(ds) = docstring {
-                    json_data["doc"] = json!(ds);
-                }
-                Ok(json_data)
-            }
-            Field::Null => Err(AvroError::InvalidASTDataType(
-                "Cannot serialize Field::Null!".to_string(),
-            )),
+    /// Serialize a type without the attributes of a field, where references are written
+    /// relative to the enclosing namespace
+    fn serialize_type(&self, field: &Field, namespace: Option<&str>) -> Result<Value, AvroError> {
+        self.write(field, namespace, true)
+    }
+
+    /// Serialize a field or declaration inside the given enclosing namespace
+    fn serialize_field(&self, field: &Field, enclosing: Option<&str>) -> Result<Value, AvroError> {
+        self.write(field, enclosing, false)
+    }
+
+    fn write(
+        &self,
+        field: &Field,
+        namespace: Option<&str>,
+        in_type: bool,
+    ) -> Result<Value, AvroError> {
+        let mut writer = JsonWriter {
+            serializer: self,
+            namespaces: vec![namespace.map(String::from)],
+            in_type,
+            values: vec![],
+            error: None,
+        };
+        writer.visit_field(field);
+        match writer.error {
+            Some(err) => Err(err),
+            None => Ok(writer.values.pop().unwrap_or(Value::Null)),
+        }
+    }
+
+    /// Name of a referenced type as written inside the given namespace, which is the short
+    /// name if the type is in that namespace and its full name otherwise
+    fn reference_name(&self, type_: &str, namespace: Option<&str>) -> String {
+        let Some(fullname) = reference_fullnames(type_, namespace)
+            .into_iter()
+            .find(|fullname| self.fullnames.contains(fullname))
+        else {
+            return type_.to_string();
+        };
+        match fullname.rsplit_once('.') {
+            Some((type_namespace, name)) if Some(type_namespace) == namespace => name.to_string(),
+            _ => fullname,
+        }
+    }
+
+    /// Add custom properties from annotations as extra attributes. Properties never
+    /// overwrite the attributes set by the serializer itself.
+    fn add_properties(json_data: &mut Value, props: Properties) {
+        let Some(object) = json_data.as_object_mut() else {
+            return;
+        };
+        for (key, value) in props.into_iter() {
+            object.entry(key).or_insert(value);
         }
     }
 
@@ -219,6 +126,549 @@ impl AvprSerializer {
     // }
 }
 
+/// Writes the JSON of the visited fields. Every visited field pushes its value, so a field
+/// takes the ones its children pushed.
+struct JsonWriter<'a> {
+    serializer: &'a AvprSerializer,
+    /// Namespaces of the enclosing protocol and records, innermost last
+    namespaces: Vec<Option<String>>,
+    /// Whether the visited field is a member of a union or the items of an array, which are
+    /// written as bare types
+    in_type: bool,
+    values: Vec<Value>,
+    error: Option<AvroError>,
+}
+
+impl JsonWriter<'_> {
+    fn namespace(&self) -> Option<&str> {
+        self.namespaces
+            .last()
+            .and_then(|namespace| namespace.as_deref())
+    }
+
+    /// Write the children of a field, as bare types or as fields and declarations
+    fn write_children(&mut self, field: &Field, in_type: bool) -> Vec<Value> {
+        let start = self.values.len();
+        let enclosing_in_type = std::mem::replace(&mut self.in_type, in_type);
+        walk_children(self, field);
+        self.in_type = enclosing_in_type;
+        self.values.split_off(start.min(self.values.len()))
+    }
+
+    /// Push the type alone inside of a type, and a field with its attributes otherwise
+    fn push_field(
+        &mut self,
+        name: &Option<String>,
+        type_: Value,
+        default: Option<Value>,
+        docstring: &Option<String>,
+        props: &Properties,
+    ) {
+        if self.in_type {
+            self.values.push(type_);
+            return;
+        }
+        let mut json_data = json!({"name": name, "type": type_});
+        if let Some(default) = default {
+            json_data["default"] = default;
+        }
+        if let Some(ds) = docstring {
+            json_data["doc"] = json!(ds);
+        }
+        AvprSerializer::add_properties(&mut json_data, props.clone());
+        self.values.push(json_data);
+    }
+
+    /// Named types are declared in the protocol or a record, never inside of a type
+    fn declaration_allowed(&mut self) -> bool {
+        if self.in_type {
+            self.fail(AvroError::InvalidASTDataType(
+                "Named types can only be used through a reference!".to_string(),
+            ));
+        }
+        !self.in_type
+    }
+
+    fn fail(&mut self, err: AvroError) {
+        self.error.get_or_insert(err);
+    }
+}
+
+/// Value of a default, which is null for a null default
+fn default_json<T: serde::Serialize>(default: &HasDefault<T>) -> Option<Value> {
+    match default {
+        HasDefault::Default(Some(v)) => Some(json!(v)),
+        HasDefault::Default(None) => Some(Value::Null),
+        HasDefault::None => None,
+    }
+}
+
+impl Visitor for JsonWriter<'_> {
+    fn visit_field(&mut self, field: &Field) {
+        if self.error.is_none() {
+            walk_field(self, field)
+        }
+    }
+
+    fn visit_protocol(&mut self, protocol: &Field) {
+        let Field::Protocol(name, _, namespace, docstring, props) = protocol else {
+            return;
+        };
+        if !self.declaration_allowed() {
+            return;
+        }
+        let Some(name) = name else {
+            return self.fail(AvroError::MissingName(
+                "Protocol doesn't have a name, but this is required!".to_string(),
+            ));
+        };
+        self.namespaces.push(namespace.clone());
+        let types = self.write_children(protocol, false);
+        self.namespaces.pop();
+        let mut json_data = json!({"protocol": name, "types": types});
+        if let Some(ns) = namespace {
+            json_data["namespace"] = json!(ns);
+        }
+        if let Some(ds) = docstring {
+            json_data["doc"] = json!(ds);
+        }
+        AvprSerializer::add_properties(&mut json_data, props.clone());
+        self.values.push(json_data);
+    }
+
+    fn visit_schema(&mut self, _schema: &Field) {
+        self.fail(AvroError::InvalidASTDataType(
+            "Schema documents can only be serialized to avsc!".to_string(),
+        ))
+    }
+
+    fn visit_record(&mut self, record: &Field) {
+        let Field::Record(name, _, namespace, docstring, props) = record else {
+            return;
+        };
+        if !self.declaration_allowed() {
+            return;
+        }
+        let enclosing = namespace
+            .clone()
+            .or_else(|| self.namespace().map(String::from));
+        self.namespaces.push(enclosing);
+        let fields = self.write_children(record, false);
+        self.namespaces.pop();
+        let mut json_data = json!({"type": "record", "name": name, "fields": fields});
+        if let Some(ns) = namespace {
+            json_data["namespace"] = json!(ns);
+        }
+        if let Some(ds) = docstring {
+            json_data["doc"] = json!(ds);
+        }
+        AvprSerializer::add_properties(&mut json_data, props.clone());
+        self.values.push(json_data);
+    }
+
+    fn visit_enum(&mut self, enum_: &Field) {
+        let Field::Enum(name, symbols, default, namespace, docstring, props) = enum_ else {
+            return;
+        };
+        if !self.declaration_allowed() {
+            return;
+        }
+        let mut json_data = json!({"type": "enum", "name": name, "symbols": symbols});
+        if let Some(default) = default_json(default) {
+            json_data["default"] = default;
+        }
+        if let Some(ns) = namespace {
+            json_data["namespace"] = json!(ns);
+        }
+        if let Some(ds) = docstring {
+            json_data["doc"] = json!(ds);
+        }
+        AvprSerializer::add_properties(&mut json_data, props.clone());
+        self.values.push(json_data);
+    }
+
+    fn visit_union(&mut self, union: &Field) {
+        let Field::Union(name, _, default, docstring, props) = union else {
+            return;
+        };
+        let types = self.write_children(union, true);
+        self.push_field(
+            name,
+            Value::Array(types),
+            default_json(default),
+            docstring,
+            props,
+        );
+    }
+
+    fn visit_array(&mut self, array: &Field) {
+        let Field::Array(name, _, _, docstring, props, type_props) = array else {
+            return;
+        };
+        let Some(items) = self.write_children(array, true).pop() else {
+            return;
+        };
+        let mut type_ = json!({"type": "array", "items": items});
+        AvprSerializer::add_properties(&mut type_, type_props.clone());
+        self.push_field(name, type_, None, docstring, props);
+    }
+
+    fn visit_primitive(&mut self, primitive: &Field) {
+        let mut type_ = json!(primitive.get_avro_type_name());
+        // Properties of a primitive type need its full form e.g. {"type": "long", ...}
+        if let Some(type_props) = primitive.type_properties().filter(|p| !p.is_empty()) {
+            type_ = json!({ "type": type_ });
+            AvprSerializer::add_properties(&mut type_, type_props.clone());
+        }
+        match primitive {
+            Field::Int(name, default, docstring, props, _) => {
+                self.push_field(name, type_, default_json(default), docstring, props)
+            }
+            Field::Long(name, default, docstring, props, _) => {
+                self.push_field(name, type_, default_json(default), docstring, props)
+            }
+            Field::Float(name, default, docstring, props, _) => {
+                self.push_field(name, type_, default_json(default), docstring, props)
+            }
+            Field::Double(name, default, docstring, props, _) => {
+                self.push_field(name, type_, default_json(default), docstring, props)
+            }
+            Field::Boolean(name, default, docstring, props, _) => {
+                self.push_field(name, type_, default_json(default), docstring, props)
+            }
+            Field::String(name, default, docstring, props, _) => {
+                self.push_field(name, type_, default_json(default), docstring, props)
+            }
+            _ if self.in_type => self.values.push(type_),
+            _ => self.fail(AvroError::InvalidASTDataType(
+                "Cannot serialize Field::Null!".to_string(),
+            )),
+        }
+    }
+
+    fn visit_reference(&mut self, reference: &Field) {
+        let type_ = match reference {
+            Field::RecordReference(_, type_, ..) | Field::EnumReference(_, type_, ..) => {
+                json!(self.serializer.reference_name(type_, self.namespace()))
+            }
+            _ => return,
+        };
+        match reference {
+            Field::RecordReference(name, _, docstring, props) => {
+                self.push_field(name, type_, None, docstring, props)
+            }
+            Field::EnumReference(name, _, default, docstring, props) => {
+                self.push_field(name, type_, default_json(default), docstring, props)
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Serialize the main schema of a schema document to avsc format. Named types
+/// referenced by the main schema are defined inline where they are first used.
+pub struct AvscSerializer {
+    schema: Field,
+}
+
+impl AvscSerializer {
+    pub fn new(schema: Field) -> Self {
+        Self { schema }
+    }
+
+    pub fn serialize(&self) -> Result<Value, AvroError> {
+        let Field::Schema(main, _, namespace) = self.schema.clone() else {
+            return Err(AvroError::InvalidASTDataType(
+                "Only schema documents can be serialized to avsc!".to_string(),
+            ));
+        };
+        let Some(main) = main else {
+            return Err(AvroError::MissingName(
+                "Schema document doesn't declare a main schema, but this is required!".to_string(),
+            ));
+        };
+
+        let namespace = namespace.as_deref();
+        let (avpr, named_types) = self.serialize_named_types_by_name()?;
+        let main_schema = avpr.serialize_type(&main, namespace)?;
+        let json_data =
+            Self::inline_named_types(main_schema, &named_types, namespace, &mut HashSet::new());
+        Ok(order_keys(json_data, ObjectKind::Schema))
+    }
+
+    /// Serialize every named type of a protocol or schema document as a standalone schema
+    pub fn serialize_named_types(&self) -> Result<Vec<Value>, AvroError> {
+        let namespace = match &self.schema {
+            Field::Schema(_, _, namespace) | Field::Protocol(_, _, namespace, ..) => {
+                namespace.as_deref()
+            }
+            _ => None,
+        };
+        let (_, named_types) = self.serialize_named_types_by_name()?;
+        Ok(named_types
+            .keys()
+            .map(|fullname| {
+                let json_data = Self::inline_named_types(
+                    Value::String(fullname.clone()),
+                    &named_types,
+                    namespace,
+                    &mut HashSet::new(),
+                );
+                order_keys(json_data, ObjectKind::Schema)
+            })
+            .collect())
+    }
+
+    /// Serialize the named types by full name without inlining the types they reference
+    fn serialize_named_types_by_name(
+        &self,
+    ) -> Result<(AvprSerializer, Map<String, Value>), AvroError> {
+        let (types, namespace) = match self.schema.clone() {
+            Field::Schema(_, types, namespace) => (types, namespace),
+            Field::Protocol(_, types, namespace, ..) => (types, namespace),
+            _ => {
+                return Err(AvroError::InvalidASTDataType(
+                    "Expected a protocol or a schema".to_string(),
+                ))
+            }
+        };
+
+        let avpr = AvprSerializer::new(Field::Protocol(
+            None,
+            types.clone(),
+            namespace.clone(),
+            None,
+            Properties::new(),
+        ));
+        let mut named_types = Map::new();
+        for named_type in types.into_iter() {
+            if let Some(fullname) = type_fullname(&named_type, namespace.as_deref()) {
+                let json_data = avpr.serialize_field(&named_type, namespace.as_deref())?;
+                named_types.insert(fullname, json_data);
+            }
+        }
+        Ok((avpr, named_types))
+    }
+
+    /// Replace the first reference to every named type by its definition, where references
+    /// are resolved relative to the enclosing namespace
+    fn inline_named_types(
+        value: Value,
+        named_types: &Map<String, Value>,
+        namespace: Option<&str>,
+        defined: &mut HashSet<String>,
+    ) -> Value {
+        match value {
+            Value::String(name) => {
+                let fullname = reference_fullnames(&name, namespace)
+                    .into_iter()
+                    .find(|fullname| named_types.contains_key(fullname));
+                match fullname {
+                    Some(fullname) if defined.insert(fullname.clone()) => {
+                        let definition = named_types[&fullname].clone();
+                        Self::inline_named_types(definition, named_types, namespace, defined)
+                    }
+                    _ => Value::String(name),
+                }
+            }
+            Value::Array(values) => Value::Array(
+                values
+                    .into_iter()
+                    .map(|v| Self::inline_named_types(v, named_types, namespace, defined))
+                    .collect(),
+            ),
+            Value::Object(mut object) => {
+                let own_namespace = object
+                    .get("namespace")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                let namespace = own_namespace.as_deref().or(namespace);
+                for key in ["type", "items", "fields"] {
+                    if let Some(inner) = object.get_mut(key) {
+                        *inner =
+                            Self::inline_named_types(inner.take(), named_types, namespace, defined);
+                    }
+                }
+                Value::Object(object)
+            }
+            _ => value,
+        }
+    }
+}
+
+/// The kind of JSON object in a serialized protocol or schema
+#[derive(Clone, Copy)]
+enum ObjectKind {
+    Protocol,
+    Schema,
+    Field,
+}
+
+impl ObjectKind {
+    /// Order of the attributes as written by Java's `avro-tools idl`, where `*` is the
+    /// position of any other attribute e.g. custom properties
+    fn key_order(&self) -> &'static [&'static str] {
+        match self {
+            ObjectKind::Protocol => &["protocol", "namespace", "doc", "*", "types", "messages"],
+            ObjectKind::Schema => &[
+                "type",
+                "name",
+                "namespace",
+                "doc",
+                "fields",
+                "symbols",
+                "items",
+                "values",
+                "size",
+                "default",
+                "*",
+            ],
+            ObjectKind::Field => &["name", "type", "doc", "default", "order", "*"],
+        }
+    }
+}
+
+/// Order the attributes of every object like Java's `avro-tools idl` does
+fn order_keys(value: Value, kind: ObjectKind) -> Value {
+    match value {
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(|v| order_keys(v, kind)).collect())
+        }
+        Value::Object(mut object) => {
+            let kind = if object.contains_key("protocol") {
+                ObjectKind::Protocol
+            } else {
+                kind
+            };
+            let key_order = kind.key_order();
+            let rank = |key: &str| {
+                key_order
+                    .iter()
+                    .position(|k| *k == key)
+                    .or(key_order.iter().position(|k| *k == "*"))
+            };
+            let mut keys = object.keys().cloned().collect::<Vec<String>>();
+            // Stable sort keeps the order of custom properties
+            keys.sort_by_key(|key| rank(key));
+
+            let mut ordered = Map::new();
+            for key in keys.into_iter() {
+                let inner = object.remove(&key).unwrap_or_default();
+                let inner = match key.as_str() {
+                    "types" | "type" | "items" | "values" => order_keys(inner, ObjectKind::Schema),
+                    "fields" => order_keys(inner, ObjectKind::Field),
+                    _ => inner,
+                };
+                ordered.insert(key, inner);
+            }
+            Value::Object(ordered)
+        }
+        _ => value,
+    }
+}
+
+/// Convert a standalone schema to its Parsing Canonical Form, see
+/// https://avro.apache.org/docs/1.11.1/specification/#parsing-canonical-form-for-schemas
+pub fn canonical_form(schema: Value) -> Value {
+    canonicalize(schema, None, &mut HashSet::new())
+}
+
+/// Canonicalize a schema within the enclosing namespace. `fullnames` holds the full names
+/// of the named types defined so far.
+fn canonicalize(
+    schema: Value,
+    namespace: Option<String>,
+    fullnames: &mut HashSet<String>,
+) -> Value {
+    const PRIMITIVES: [&str; 8] = [
+        "null", "boolean", "int", "long", "float", "double", "bytes", "string",
+    ];
+    match schema {
+        Value::String(name) if PRIMITIVES.contains(&name.as_str()) => Value::String(name),
+        // A name is in the enclosing namespace, unless only a type without namespace has it
+        Value::String(name) => {
+            let defined = reference_fullnames(&name, namespace.as_deref())
+                .into_iter()
+                .find(|fullname| fullnames.contains(fullname));
+            Value::String(defined.unwrap_or_else(|| qualify(&name, namespace.as_deref())))
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|v| canonicalize(v, namespace.clone(), fullnames))
+                .collect(),
+        ),
+        Value::Object(object) => {
+            let type_ = object.get("type").cloned().unwrap_or_default();
+            let mut canonical = Map::new();
+            match type_.as_str() {
+                Some("record" | "error" | "enum" | "fixed") => {
+                    let name = object
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or_default();
+                    let own_namespace = object
+                        .get("namespace")
+                        .and_then(|ns| ns.as_str())
+                        .map(|ns| ns.to_string())
+                        .or(namespace);
+                    let full = qualify(name, own_namespace.as_deref());
+                    fullnames.insert(full.clone());
+                    let inner_namespace = full.rsplit_once('.').map(|(ns, _)| ns.to_string());
+
+                    canonical.insert("name".to_string(), json!(full));
+                    canonical.insert("type".to_string(), type_);
+                    if let Some(Value::Array(fields)) = object.get("fields") {
+                        let fields = fields
+                            .iter()
+                            .map(|field| {
+                                json!({
+                                    "name": field.get("name").cloned().unwrap_or_default(),
+                                    "type": canonicalize(
+                                        field.get("type").cloned().unwrap_or_default(),
+                                        inner_namespace.clone(),
+                                        fullnames,
+                                    ),
+                                })
+                            })
+                            .collect::<Vec<Value>>();
+                        canonical.insert("fields".to_string(), Value::Array(fields));
+                    }
+                    for key in ["symbols", "size"] {
+                        if let Some(inner) = object.get(key) {
+                            canonical.insert(key.to_string(), inner.clone());
+                        }
+                    }
+                    Value::Object(canonical)
+                }
+                Some("array" | "map") => {
+                    canonical.insert("type".to_string(), type_);
+                    for key in ["items", "values"] {
+                        if let Some(inner) = object.get(key) {
+                            let inner = canonicalize(inner.clone(), namespace.clone(), fullnames);
+                            canonical.insert(key.to_string(), inner);
+                        }
+                    }
+                    Value::Object(canonical)
+                }
+                // Primitives in their simple form e.g. {"type": "int"} becomes "int"
+                _ => canonicalize(type_, namespace, fullnames),
+            }
+        }
+        _ => schema,
+    }
+}
+
+/// Full name of a record or enum, which is in the given namespace unless it sets its own
+fn type_fullname(field: &Field, namespace: Option<&str>) -> Option<String> {
+    match field {
+        Field::Record(Some(name), _, own, ..) | Field::Enum(Some(name), _, _, own, ..) => {
+            Some(qualify(name, own.as_deref().or(namespace)))
+        }
+        _ => None,
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use super::AVSCSerializer;
@@ -236,3 +686,163 @@ impl AvprSerializer {
 //         assert_eq!(AVSCSerializer::serialize_enum(en), expected);
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{canonical_form, AvprSerializer, AvscSerializer};
+    use crate::ast::{Field, HasDefault, Properties};
+    use crate::compiler::IdlCompiler;
+
+    #[test]
+    fn test_avsc_inlines_named_types_once() {
+        let schema = Field::Schema(
+            Some(Box::new(Field::RecordReference(
+                None,
+                "Person".to_string(),
+                None,
+                Properties::new(),
+            ))),
+            vec![
+                Field::Record(
+                    Some("Person".to_string()),
+                    vec![
+                        Field::RecordReference(
+                            Some("home".to_string()),
+                            "Address".to_string(),
+                            None,
+                            Properties::new(),
+                        ),
+                        Field::RecordReference(
+                            Some("work".to_string()),
+                            "Address".to_string(),
+                            None,
+                            Properties::new(),
+                        ),
+                    ],
+                    None,
+                    None,
+                    Properties::new(),
+                ),
+                Field::Record(
+                    Some("Address".to_string()),
+                    vec![Field::String(
+                        Some("street".to_string()),
+                        HasDefault::None,
+                        None,
+                        Properties::new(),
+                        Properties::new(),
+                    )],
+                    None,
+                    None,
+                    Properties::new(),
+                ),
+            ],
+            None,
+        );
+        let expected = json!({
+            "type": "record",
+            "name": "Person",
+            "fields": [
+                {"name": "home", "type": {
+                    "type": "record",
+                    "name": "Address",
+                    "fields": [{"name": "street", "type": "string"}]
+                }},
+                {"name": "work", "type": "Address"}
+            ]
+        });
+        assert_eq!(AvscSerializer::new(schema).serialize().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_java_key_order() {
+        let protocol = Field::Protocol(
+            Some("Event".to_string()),
+            vec![Field::Enum(
+                Some("Meal".to_string()),
+                vec!["Dinner".to_string()],
+                HasDefault::Default(Some("Dinner".to_string())),
+                Some("org.meals".to_string()),
+                Some("Meals".to_string()),
+                json!({"owner": "team"}).as_object().unwrap().clone(),
+            )],
+            Some("org.example".to_string()),
+            Some("Events".to_string()),
+            Properties::new(),
+        );
+        let serialized = AvprSerializer::new(protocol).serialize().unwrap();
+        assert_eq!(
+            serde_json::to_string(&serialized).unwrap(),
+            concat!(
+                r#"{"protocol":"Event","namespace":"org.example","doc":"Events","types":["#,
+                r#"{"type":"enum","name":"Meal","namespace":"org.meals","doc":"Meals","#,
+                r#""symbols":["Dinner"],"default":"Dinner","owner":"team"}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_annotations_of_field_and_type() {
+        let src = "protocol Event {
+  record Person {
+    @logicalType(\"timestamp-millis\") long @pii(false) created;
+    @logicalType(\"date\") int? birthday;
+  }
+}";
+        let protocol = IdlCompiler::new().compile(src).unwrap();
+        let serialized = AvprSerializer::new(protocol).serialize().unwrap();
+        assert_eq!(
+            serialized["types"][0]["fields"],
+            json!([
+                {
+                    "name": "created",
+                    "type": {"type": "long", "logicalType": "timestamp-millis"},
+                    "pii": false
+                },
+                {"name": "birthday", "type": [{"type": "int", "logicalType": "date"}, "null"]}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let schema = json!({
+            "type": "record",
+            "name": "Person",
+            "namespace": "org.example",
+            "doc": "A person",
+            "fields": [
+                {"name": "age", "type": {"type": "int"}, "default": 1},
+                {"name": "meal", "type": {
+                    "type": "enum",
+                    "name": "Meal",
+                    "namespace": "org.meals",
+                    "symbols": ["Dinner"]
+                }},
+                {"name": "meals", "type": {"type": "array", "items": "org.meals.Meal"}},
+                {"name": "friend", "type": ["null", "Person"]},
+                {"name": "dinner", "type": {
+                    "type": "record",
+                    "name": "Meal",
+                    "fields": [{"name": "kind", "type": "org.meals.Meal"}]
+                }},
+                {"name": "lunch", "type": "Meal"}
+            ]
+        });
+        assert_eq!(
+            serde_json::to_string(&canonical_form(schema)).unwrap(),
+            concat!(
+                r#"{"name":"org.example.Person","type":"record","fields":["#,
+                r#"{"name":"age","type":"int"},"#,
+                r#"{"name":"meal","type":{"name":"org.meals.Meal","type":"enum","symbols":["Dinner"]}},"#,
+                r#"{"name":"meals","type":{"type":"array","items":"org.meals.Meal"}},"#,
+                r#"{"name":"friend","type":["null","org.example.Person"]},"#,
+                r#"{"name":"dinner","type":{"name":"org.example.Meal","type":"record","fields":["#,
+                r#"{"name":"kind","type":"org.meals.Meal"}]}},"#,
+                r#"{"name":"lunch","type":"org.example.Meal"}]}"#
+            )
+        );
+    }
+}
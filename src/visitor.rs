@@ -0,0 +1,326 @@
+//! Traversal of the parsed and the linked AST. Implementors of `Visitor` or `VisitorMut`
+//! override the methods for the kinds of fields they care about, and the default methods
+//! walk into the children of protocols, schema files, records, unions and arrays. An
+//! overriding method can call `walk_children` to keep descending. The linker walks the
+//! parsed `RawField` tree and the avpr serializer the linked `Field` tree this way.
+
+use crate::ast::{Field, RawField};
+
+/// The kind of a field, which decides the method of the visitor it is dispatched to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeKind {
+    Protocol,
+    Schema,
+    Record,
+    Enum,
+    Union,
+    Array,
+    Reference,
+    Primitive,
+    /// Imports and comments of a parsed file
+    Other,
+}
+
+/// A tree of fields which can be visited
+pub trait Node {
+    fn kind(&self) -> NodeKind;
+
+    /// The fields directly contained in this one, where the main schema of a schema file
+    /// comes before its named types
+    fn children(&self) -> Vec<&Self>;
+
+    fn children_mut(&mut self) -> Vec<&mut Self>;
+}
+
+impl Node for Field {
+    fn kind(&self) -> NodeKind {
+        match self {
+            Field::Protocol(..) => NodeKind::Protocol,
+            Field::Schema(..) => NodeKind::Schema,
+            Field::Record(..) => NodeKind::Record,
+            Field::Enum(..) => NodeKind::Enum,
+            Field::Union(..) => NodeKind::Union,
+            Field::Array(..) => NodeKind::Array,
+            Field::RecordReference(..) | Field::EnumReference(..) => NodeKind::Reference,
+            Field::Int(..)
+            | Field::Long(..)
+            | Field::Float(..)
+            | Field::Double(..)
+            | Field::Boolean(..)
+            | Field::String(..)
+            | Field::Null => NodeKind::Primitive,
+        }
+    }
+
+    fn children(&self) -> Vec<&Self> {
+        match self {
+            Field::Protocol(_, fields, ..)
+            | Field::Record(_, fields, ..)
+            | Field::Union(_, fields, ..) => fields.iter().collect(),
+            Field::Schema(main, fields, _) => {
+                main.iter().map(|main| &**main).chain(fields).collect()
+            }
+            Field::Array(_, inner, ..) => vec![inner],
+            _ => vec![],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Self> {
+        match self {
+            Field::Protocol(_, fields, ..)
+            | Field::Record(_, fields, ..)
+            | Field::Union(_, fields, ..) => fields.iter_mut().collect(),
+            Field::Schema(main, fields, _) => main
+                .iter_mut()
+                .map(|main| &mut **main)
+                .chain(fields)
+                .collect(),
+            Field::Array(_, inner, ..) => vec![inner],
+            _ => vec![],
+        }
+    }
+}
+
+impl Node for RawField {
+    fn kind(&self) -> NodeKind {
+        match self {
+            RawField::Protocol(..) => NodeKind::Protocol,
+            RawField::Schema(..) => NodeKind::Schema,
+            RawField::Record(..) => NodeKind::Record,
+            RawField::Enum(..) => NodeKind::Enum,
+            RawField::Union(..) => NodeKind::Union,
+            RawField::Array(..) => NodeKind::Array,
+            RawField::Unresolved(..) => NodeKind::Reference,
+            RawField::Int(..)
+            | RawField::Long(..)
+            | RawField::Float(..)
+            | RawField::Double(..)
+            | RawField::Boolean(..)
+            | RawField::String(..)
+            | RawField::Null => NodeKind::Primitive,
+            RawField::Import(_) | RawField::Comment(..) | RawField::Symbol(..) => NodeKind::Other,
+        }
+    }
+
+    fn children(&self) -> Vec<&Self> {
+        match self {
+            RawField::Protocol(_, fields, ..)
+            | RawField::Record(_, fields, ..)
+            | RawField::Union(_, fields, ..) => fields.iter().collect(),
+            RawField::Schema(main, fields, _) => {
+                main.iter().map(|main| &**main).chain(fields).collect()
+            }
+            RawField::Array(_, inner, ..) => vec![inner],
+            _ => vec![],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Self> {
+        match self {
+            RawField::Protocol(_, fields, ..)
+            | RawField::Record(_, fields, ..)
+            | RawField::Union(_, fields, ..) => fields.iter_mut().collect(),
+            RawField::Schema(main, fields, _) => main
+                .iter_mut()
+                .map(|main| &mut **main)
+                .chain(fields)
+                .collect(),
+            RawField::Array(_, inner, ..) => vec![inner],
+            _ => vec![],
+        }
+    }
+}
+
+/// Visits the linked `Field` tree unless another tree is given, e.g. `Visitor<RawField>`
+pub trait Visitor<T: Node = Field> {
+    /// Dispatch a field to the method for its kind
+    fn visit_field(&mut self, field: &T) {
+        walk_field(self, field)
+    }
+
+    fn visit_protocol(&mut self, protocol: &T) {
+        walk_children(self, protocol)
+    }
+
+    /// A schema file, whose main schema is visited before its named types
+    fn visit_schema(&mut self, schema: &T) {
+        walk_children(self, schema)
+    }
+
+    fn visit_record(&mut self, record: &T) {
+        walk_children(self, record)
+    }
+
+    fn visit_enum(&mut self, _enum: &T) {}
+
+    fn visit_union(&mut self, union: &T) {
+        walk_children(self, union)
+    }
+
+    fn visit_array(&mut self, array: &T) {
+        walk_children(self, array)
+    }
+
+    /// Int, long, float, double, boolean, string or null
+    fn visit_primitive(&mut self, _primitive: &T) {}
+
+    /// A reference to a record or an enum, which is unresolved in the parsed tree
+    fn visit_reference(&mut self, _reference: &T) {}
+
+    /// An import or a comment, which only the parsed tree contains
+    fn visit_other(&mut self, _other: &T) {}
+}
+
+/// Call the method of the visitor for the kind of the field
+pub fn walk_field<T: Node, V: Visitor<T> + ?Sized>(visitor: &mut V, field: &T) {
+    match field.kind() {
+        NodeKind::Protocol => visitor.visit_protocol(field),
+        NodeKind::Schema => visitor.visit_schema(field),
+        NodeKind::Record => visitor.visit_record(field),
+        NodeKind::Enum => visitor.visit_enum(field),
+        NodeKind::Union => visitor.visit_union(field),
+        NodeKind::Array => visitor.visit_array(field),
+        NodeKind::Reference => visitor.visit_reference(field),
+        NodeKind::Primitive => visitor.visit_primitive(field),
+        NodeKind::Other => visitor.visit_other(field),
+    }
+}
+
+/// Visit the fields directly contained in a field
+pub fn walk_children<T: Node, V: Visitor<T> + ?Sized>(visitor: &mut V, field: &T) {
+    for child in field.children() {
+        visitor.visit_field(child);
+    }
+}
+
+/// Like `Visitor`, but with mutable access to the fields e.g. to rename or annotate them
+pub trait VisitorMut<T: Node = Field> {
+    /// Dispatch a field to the method for its kind
+    fn visit_field_mut(&mut self, field: &mut T) {
+        walk_field_mut(self, field)
+    }
+
+    fn visit_protocol_mut(&mut self, protocol: &mut T) {
+        walk_children_mut(self, protocol)
+    }
+
+    /// A schema file, whose main schema is visited before its named types
+    fn visit_schema_mut(&mut self, schema: &mut T) {
+        walk_children_mut(self, schema)
+    }
+
+    fn visit_record_mut(&mut self, record: &mut T) {
+        walk_children_mut(self, record)
+    }
+
+    fn visit_enum_mut(&mut self, _enum: &mut T) {}
+
+    fn visit_union_mut(&mut self, union: &mut T) {
+        walk_children_mut(self, union)
+    }
+
+    fn visit_array_mut(&mut self, array: &mut T) {
+        walk_children_mut(self, array)
+    }
+
+    /// Int, long, float, double, boolean, string or null
+    fn visit_primitive_mut(&mut self, _primitive: &mut T) {}
+
+    /// A reference to a record or an enum, which is unresolved in the parsed tree
+    fn visit_reference_mut(&mut self, _reference: &mut T) {}
+
+    /// An import or a comment, which only the parsed tree contains
+    fn visit_other_mut(&mut self, _other: &mut T) {}
+}
+
+/// Call the method of the visitor for the kind of the field
+pub fn walk_field_mut<T: Node, V: VisitorMut<T> + ?Sized>(visitor: &mut V, field: &mut T) {
+    match field.kind() {
+        NodeKind::Protocol => visitor.visit_protocol_mut(field),
+        NodeKind::Schema => visitor.visit_schema_mut(field),
+        NodeKind::Record => visitor.visit_record_mut(field),
+        NodeKind::Enum => visitor.visit_enum_mut(field),
+        NodeKind::Union => visitor.visit_union_mut(field),
+        NodeKind::Array => visitor.visit_array_mut(field),
+        NodeKind::Reference => visitor.visit_reference_mut(field),
+        NodeKind::Primitive => visitor.visit_primitive_mut(field),
+        NodeKind::Other => visitor.visit_other_mut(field),
+    }
+}
+
+/// Visit the fields directly contained in a field
+pub fn walk_children_mut<T: Node, V: VisitorMut<T> + ?Sized>(visitor: &mut V, field: &mut T) {
+    for child in field.children_mut() {
+        visitor.visit_field_mut(child);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{walk_children, Visitor, VisitorMut};
+    use crate::ast::Field;
+    use crate::compiler::IdlCompiler;
+
+    const SRC: &str = "protocol Event {
+  enum Meal { Dinner, Lunch }
+  record Person {
+    string name;
+    union { null, Meal } meal = null;
+    array<Person> friends;
+  }
+}";
+
+    #[test]
+    fn test_visitor() {
+        /// Collects the names of the records and the types they reference
+        #[derive(Default)]
+        struct References(Vec<String>);
+
+        impl Visitor for References {
+            fn visit_record(&mut self, record: &Field) {
+                self.0.push(record.name().unwrap());
+                walk_children(self, record)
+            }
+
+            fn visit_reference(&mut self, reference: &Field) {
+                self.0.push(reference.get_avro_type_name().unwrap());
+            }
+        }
+
+        let protocol = IdlCompiler::new().compile(SRC).unwrap();
+        let mut references = References::default();
+        references.visit_field(&protocol);
+        assert_eq!(references.0, vec!["Person", "Meal", "Person"]);
+    }
+
+    #[test]
+    fn test_visitor_mut() {
+        struct Rename;
+
+        impl VisitorMut for Rename {
+            fn visit_reference_mut(&mut self, reference: &mut Field) {
+                if let Field::RecordReference(_, type_, ..) | Field::EnumReference(_, type_, ..) =
+                    reference
+                {
+                    *type_ = format!("org.example.{}", type_);
+                }
+            }
+        }
+
+        let mut protocol = IdlCompiler::new().compile(SRC).unwrap();
+        Rename.visit_field_mut(&mut protocol);
+
+        #[derive(Default)]
+        struct Types(Vec<String>);
+
+        impl Visitor for Types {
+            fn visit_reference(&mut self, reference: &Field) {
+                self.0.push(reference.get_avro_type_name().unwrap());
+            }
+        }
+
+        let mut types = Types::default();
+        types.visit_field(&protocol);
+        assert_eq!(types.0, vec!["org.example.Meal", "org.example.Person"]);
+    }
+}